@@ -0,0 +1,9 @@
+//! The terminal frontend on its own. Built without the `window` feature it leaves out Bevy's
+//! windowing, audio and gamepad support:
+//!
+//! cargo install --path . --no-default-features --features tui
+
+fn main() {
+    #[cfg(not(target_arch = "wasm32"))]
+    rust_roguelike::build_app(rust_roguelike::Frontend::Terminal).run();
+}
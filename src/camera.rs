@@ -7,7 +7,7 @@ use crate::player::Player;
 pub const SCREEN_WIDTH: i32 = 80;
 pub const SCREEN_HEIGHT: i32 = 43;
 
-#[derive(Resource, Default)]
+#[derive(Resource, Default, PartialEq)]
 pub struct Camera {
     pub x: i32,
     pub y: i32,
@@ -54,7 +54,9 @@ impl Plugin for CameraPlugin {
 
 fn update_camera(player_query: Query<&Position, With<Player>>, mut camera: ResMut<Camera>) {
     if let Ok(player_pos) = player_query.get_single() {
-        camera.x = player_pos.x;
-        camera.y = player_pos.y;
+        camera.set_if_neq(Camera {
+            x: player_pos.x,
+            y: player_pos.y,
+        });
     }
 }
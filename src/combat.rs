@@ -2,10 +2,11 @@ use bevy::prelude::*;
 
 use crate::components::{DefenseBonus, Equipped, HungerClock, HungerState, MeleePowerBonus, Name};
 use crate::debug::GodMode;
-use crate::gamelog::GameLog;
+use crate::effects::{EffectEvent, Targets};
+use crate::gamelog::{GameLog, LogCategory, DAMAGE_COLOR};
 use crate::map::{Map, Position};
-use crate::particle::ParticleBuilder;
 use crate::player::Player;
+use crate::run_stats::RunStats;
 use crate::saveload;
 use crate::RunState;
 
@@ -38,19 +39,19 @@ impl SufferDamage {
 pub fn melee_combat_system(
     mut commands: Commands,
     mut log: ResMut<GameLog>,
-    mut particle_builder: ResMut<ParticleBuilder>,
-    query: Query<(Entity, &WantsToMelee, &Name, &CombatStats)>,
-    targets: Query<(&Name, &CombatStats, &Position)>,
+    mut effects: EventWriter<EffectEvent>,
+    query: Query<(Entity, &WantsToMelee, &Name, &CombatStats, &TextColor)>,
+    targets: Query<(&Name, &CombatStats, &TextColor)>,
     melee_bonus_query: Query<(&Equipped, &MeleePowerBonus)>,
     defense_bonus_query: Query<(&Equipped, &DefenseBonus)>,
     hunger_query: Query<&HungerClock>,
 ) {
-    for (entity, wants_melee, name, stats) in &query {
+    for (entity, wants_melee, name, stats, color) in &query {
         if stats.hp <= 0 {
             continue;
         }
 
-        if let Ok((target_name, target_stats, target_pos)) = targets.get(wants_melee.target) {
+        if let Ok((target_name, target_stats, target_color)) = targets.get(wants_melee.target) {
             if target_stats.hp <= 0 {
                 continue;
             }
@@ -84,25 +85,22 @@ pub fn melee_combat_system(
             );
 
             if damage == 0 {
-                log.entries.push(format!(
-                    "{} is unable to hurt {}",
-                    name.name, target_name.name
-                ));
+                log.entry(LogCategory::Combat)
+                    .colored(&name.name, color.0)
+                    .text(" is unable to hurt ")
+                    .colored(&target_name.name, target_color.0)
+                    .log();
             } else {
-                log.entries.push(format!(
-                    "{} hits {} for {} hp",
-                    name.name, target_name.name, damage
-                ));
-                SufferDamage::new_damage(&mut commands, wants_melee.target, damage);
-
-                // Spawn hit particle
-                particle_builder.request(
-                    target_pos.x,
-                    target_pos.y,
+                let target = Targets::Single(wants_melee.target);
+                effects.send(
+                    EffectEvent::damage(damage, target.clone())
+                        .with_source(entity, &name.name, color.0),
+                );
+                effects.send(EffectEvent::particle(
                     "!",
                     Color::srgb(1.0, 0.5, 0.0), // Orange
-                    200.0,
-                );
+                    target,
+                ));
             }
         }
 
@@ -114,6 +112,7 @@ pub fn damage_system(
     mut commands: Commands,
     god_mode: Res<GodMode>,
     mut map: ResMut<Map>,
+    mut run_stats: ResMut<RunStats>,
     mut query: Query<(Entity, &mut CombatStats, &SufferDamage)>,
     position_query: Query<&Position>,
     player_query: Query<Entity, With<Player>>,
@@ -126,7 +125,13 @@ pub fn damage_system(
             commands.entity(entity).remove::<SufferDamage>();
             continue;
         }
-        stats.hp -= damage.amount.iter().sum::<i32>();
+        let total: i32 = damage.amount.iter().sum();
+        stats.hp -= total;
+
+        // Damage dealt is credited by source when the effect lands
+        if Some(entity) == player {
+            run_stats.damage_taken += total;
+        }
 
         // Mark tile as bloody
         if let Ok(pos) = position_query.get(entity) {
@@ -142,19 +147,32 @@ pub fn delete_the_dead(
     mut commands: Commands,
     mut log: ResMut<GameLog>,
     mut next_state: ResMut<NextState<RunState>>,
-    query: Query<(Entity, &CombatStats, &Name), Without<Player>>,
+    mut run_stats: ResMut<RunStats>,
+    query: Query<(Entity, &CombatStats, &Name, &TextColor), Without<Player>>,
     player_query: Query<&CombatStats, With<Player>>,
 ) {
-    for (entity, stats, name) in &query {
+    for (entity, stats, name, color) in &query {
         if stats.hp <= 0 {
-            log.entries.push(format!("{} is dead", name.name));
-            commands.entity(entity).despawn();
+            log.entry(LogCategory::Combat)
+                .colored(&name.name, color.0)
+                .text(" is dead")
+                .log();
+            run_stats.record_kill(&name.name);
+            commands.entity(entity).despawn_recursive();
         }
     }
 
     if let Ok(player_stats) = player_query.get_single() {
         if player_stats.hp <= 0 {
-            log.entries.push("You are dead".to_string());
+            log.entry(LogCategory::Combat)
+                .colored("You are dead", DAMAGE_COLOR)
+                .log();
+            run_stats.cause_of_death = Some(
+                run_stats
+                    .last_damage_source
+                    .clone()
+                    .unwrap_or_else(|| "Died of unknown causes".to_string()),
+            );
             // Delete save file on death (permadeath)
             saveload::delete_save_file();
             // Transition to game over screen
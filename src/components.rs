@@ -89,6 +89,10 @@ pub struct InBackpack {
     pub owner: Entity,
 }
 
+/// Spending the turn looking for hidden things nearby
+#[derive(Component, Debug)]
+pub struct WantsToSearch;
+
 #[derive(Component, Debug)]
 pub struct WantsToPickupItem {
     pub collected_by: Entity,
@@ -172,10 +176,46 @@ pub struct EntryTrigger;
 pub struct SingleActivation;
 
 // Door system
-#[derive(Component, Debug)]
+#[derive(Component, Debug, Default)]
 pub struct Door {
     pub open: bool,
+    /// ID of the key that fits its lock, if it has one
+    pub lock: Option<u32>,
+    pub locked: bool,
+    /// A portcullis, raised and lowered by the levers with this ID instead of by hand
+    pub gate: Option<u32>,
+}
+
+/// Unlocks the door with the same ID
+#[derive(Component, Debug)]
+pub struct Key {
+    pub lock: u32,
+}
+
+/// Mounted on a wall; pulling it raises or lowers the portcullises with the same ID
+#[derive(Component, Debug)]
+pub struct Lever {
+    pub gate: u32,
+    pub pulled: bool,
 }
 
+/// Has the hands to open doors that aren't locked
+#[derive(Component, Debug)]
+pub struct OpensDoors;
+
+/// A door that passes for wall until it is found
+#[derive(Component, Debug)]
+pub struct SecretDoor;
+
 #[derive(Component, Debug)]
 pub struct BlocksVisibility;
+
+// Townsfolk: they stand in the way and talk, but never fight
+#[derive(Component, Debug)]
+pub struct Npc {
+    pub greeting: &'static str,
+}
+
+// Victory artifact
+#[derive(Component, Debug)]
+pub struct HeartOfTheAbyss;
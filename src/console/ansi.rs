@@ -0,0 +1,102 @@
+use std::io::Write;
+
+use bevy::prelude::*;
+
+use super::{Cell, Console, ConsoleBackend};
+
+/// Presents a console on a real terminal with ANSI escape codes and 24-bit color. Only the cells
+/// that changed since the last frame are sent, which keeps it usable over SSH.
+pub struct AnsiBackend<W: Write> {
+    out: W,
+    last: Vec<Cell>,
+}
+
+impl<W: Write> AnsiBackend<W> {
+    pub fn new(out: W) -> Self {
+        Self {
+            out,
+            last: Vec::new(),
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+/// A color as 8-bit RGB, faded towards `under` by its alpha
+fn rgb(color: Color, under: Color) -> (u8, u8, u8) {
+    let c = color.to_srgba();
+    let u = under.to_srgba();
+    let mix = |top: f32, bottom: f32| {
+        let value = top * c.alpha + bottom * (1.0 - c.alpha);
+        (value.clamp(0.0, 1.0) * 255.0).round() as u8
+    };
+    (mix(c.red, u.red), mix(c.green, u.green), mix(c.blue, u.blue))
+}
+
+impl<W: Write> ConsoleBackend for AnsiBackend<W> {
+    fn present(&mut self, console: &Console) -> std::io::Result<()> {
+        let size = (console.width * console.height) as usize;
+        let full_redraw = self.last.len() != size;
+        if full_redraw {
+            self.last = vec![Cell::default(); size];
+        }
+
+        let mut colors = None;
+        let mut cursor = None;
+        for y in 0..console.height {
+            for x in 0..console.width {
+                let idx = (y * console.width + x) as usize;
+                let cell = console.composite(x, y);
+                if !full_redraw && self.last[idx] == cell {
+                    continue;
+                }
+                self.last[idx] = cell;
+
+                if cursor != Some((x, y)) {
+                    write!(self.out, "\x1b[{};{}H", y + 1, x + 1)?;
+                }
+                let bg = rgb(cell.bg, Color::BLACK);
+                let fg = rgb(cell.fg, cell.bg);
+                if colors != Some((fg, bg)) {
+                    write!(
+                        self.out,
+                        "\x1b[38;2;{};{};{}m\x1b[48;2;{};{};{}m",
+                        fg.0, fg.1, fg.2, bg.0, bg.1, bg.2
+                    )?;
+                    colors = Some((fg, bg));
+                }
+                write!(self.out, "{}", cell.glyph)?;
+                cursor = Some((x + 1, y));
+            }
+        }
+
+        write!(self.out, "\x1b[0m")?;
+        self.out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::console::Layer;
+
+    #[test]
+    fn test_only_changed_cells_are_resent() {
+        let mut console = Console::new(3, 1);
+        console.print(Layer::Map, 0, 0, "...", Color::WHITE, Color::BLACK);
+        let mut backend = AnsiBackend::new(Vec::new());
+        backend.present(&console).unwrap();
+
+        console.set(Layer::Entities, 2, 0, Cell::new('@', Color::WHITE));
+        let first_frame = backend.out.len();
+        backend.present(&console).unwrap();
+
+        let second = String::from_utf8(backend.into_inner()[first_frame..].to_vec()).unwrap();
+        assert_eq!(
+            second,
+            "\x1b[1;3H\x1b[38;2;255;255;255m\x1b[48;2;0;0;0m@\x1b[0m"
+        );
+    }
+}
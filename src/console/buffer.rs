@@ -0,0 +1,73 @@
+use super::{Cell, Console, ConsoleBackend};
+
+/// Keeps the last presented frame in memory, for snapshot tests and anything else that wants
+/// to read the screen back
+#[derive(Default)]
+pub struct BufferBackend {
+    width: i32,
+    cells: Vec<Cell>,
+}
+
+impl BufferBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[cfg(test)]
+    pub fn cell(&self, x: i32, y: i32) -> Option<Cell> {
+        if x < 0 || x >= self.width {
+            return None;
+        }
+        self.cells.get((y * self.width + x) as usize).copied()
+    }
+
+    /// The glyphs of the frame, one line per row with trailing blanks trimmed
+    pub fn text(&self) -> String {
+        if self.width == 0 {
+            return String::new();
+        }
+        self.cells
+            .chunks(self.width as usize)
+            .map(|row| {
+                let line: String = row.iter().map(|cell| cell.glyph).collect();
+                line.trim_end().to_string()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl ConsoleBackend for BufferBackend {
+    fn present(&mut self, console: &Console) -> std::io::Result<()> {
+        self.width = console.width;
+        self.cells = (0..console.height)
+            .flat_map(|y| (0..console.width).map(move |x| (x, y)))
+            .map(|(x, y)| console.composite(x, y))
+            .collect();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::*;
+    use crate::console::Layer;
+
+    #[test]
+    fn test_buffer_snapshot() {
+        let mut console = Console::new(6, 3);
+        console.print(Layer::Map, 0, 0, "######", Color::WHITE, Color::BLACK);
+        console.print(Layer::Map, 0, 1, "#....#", Color::WHITE, Color::BLACK);
+        console.print(Layer::Map, 0, 2, "######", Color::WHITE, Color::BLACK);
+        console.set(Layer::Entities, 2, 1, Cell::new('@', Color::srgb(1.0, 1.0, 0.0)));
+        console.print(Layer::Ui, 4, 2, "HP", Color::WHITE, Color::NONE);
+
+        let mut buffer = BufferBackend::new();
+        buffer.present(&console).unwrap();
+
+        assert_eq!(buffer.text(), "######\n#.@..#\n####HP");
+        assert_eq!(buffer.cell(2, 1).unwrap().fg, Color::srgb(1.0, 1.0, 0.0));
+    }
+}
@@ -0,0 +1,279 @@
+mod ansi;
+mod buffer;
+
+use bevy::prelude::*;
+use bevy::text::Update2dText;
+
+use crate::camera::{Camera, SCREEN_WIDTH};
+use crate::components::RenderOrder;
+use crate::map::{glyph_cells, Position};
+use crate::particle::ParticleLifetime;
+
+pub use ansi::AnsiBackend;
+pub use buffer::BufferBackend;
+
+// ============================================================================
+// Console - A layered grid of glyph cells that backends present
+// ============================================================================
+
+/// One character cell: a glyph drawn in a foreground color over a background color
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cell {
+    pub glyph: char,
+    pub fg: Color,
+    pub bg: Color,
+}
+
+impl Cell {
+    pub fn new(glyph: char, fg: Color) -> Self {
+        Self {
+            glyph,
+            fg,
+            bg: Color::NONE,
+        }
+    }
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Self {
+            glyph: ' ',
+            fg: Color::NONE,
+            bg: Color::BLACK,
+        }
+    }
+}
+
+/// Layers in drawing order; cells on a later layer cover the ones below
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Layer {
+    Map,
+    Entities,
+    Particles,
+    Ui,
+}
+
+impl Layer {
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// The screen as a grid of cells: the map viewport on top and the HUD rows under it. Game code
+/// draws into its layers and a backend presents the composited result.
+#[derive(Resource, Clone)]
+pub struct Console {
+    pub width: i32,
+    pub height: i32,
+    layers: [Vec<Option<Cell>>; 4],
+}
+
+impl Default for Console {
+    fn default() -> Self {
+        Self::new(SCREEN_WIDTH, crate::SCREEN_HEIGHT as i32)
+    }
+}
+
+impl Console {
+    pub fn new(width: i32, height: i32) -> Self {
+        let size = (width * height) as usize;
+        Self {
+            width,
+            height,
+            layers: std::array::from_fn(|_| vec![None; size]),
+        }
+    }
+
+    fn idx(&self, x: i32, y: i32) -> Option<usize> {
+        (x >= 0 && x < self.width && y >= 0 && y < self.height).then(|| (y * self.width + x) as usize)
+    }
+
+    pub fn clear_layer(&mut self, layer: Layer) {
+        self.layers[layer.index()].fill(None);
+    }
+
+    /// Puts a cell on a layer; anything outside the grid is clipped
+    pub fn set(&mut self, layer: Layer, x: i32, y: i32, cell: Cell) {
+        if let Some(idx) = self.idx(x, y) {
+            self.layers[layer.index()][idx] = Some(cell);
+        }
+    }
+
+    pub fn unset(&mut self, layer: Layer, x: i32, y: i32) {
+        if let Some(idx) = self.idx(x, y) {
+            self.layers[layer.index()][idx] = None;
+        }
+    }
+
+    /// Writes a line of text left to right from (x, y)
+    #[cfg(test)]
+    pub fn print(&mut self, layer: Layer, x: i32, y: i32, text: &str, fg: Color, bg: Color) {
+        for (i, glyph) in text.chars().enumerate() {
+            self.set(layer, x + i as i32, y, Cell { glyph, fg, bg });
+        }
+    }
+
+    pub fn get(&self, layer: Layer, x: i32, y: i32) -> Option<Cell> {
+        self.idx(x, y).and_then(|idx| self.layers[layer.index()][idx])
+    }
+
+    /// What shows at (x, y) once the layers are stacked: the glyph of the topmost cell, over the
+    /// topmost background that isn't transparent
+    pub fn composite(&self, x: i32, y: i32) -> Cell {
+        let mut out = Cell::default();
+        let Some(idx) = self.idx(x, y) else {
+            return out;
+        };
+        for layer in &self.layers {
+            if let Some(cell) = layer[idx] {
+                out.glyph = cell.glyph;
+                out.fg = cell.fg;
+                if cell.bg.alpha() > 0.0 {
+                    out.bg = cell.bg;
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Something that can show a console: a window, a terminal, a buffer for tests
+pub trait ConsoleBackend {
+    fn present(&mut self, console: &Console) -> std::io::Result<()>;
+}
+
+// ============================================================================
+// Plugin - Fills the entity and particle layers each frame
+// ============================================================================
+
+/// Console work within a frame: every layer is drawn before any backend presents
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ConsoleSet {
+    Draw,
+    Present,
+}
+
+pub struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Console>()
+            .configure_sets(
+                PostUpdate,
+                (ConsoleSet::Draw, ConsoleSet::Present)
+                    .chain()
+                    .before(Update2dText)
+                    .before(TransformSystem::TransformPropagate),
+            )
+            .add_systems(
+                PostUpdate,
+                (draw_entity_layer, draw_particle_layer).in_set(ConsoleSet::Draw),
+            );
+    }
+}
+
+/// Everything the entity layer needs to draw one renderable
+type Renderable = (
+    &'static Position,
+    &'static Text2d,
+    &'static TextColor,
+    Option<&'static BackgroundColor>,
+    &'static RenderOrder,
+    &'static Visibility,
+);
+
+/// Renderables whose look or place changed since the entity layer was last drawn
+type RenderableChanged = (
+    With<RenderOrder>,
+    Or<(
+        Changed<Position>,
+        Changed<Text2d>,
+        Changed<TextColor>,
+        Changed<BackgroundColor>,
+        Changed<RenderOrder>,
+        Changed<Visibility>,
+    )>,
+);
+
+/// Every renderable that is currently visible, lowest render order first so the player ends up
+/// on top. Only redrawn when a renderable or the camera changes, so a still frame leaves the
+/// console alone.
+fn draw_entity_layer(
+    mut console: ResMut<Console>,
+    camera: Res<Camera>,
+    query: Query<Renderable>,
+    changed: Query<(), RenderableChanged>,
+    mut removed: RemovedComponents<RenderOrder>,
+) {
+    let removed = removed.read().count() > 0;
+    if !camera.is_changed() && changed.is_empty() && !removed {
+        return;
+    }
+    console.clear_layer(Layer::Entities);
+
+    let mut renderables: Vec<_> = query
+        .iter()
+        .filter(|(.., visibility)| **visibility != Visibility::Hidden)
+        .collect();
+    renderables.sort_by_key(|(.., order, _)| order.0);
+
+    for (pos, text, color, background, ..) in renderables {
+        let bg = background.map_or(Color::NONE, |bg| bg.0);
+        // Large monsters cover a tile per character of their glyph
+        for (wx, wy, glyph) in glyph_cells(*pos, &text.0) {
+            if !camera.in_bounds(wx, wy) {
+                continue;
+            }
+            let (x, y) = camera.world_to_screen(wx, wy);
+            console.set(Layer::Entities, x, y, Cell { glyph, fg: color.0, bg });
+        }
+    }
+}
+
+/// Particles live for a few frames, so the layer is redrawn while there are any and once more
+/// when the last goes
+fn draw_particle_layer(
+    mut console: ResMut<Console>,
+    camera: Res<Camera>,
+    query: Query<(&Position, &Text2d, &TextColor), With<ParticleLifetime>>,
+    mut removed: RemovedComponents<ParticleLifetime>,
+) {
+    let removed = removed.read().count() > 0;
+    if !camera.is_changed() && query.is_empty() && !removed {
+        return;
+    }
+    console.clear_layer(Layer::Particles);
+
+    for (pos, text, color) in &query {
+        if !camera.in_bounds(pos.x, pos.y) {
+            continue;
+        }
+        if let Some(glyph) = text.0.chars().next() {
+            let (x, y) = camera.world_to_screen(pos.x, pos.y);
+            console.set(Layer::Particles, x, y, Cell::new(glyph, color.0));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upper_layers_cover_lower_ones() {
+        let mut console = Console::new(4, 1);
+        console.print(Layer::Map, 0, 0, "....", Color::WHITE, Color::BLACK);
+        console.set(Layer::Entities, 1, 0, Cell::new('g', Color::srgb(1.0, 0.0, 0.0)));
+        console.set(Layer::Particles, 1, 0, Cell::new('*', Color::WHITE));
+        console.set(Layer::Particles, 9, 9, Cell::new('*', Color::WHITE));
+
+        let cell = console.composite(1, 0);
+        assert_eq!(cell.glyph, '*');
+        // The particle has no background of its own, so the map's shows through
+        assert_eq!(cell.bg, Color::BLACK);
+
+        console.clear_layer(Layer::Particles);
+        assert_eq!(console.composite(1, 0).glyph, 'g');
+        assert_eq!(console.composite(2, 0).glyph, '.');
+    }
+}
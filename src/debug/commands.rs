@@ -1,6 +1,13 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::{SystemTime, UNIX_EPOCH};
+
 use bevy::prelude::*;
 
 use crate::combat::CombatStats;
+use crate::console::{AnsiBackend, BufferBackend, Console, ConsoleBackend};
+use crate::effects::{EffectEvent, EffectType, Targets};
 use crate::map::{Position, MAP_HEIGHT, MAP_WIDTH};
 use crate::monsters::Monster;
 use crate::player::Player;
@@ -17,6 +24,8 @@ pub fn execute_command(
     font: &UiFont,
     player_query: &mut Query<(Entity, &mut Position, &mut CombatStats), With<Player>>,
     monster_query: &Query<Entity, With<Monster>>,
+    effects: &mut EventWriter<EffectEvent>,
+    console: &Console,
 ) -> String {
     let parts: Vec<&str> = input.trim().split_whitespace().collect();
     let cmd = parts.first().map(|s| s.to_lowercase());
@@ -28,15 +37,16 @@ pub fn execute_command(
         Some("nofog") => cmd_nofog(debug_state),
         Some("heal") => cmd_heal(player_query),
         Some("kill_all") => cmd_kill_all(commands, monster_query),
-        Some("teleport") => cmd_teleport(&parts, player_query),
+        Some("teleport") => cmd_teleport(&parts, player_query, effects),
         Some("spawn") => cmd_spawn(&parts, commands, font, player_query),
+        Some("screenshot") => cmd_screenshot(console),
         Some(cmd) => format!("Unknown command: {}", cmd),
         None => String::new(),
     }
 }
 
 fn cmd_help() -> String {
-    "Commands: spawn <item>, teleport <x> <y>, godmode, reveal, nofog, heal, kill_all".to_string()
+    "Commands: spawn <item>, teleport <x> <y>, godmode, reveal, nofog, heal, kill_all, screenshot".to_string()
 }
 
 fn cmd_godmode(god_mode: &mut GodMode) -> String {
@@ -68,6 +78,36 @@ fn cmd_heal(
     }
 }
 
+/// Saves the console as plain text and as ANSI (for `cat` in a terminal)
+#[cfg(not(target_arch = "wasm32"))]
+fn cmd_screenshot(console: &Console) -> String {
+    let mut buffer = BufferBackend::new();
+    let mut ansi = AnsiBackend::new(Vec::new());
+    if let Err(e) = buffer.present(console).and(ansi.present(console)) {
+        return format!("Screenshot failed: {}", e);
+    }
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let path = format!("screenshot-{}", timestamp);
+    let written = fs::write(format!("{}.txt", path), buffer.text())
+        .and(fs::write(format!("{}.ans", path), ansi.into_inner()));
+    match written {
+        Ok(()) => format!("Screenshot saved to {}.txt and {}.ans", path, path),
+        Err(e) => format!("Screenshot failed: {}", e),
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn cmd_screenshot(console: &Console) -> String {
+    let mut buffer = BufferBackend::new();
+    let _ = buffer.present(console);
+    info!("Screenshot:\n{}", buffer.text());
+    "Screenshot written to the browser console".to_string()
+}
+
 fn cmd_kill_all(commands: &mut Commands, monster_query: &Query<Entity, With<Monster>>) -> String {
     let count = monster_query.iter().count();
     for entity in monster_query.iter() {
@@ -79,6 +119,7 @@ fn cmd_kill_all(commands: &mut Commands, monster_query: &Query<Entity, With<Mons
 fn cmd_teleport(
     parts: &[&str],
     player_query: &mut Query<(Entity, &mut Position, &mut CombatStats), With<Player>>,
+    effects: &mut EventWriter<EffectEvent>,
 ) -> String {
     if parts.len() < 3 {
         return "Usage: teleport <x> <y>".to_string();
@@ -88,9 +129,11 @@ fn cmd_teleport(
     match (x, y) {
         (Ok(x), Ok(y)) => {
             if x >= 0 && x < MAP_WIDTH as i32 && y >= 0 && y < MAP_HEIGHT as i32 {
-                if let Ok((_, mut pos, _)) = player_query.get_single_mut() {
-                    pos.x = x;
-                    pos.y = y;
+                if let Ok((player, _, _)) = player_query.get_single() {
+                    effects.send(EffectEvent::new(
+                        EffectType::Teleport { x, y },
+                        Targets::Single(player),
+                    ));
                     format!("Teleported to ({}, {})", x, y)
                 } else {
                     "No player found".to_string()
@@ -166,6 +209,11 @@ fn cmd_spawn(
             crate::spawner::spawn_magic_mapping_scroll(commands, &text_font, x, y);
             format!("Spawned Magic Mapping Scroll at ({}, {})", x, y)
         }
+        "dragon" => {
+            // Out of the way of the player, who would otherwise stand inside it
+            crate::spawner::spawn_dragon(commands, &text_font, x + 2, y, 0);
+            format!("Spawned Dragon at ({}, {})", x + 2, y)
+        }
         _ => format!("Unknown item: {}", item_name),
     }
 }
@@ -3,6 +3,9 @@ use bevy::input::ButtonState;
 use bevy::prelude::*;
 
 use crate::combat::CombatStats;
+use crate::console::Console;
+use crate::effects::EffectEvent;
+use crate::gamelog::{GameLog, LogCategory};
 use crate::map::Position;
 use crate::monsters::Monster;
 use crate::player::Player;
@@ -110,7 +113,7 @@ pub fn update_console(
 
             // Help hint
             parent.spawn((
-                Text::new("Commands: spawn, teleport, godmode, reveal, nofog, heal, kill_all, help"),
+                Text::new("Commands: spawn, teleport, godmode, reveal, nofog, heal, kill_all, screenshot, help"),
                 TextFont {
                     font: font.0.clone(),
                     font_size: 11.0,
@@ -126,11 +129,14 @@ pub fn handle_console_input(
     mut debug: ResMut<DebugMode>,
     mut debug_state: ResMut<DebugState>,
     mut god_mode: ResMut<GodMode>,
+    mut gamelog: ResMut<GameLog>,
+    mut effects: EventWriter<EffectEvent>,
     mut evr_kbd: EventReader<KeyboardInput>,
     keyboard: Res<ButtonInput<KeyCode>>,
     font: Res<UiFont>,
     mut player_query: Query<(Entity, &mut Position, &mut CombatStats), With<Player>>,
     monster_query: Query<Entity, With<Monster>>,
+    console: Res<Console>,
 ) {
     if !debug.show_console {
         return;
@@ -204,7 +210,11 @@ pub fn handle_console_input(
                         &font,
                         &mut player_query,
                         &monster_query,
+                        &mut effects,
+                        &console,
                     );
+                    // Record debug commands in the run's log so morgue files show them
+                    gamelog.log(LogCategory::Debug, format!("> {}: {}", input, output));
                     debug_state.console_output.push(output);
 
                     debug_state.console_input.clear();
@@ -4,6 +4,7 @@ mod overlays;
 mod resources;
 
 use bevy::prelude::*;
+use crate::map_builders::RegionGraph;
 use crate::RunState;
 
 pub use resources::{DebugMode, DebugState, GodMode};
@@ -14,6 +15,7 @@ impl Plugin for DebugPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<DebugMode>()
             .init_resource::<DebugState>()
+            .init_resource::<RegionGraph>()
             .init_resource::<GodMode>()
             // Master toggle always runs (except during map generation)
             .add_systems(
@@ -26,6 +28,7 @@ impl Plugin for DebugPlugin {
                 (
                     overlays::toggle_debug_overlays,
                     overlays::update_fov_overlay,
+                    overlays::update_region_overlay,
                     overlays::update_tile_info_overlay,
                     overlays::update_state_inspector,
                     overlays::process_reveal_map,
@@ -2,7 +2,8 @@ use bevy::prelude::*;
 
 use crate::combat::CombatStats;
 use crate::components::{HungerClock, Item};
-use crate::map::{Map, Position, Revealed, RevealedState, Tile, TileType, GRID_PX, MAP_HEIGHT, MAP_WIDTH};
+use crate::map::{Map, Position, GRID_PX, MAP_HEIGHT, MAP_WIDTH};
+use crate::map_builders::{RegionGraph, RegionKind};
 use crate::monsters::Monster;
 use crate::player::Player;
 use crate::resources::UiFont;
@@ -18,6 +19,9 @@ use super::resources::{DebugMode, DebugState, GodMode};
 #[derive(Component)]
 pub struct FovOverlay;
 
+#[derive(Component)]
+pub struct RegionOverlay;
+
 #[derive(Component)]
 pub struct TileInfoOverlay;
 
@@ -43,6 +47,9 @@ pub fn toggle_debug_overlays(keyboard: Res<ButtonInput<KeyCode>>, mut debug: Res
     if keyboard.just_pressed(KeyCode::F1) {
         debug.show_fov_overlay = !debug.show_fov_overlay;
     }
+    if keyboard.just_pressed(KeyCode::F2) {
+        debug.show_regions = !debug.show_regions;
+    }
     if keyboard.just_pressed(KeyCode::F3) {
         debug.show_tile_info = !debug.show_tile_info;
     }
@@ -86,7 +93,7 @@ pub fn update_fov_overlay(
     let half_height = window.height() / 2.0;
 
     // Spawn overlay for each visible tile
-    for &(x, y) in &viewshed.visible_tiles {
+    for (x, y) in viewshed.visible_tiles.iter() {
         let screen_x = (x as f32) * GRID_PX.x + (GRID_PX.x / 2.0) - half_width;
         let screen_y = (y as f32) * -GRID_PX.y - (GRID_PX.y / 2.0) + half_height;
 
@@ -102,6 +109,116 @@ pub fn update_fov_overlay(
     }
 }
 
+// ============================================================================
+// Region Overlay (F2)
+// ============================================================================
+
+pub fn update_region_overlay(
+    mut commands: Commands,
+    debug: Res<DebugMode>,
+    graph: Res<RegionGraph>,
+    window: Query<&Window>,
+    font: Res<UiFont>,
+    overlay_query: Query<Entity, With<RegionOverlay>>,
+) {
+    // Despawn existing overlays
+    for entity in &overlay_query {
+        commands.entity(entity).despawn();
+    }
+
+    if !debug.show_regions {
+        return;
+    }
+
+    let Ok(window) = window.get_single() else {
+        return;
+    };
+
+    let half_width = window.width() / 2.0;
+    let half_height = window.height() / 2.0;
+    let screen_pos = |idx: usize| {
+        let (x, y) = ((idx % MAP_WIDTH) as f32, (idx / MAP_WIDTH) as f32);
+        (
+            x * GRID_PX.x + (GRID_PX.x / 2.0) - half_width,
+            y * -GRID_PX.y - (GRID_PX.y / 2.0) + half_height,
+        )
+    };
+    let tint = |idx: usize, color: Color, z: f32| {
+        let (screen_x, screen_y) = screen_pos(idx);
+        (
+            RegionOverlay,
+            Sprite {
+                color,
+                custom_size: Some(Vec2::new(GRID_PX.x, GRID_PX.y)),
+                ..default()
+            },
+            Transform::from_xyz(screen_x, screen_y, z),
+        )
+    };
+
+    // Each region in a color of its own, labelled in its middle with how many passages it is
+    // from the entrance; those on the way to the exit are labelled in yellow
+    let dead_ends = graph.dead_ends();
+    let critical_path = graph.critical_path();
+    let hops = graph
+        .entrance
+        .map(|entrance| graph.distances_from(entrance))
+        .unwrap_or_default();
+    for (i, region) in graph.regions.iter().enumerate() {
+        let hue = (i as f32 * 137.5) % 360.0;
+        for &idx in &region.tiles {
+            commands.spawn(tint(idx, Color::hsla(hue, 0.8, 0.5, 0.2), 5.0));
+        }
+
+        let kind = match region.kind {
+            RegionKind::Room => 'R',
+            RegionKind::Area => 'A',
+        };
+        let mut label = format!("{}{}", kind, i);
+        if let Some(Some(hops)) = hops.get(i) {
+            label.push_str(&format!(" ({})", hops));
+        }
+        if graph.entrance == Some(i) {
+            label.push_str(" in");
+        }
+        if graph.exit == Some(i) {
+            label.push_str(" out");
+        }
+        if dead_ends.contains(&i) {
+            label.push_str(" end");
+        }
+        let (screen_x, screen_y) = screen_pos(region.tiles[region.tiles.len() / 2]);
+        commands.spawn((
+            RegionOverlay,
+            Text2d::new(label),
+            TextFont {
+                font: font.0.clone(),
+                font_size: 12.0,
+                ..default()
+            },
+            TextColor(if critical_path.contains(&i) {
+                Color::srgb(1.0, 1.0, 0.0)
+            } else {
+                Color::WHITE
+            }),
+            Transform::from_xyz(screen_x, screen_y, 100.0),
+        ));
+    }
+
+    // Passages on top: choke points red, the rest yellow
+    let choke_points = graph.choke_points();
+    for (i, passage) in graph.passages.iter().enumerate() {
+        let color = if choke_points.contains(&i) {
+            Color::srgba(1.0, 0.0, 0.0, 0.4)
+        } else {
+            Color::srgba(1.0, 1.0, 0.0, 0.25)
+        };
+        for &idx in &passage.tiles {
+            commands.spawn(tint(idx, color, 6.0));
+        }
+    }
+}
+
 // ============================================================================
 // Tile Info Overlay (F3)
 // ============================================================================
@@ -114,7 +231,6 @@ pub fn update_tile_info_overlay(
     camera_query: Query<(&Camera, &GlobalTransform)>,
     font: Res<UiFont>,
     overlay_query: Query<Entity, With<TileInfoOverlay>>,
-    tile_query: Query<Entity, With<Tile>>,
 ) {
     // Despawn existing overlay
     for entity in &overlay_query {
@@ -151,18 +267,10 @@ pub fn update_tile_info_overlay(
     }
 
     let idx = map.xy_idx(grid_x, grid_y);
-    let tile_type = match map.tiles[idx] {
-        TileType::Floor => "Floor",
-        TileType::Wall => "Wall",
-        TileType::DownStairs => "Stairs",
-    };
+    let tile_type = map.tiles[idx].name();
     let blocked = map.blocked_tiles[idx];
 
-    // Count entities excluding Tile entities
-    let entity_count = map.tile_content[idx]
-        .iter()
-        .filter(|e| tile_query.get(**e).is_err())
-        .count();
+    let entity_count = map.tile_content[idx].len();
 
     let info_text = format!(
         "({}, {}) {} B:{} E:{}",
@@ -307,7 +415,7 @@ pub fn update_state_inspector(
 
             // Key hints
             parent.spawn((
-                Text::new("F1:FOV F3:Tile F4:This `:Console"),
+                Text::new("F1:FOV F2:Regions F3:Tile F4:This `:Console"),
                 TextFont {
                     font: font.0.clone(),
                     font_size: 12.0,
@@ -322,11 +430,7 @@ pub fn update_state_inspector(
 // Map Reveal System
 // ============================================================================
 
-pub fn process_reveal_map(
-    mut debug_state: ResMut<DebugState>,
-    mut map: ResMut<Map>,
-    mut tile_query: Query<(&Position, &mut Revealed), With<Tile>>,
-) {
+pub fn process_reveal_map(mut debug_state: ResMut<DebugState>, mut map: ResMut<Map>) {
     if !debug_state.reveal_map {
         return;
     }
@@ -336,14 +440,6 @@ pub fn process_reveal_map(
         map.revealed_tiles[i] = true;
     }
 
-    // Update all Tile entities to show as explored
-    for (pos, mut revealed) in &mut tile_query {
-        let idx = map.xy_idx(pos.x, pos.y);
-        if map.revealed_tiles[idx] {
-            *revealed = Revealed(RevealedState::Explored);
-        }
-    }
-
     // Reset the flag
     debug_state.reveal_map = false;
 }
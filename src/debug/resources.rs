@@ -4,6 +4,7 @@ use bevy::prelude::*;
 pub struct DebugMode {
     pub enabled: bool,
     pub show_fov_overlay: bool,
+    pub show_regions: bool,
     pub show_tile_info: bool,
     pub show_inspector: bool,
     pub show_console: bool,
@@ -14,6 +15,7 @@ impl Default for DebugMode {
         Self {
             enabled: true,
             show_fov_overlay: false,
+            show_regions: false,
             show_tile_info: false,
             show_inspector: false,
             show_console: false,
@@ -1,6 +1,8 @@
 use bevy::prelude::*;
+use serde::Deserialize;
 
 /// Distance calculation algorithms
+#[derive(Clone, Copy, Debug, Deserialize)]
 pub enum DistanceAlg {
     /// Straight-line distance: sqrt((x2-x1)² + (y2-y1)²)
     /// Best for: circular ranges, spell AOE.
@@ -0,0 +1,275 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::combat::CombatStats;
+use crate::components::{BlocksTile, BlocksVisibility, Door, Hidden, InBackpack, Item, Key, Name};
+use crate::gamelog::{GameLog, LogCategory};
+use crate::map::{Map, Position, TileType};
+use crate::player::{direction_of, held_key, Player};
+use crate::rng::GameRng;
+use crate::spawner;
+use crate::RunState;
+
+/// Something the player does to a door next to them, once they pick which way
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DoorCommand {
+    #[default]
+    Close,
+    Bash,
+    Lock,
+}
+
+impl DoorCommand {
+    pub fn verb(self) -> &'static str {
+        match self {
+            DoorCommand::Close => "Close",
+            DoorCommand::Bash => "Bash",
+            DoorCommand::Lock => "Lock",
+        }
+    }
+}
+
+pub struct DoorsPlugin;
+
+impl Plugin for DoorsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DoorCommand>().add_systems(
+            Update,
+            handle_door_command.run_if(in_state(RunState::ChooseDirection)),
+        );
+    }
+}
+
+/// Whether something can get through a door: it is open, or it can be opened by hand and
+/// whoever is going through has hands
+pub fn passable(door: &Door, opens_doors: bool) -> bool {
+    door.open || (opens_doors && !door.locked && door.gate.is_none())
+}
+
+/// Opens or shuts a door, changing how it looks and what it blocks. Portcullises never block
+/// the view.
+pub fn set_open(
+    commands: &mut Commands,
+    entity: Entity,
+    door: &mut Door,
+    glyph: &mut Text2d,
+    open: bool,
+) {
+    door.open = open;
+    glyph.0 = spawner::door_glyph(door).to_string();
+    if open {
+        commands
+            .entity(entity)
+            .remove::<(BlocksTile, BlocksVisibility)>();
+    } else if door.gate.is_some() {
+        commands.entity(entity).insert(BlocksTile);
+    } else {
+        commands
+            .entity(entity)
+            .insert((BlocksTile, BlocksVisibility));
+    }
+}
+
+/// Whether a doorway has nothing in it but the door, so the door can swing shut
+pub fn doorway_clear(
+    map: &Map,
+    idx: usize,
+    door: Entity,
+    creatures: &Query<(), With<BlocksTile>>,
+    items: &Query<(), With<Item>>,
+) -> bool {
+    !map.tile_content[idx]
+        .iter()
+        .any(|&e| e != door && (creatures.contains(e) || items.contains(e)))
+}
+
+/// Carries out the door command the player picked, in the direction they pick. Only doing
+/// something to a door takes a turn.
+fn handle_door_command(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    command: Res<DoorCommand>,
+    mut map: ResMut<Map>,
+    mut rng: ResMut<GameRng>,
+    mut gamelog: ResMut<GameLog>,
+    mut next_state: ResMut<NextState<RunState>>,
+    player: Single<(Entity, &Position, &CombatStats), With<Player>>,
+    mut doors: Query<(Entity, &mut Door, &mut Text2d)>,
+    hidden: Query<(), With<Hidden>>,
+    keys: Query<(&Key, &Name, &InBackpack)>,
+    creatures: Query<(), With<BlocksTile>>,
+    items: Query<(), With<Item>>,
+) {
+    if keyboard.just_pressed(KeyCode::Escape) {
+        gamelog.log(LogCategory::System, "Never mind.");
+        next_state.set(RunState::AwaitingInput);
+        return;
+    }
+    // Pressed this frame, so the key that asked for the direction isn't taken as one
+    let Some((dx, dy)) = keyboard.get_just_pressed().find_map(direction_of) else {
+        return;
+    };
+
+    let (player_entity, pos, stats) = player.into_inner();
+    let idx = map.xy_idx(pos.x + dx, pos.y + dy);
+    // Secret doors stay hidden, and the map has wall there, until they are found
+    let door = map.tile_content[idx]
+        .iter()
+        .find(|&&e| doors.contains(e) && !hidden.contains(e))
+        .copied()
+        .filter(|_| map.tiles[idx].is_walkable());
+    let Some((door_entity, mut door, mut glyph)) = door.and_then(|e| doors.get_mut(e).ok()) else {
+        gamelog.log(LogCategory::System, "There is no door there.");
+        next_state.set(RunState::AwaitingInput);
+        return;
+    };
+
+    let acted = match *command {
+        DoorCommand::Close if !door.open => {
+            gamelog.log(LogCategory::System, "That door is already shut.");
+            false
+        }
+        DoorCommand::Close if door.gate.is_some() => {
+            gamelog.log(
+                LogCategory::System,
+                "Only a lever will move the portcullis.",
+            );
+            false
+        }
+        DoorCommand::Close if !doorway_clear(&map, idx, door_entity, &creatures, &items) => {
+            gamelog.log(LogCategory::System, "Something is in the way.");
+            false
+        }
+        DoorCommand::Close => {
+            set_open(&mut commands, door_entity, &mut door, &mut glyph, false);
+            gamelog.log(LogCategory::System, "You close the door.");
+            true
+        }
+        DoorCommand::Lock if door.open => {
+            gamelog.log(LogCategory::System, "You will have to close it first.");
+            false
+        }
+        DoorCommand::Lock if door.locked => {
+            gamelog.log(LogCategory::System, "It is already locked.");
+            false
+        }
+        DoorCommand::Lock => match door
+            .lock
+            .and_then(|lock| held_key(&keys, player_entity, lock))
+        {
+            Some(key_name) => {
+                door.locked = true;
+                gamelog.log(
+                    LogCategory::System,
+                    format!("You lock the door with the {}.", key_name),
+                );
+                true
+            }
+            None => {
+                gamelog.log(LogCategory::System, "You have no key that fits this door.");
+                false
+            }
+        },
+        DoorCommand::Bash if door.open => {
+            gamelog.log(LogCategory::System, "It is already open.");
+            false
+        }
+        DoorCommand::Bash if door.gate.is_some() => {
+            gamelog.log(
+                LogCategory::System,
+                "The portcullis is far too strong to bash.",
+            );
+            false
+        }
+        DoorCommand::Bash => {
+            // The stronger the blow, the likelier the door gives way
+            if rng.0.gen_range(0..20) < stats.power {
+                commands.entity(door_entity).despawn_recursive();
+                if map.tiles[idx] != TileType::Floor {
+                    map.tiles[idx] = TileType::Floor;
+                }
+                gamelog.log(LogCategory::System, "You smash the door to splinters!");
+            } else {
+                gamelog.log(LogCategory::System, "You slam into the door, but it holds.");
+            }
+            true
+        }
+    };
+
+    next_state.set(if acted {
+        RunState::PlayerTurn
+    } else {
+        RunState::AwaitingInput
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_hands_open_doors_and_nothing_opens_locks_by_hand() {
+        let door = Door::default();
+        let locked = Door {
+            lock: Some(0),
+            locked: true,
+            ..default()
+        };
+        let gate = Door {
+            gate: Some(0),
+            ..default()
+        };
+        assert!(passable(&door, true));
+        assert!(!passable(&door, false));
+        assert!(!passable(&locked, true));
+        assert!(!passable(&gate, true));
+        assert!(passable(&Door { open: true, ..gate }, false));
+    }
+
+    #[test]
+    fn test_secret_doors_cant_be_picked_until_found() {
+        use crate::combat::CombatStats;
+        use crate::components::SecretDoor;
+        use bevy::ecs::system::RunSystemOnce;
+
+        let mut world = World::new();
+        let mut map = Map::default();
+        let start = map.xy_idx(10, 10);
+        let doorway = map.xy_idx(11, 10);
+        map.tiles[start] = TileType::Floor;
+        world.spawn((
+            Player,
+            Position { x: 10, y: 10 },
+            CombatStats {
+                max_hp: 30,
+                hp: 30,
+                defense: 2,
+                power: 20,
+            },
+        ));
+        let door = world
+            .spawn((Door::default(), Text2d::new("#"), SecretDoor, Hidden))
+            .id();
+        map.tile_content[doorway].push(door);
+        world.insert_resource(map);
+        world.insert_resource(DoorCommand::Bash);
+        world.insert_resource(GameRng::seeded(1));
+        world.init_resource::<GameLog>();
+        world.init_resource::<NextState<RunState>>();
+        let mut keyboard = ButtonInput::<KeyCode>::default();
+        keyboard.press(KeyCode::ArrowRight);
+        world.insert_resource(keyboard);
+
+        world.run_system_once(handle_door_command).unwrap();
+        assert!(world.get_entity(door).is_ok());
+        let said = |world: &World| world.resource::<GameLog>().entries.back().unwrap().text();
+        assert_eq!(said(&world), "There is no door there.");
+
+        // Once found it can be smashed, and leaves floor behind
+        world.entity_mut(door).remove::<(SecretDoor, Hidden)>();
+        world.resource_mut::<Map>().tiles[doorway] = TileType::WoodFloor;
+        world.run_system_once(handle_door_command).unwrap();
+        assert!(world.get_entity(door).is_err());
+        assert_eq!(world.resource::<Map>().tiles[doorway], TileType::Floor);
+    }
+}
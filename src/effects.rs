@@ -0,0 +1,345 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+
+use crate::combat::{CombatStats, SufferDamage};
+use crate::components::{Confusion, HungerClock, HungerState, Name};
+use crate::distance::DistanceAlg;
+use crate::gamelog::{GameLog, LogCategory};
+use crate::lighting::LightSource;
+use crate::map::{Map, Position};
+use crate::particle::ParticleBuilder;
+use crate::player::Player;
+use crate::run_stats::{monster_type, RunStats};
+use crate::viewshed::Viewshed;
+use crate::PendingMagicMap;
+
+/// What an effect does to each entity or tile it reaches
+#[derive(Clone, Debug)]
+pub enum EffectType {
+    Damage {
+        amount: i32,
+    },
+    Heal {
+        amount: i32,
+    },
+    ApplyStatus(Status),
+    Teleport {
+        x: i32,
+        y: i32,
+    },
+    RevealMap,
+    Particle {
+        glyph: String,
+        color: Color,
+        lifetime_ms: f32,
+    },
+    /// A brief burst of light, e.g. a spell going off
+    Flash {
+        radius: i32,
+        color: Color,
+    },
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Status {
+    Confusion { turns: i32 },
+    WellFed,
+}
+
+/// Where an effect lands
+#[derive(Clone, Debug)]
+pub enum Targets {
+    Single(Entity),
+    Tile { x: i32, y: i32 },
+    Tiles(Vec<(i32, i32)>),
+}
+
+/// Whoever or whatever caused an effect, as it should be named in the log
+#[derive(Clone, Debug)]
+pub struct EffectSource {
+    /// The attacker, trap, or whoever used the item
+    pub entity: Entity,
+    pub name: String,
+    pub color: Color,
+}
+
+/// A queued gameplay effect. Items, traps and AI send these instead of changing state directly.
+#[derive(Event, Clone, Debug)]
+pub struct EffectEvent {
+    pub effect: EffectType,
+    pub targets: Targets,
+    pub source: Option<EffectSource>,
+}
+
+impl EffectEvent {
+    pub fn new(effect: EffectType, targets: Targets) -> Self {
+        Self {
+            effect,
+            targets,
+            source: None,
+        }
+    }
+
+    /// Name the cause of the effect; damage from a named source is logged
+    pub fn with_source(mut self, entity: Entity, name: impl Into<String>, color: Color) -> Self {
+        self.source = Some(EffectSource {
+            entity,
+            name: name.into(),
+            color,
+        });
+        self
+    }
+
+    pub fn damage(amount: i32, targets: Targets) -> Self {
+        Self::new(EffectType::Damage { amount }, targets)
+    }
+
+    pub fn particle(glyph: &str, color: Color, targets: Targets) -> Self {
+        Self::new(
+            EffectType::Particle {
+                glyph: glyph.to_string(),
+                color,
+                lifetime_ms: 200.0,
+            },
+            targets,
+        )
+    }
+}
+
+impl Targets {
+    /// Every tile within `radius` (Euclidean) of the center
+    pub fn area(x: i32, y: i32, radius: i32) -> Self {
+        let mut tiles = Vec::new();
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                let distance =
+                    DistanceAlg::Euclidean.distance2d(Vec2::ZERO, Vec2::new(dx as f32, dy as f32));
+                if distance <= radius as f32 {
+                    tiles.push((x + dx, y + dy));
+                }
+            }
+        }
+        Targets::Tiles(tiles)
+    }
+
+    /// The map tiles covered by these targets (empty for a single entity)
+    pub fn tiles(&self, map: &Map) -> Vec<(i32, i32)> {
+        let in_bounds = |&(x, y): &(i32, i32)| x >= 0 && x < map.width && y >= 0 && y < map.height;
+        match self {
+            Targets::Single(_) => Vec::new(),
+            Targets::Tile { x, y } => vec![(*x, *y)].into_iter().filter(in_bounds).collect(),
+            Targets::Tiles(tiles) => tiles.iter().copied().filter(in_bounds).collect(),
+        }
+    }
+}
+
+/// Applies every queued effect. Events are drained, so the system can be scheduled in several
+/// places (each turn phase, plus a catch-all for effects sent outside a turn) without
+/// applying anything twice.
+pub fn effects_system(
+    mut commands: Commands,
+    mut events: ResMut<Events<EffectEvent>>,
+    map: Res<Map>,
+    mut gamelog: ResMut<GameLog>,
+    mut particle_builder: ResMut<ParticleBuilder>,
+    mut run_stats: ResMut<RunStats>,
+    mut pending_magic_map: ResMut<PendingMagicMap>,
+    mut position_query: Query<&mut Position>,
+    mut stats_query: Query<(&Name, &TextColor, &mut CombatStats)>,
+    mut hunger_query: Query<&mut HungerClock>,
+    mut viewshed_query: Query<&mut Viewshed>,
+    player_query: Query<Entity, With<Player>>,
+) {
+    let player = player_query.get_single().ok();
+
+    for event in events.drain() {
+        let tiles = event.targets.tiles(&map);
+
+        // Effects that play out on the map rather than on entities
+        match &event.effect {
+            EffectType::Particle {
+                glyph,
+                color,
+                lifetime_ms,
+            } => {
+                let mut spots = tiles.clone();
+                if let Targets::Single(entity) = event.targets {
+                    if let Ok(pos) = position_query.get(entity) {
+                        spots.push((pos.x, pos.y));
+                    }
+                }
+                for (x, y) in spots {
+                    particle_builder.request(x, y, glyph, *color, *lifetime_ms);
+                }
+                continue;
+            }
+            EffectType::Flash { radius, color } => {
+                let light = LightSource {
+                    radius: *radius,
+                    color: *color,
+                    falloff: 1.0,
+                };
+                for (x, y) in tiles {
+                    particle_builder.flash(x, y, light, 300.0);
+                }
+                continue;
+            }
+            EffectType::RevealMap => {
+                pending_magic_map.0 = true;
+                continue;
+            }
+            _ => {}
+        }
+
+        // Everything else affects the entities standing in the target area, once each however
+        // many of the tiles a large one covers
+        let entities: Vec<Entity> = match event.targets {
+            Targets::Single(entity) => vec![entity],
+            _ => {
+                let mut seen = HashSet::new();
+                tiles
+                    .iter()
+                    .flat_map(|&(x, y)| map.tile_content[map.xy_idx(x, y)].iter().copied())
+                    .filter(|&entity| seen.insert(entity))
+                    .collect()
+            }
+        };
+
+        let mut affected = 0;
+        for target in entities {
+            match &event.effect {
+                EffectType::Damage { amount } => {
+                    let Ok((name, color, stats)) = stats_query.get(target) else {
+                        continue;
+                    };
+                    if stats.hp <= 0 {
+                        continue;
+                    }
+                    SufferDamage::new_damage(&mut commands, target, *amount);
+                    affected += 1;
+
+                    if let Some(source) = &event.source {
+                        gamelog
+                            .entry(LogCategory::Combat)
+                            .colored(&source.name, source.color)
+                            .text(" hits ")
+                            .colored(&name.name, color.0)
+                            .text(" for ")
+                            .damage(*amount)
+                            .text(" hp.")
+                            .log();
+                        if Some(target) == player {
+                            run_stats.last_damage_source =
+                                Some(format!("Killed by {}", monster_type(&source.name)));
+                        } else if Some(source.entity) == player {
+                            run_stats.damage_dealt += amount;
+                        }
+                    }
+                }
+                EffectType::Heal { amount } => {
+                    if let Ok((_, _, mut stats)) = stats_query.get_mut(target) {
+                        stats.hp = (stats.hp + amount).min(stats.max_hp);
+                        affected += 1;
+                    }
+                }
+                EffectType::ApplyStatus(Status::Confusion { turns }) => {
+                    let Ok((name, color, _)) = stats_query.get(target) else {
+                        continue;
+                    };
+                    commands.entity(target).insert(Confusion { turns: *turns });
+                    gamelog
+                        .entry(LogCategory::Status)
+                        .colored(&name.name, color.0)
+                        .text(format!(" is confused for {} turns.", turns))
+                        .log();
+                    affected += 1;
+                }
+                EffectType::ApplyStatus(Status::WellFed) => {
+                    if let Ok(mut hunger) = hunger_query.get_mut(target) {
+                        hunger.state = HungerState::WellFed;
+                        hunger.duration = 200;
+                        affected += 1;
+                    }
+                }
+                EffectType::Teleport { x, y } => {
+                    if let Ok(mut pos) = position_query.get_mut(target) {
+                        pos.x = *x;
+                        pos.y = *y;
+                        if let Ok(mut viewshed) = viewshed_query.get_mut(target) {
+                            viewshed.dirty = true;
+                        }
+                        affected += 1;
+                    }
+                }
+                EffectType::Particle { .. } | EffectType::Flash { .. } | EffectType::RevealMap => {}
+            }
+        }
+
+        if affected == 0 {
+            if let (EffectType::Damage { .. }, Some(source)) = (&event.effect, &event.source) {
+                gamelog.log(
+                    LogCategory::Combat,
+                    format!("{} hits nothing.", source.name),
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_area_targets_are_a_clipped_circle() {
+        let map = Map::new(10, 10, 1);
+
+        // Radius 1 is a plus shape: the diagonals are further than 1 away
+        let mut tiles = Targets::area(5, 5, 1).tiles(&map);
+        tiles.sort();
+        assert_eq!(tiles, vec![(4, 5), (5, 4), (5, 5), (5, 6), (6, 5)]);
+
+        // Tiles off the edge of the map are dropped
+        let corner = Targets::area(0, 0, 1).tiles(&map);
+        assert_eq!(corner.len(), 3);
+    }
+
+    #[test]
+    fn test_area_damage_hits_a_large_monster_once() {
+        use bevy::ecs::system::RunSystemOnce;
+
+        let mut world = World::new();
+        let dragon = world
+            .spawn((
+                Name {
+                    name: "Dragon".to_string(),
+                },
+                TextColor(Color::WHITE),
+                CombatStats {
+                    max_hp: 60,
+                    hp: 60,
+                    defense: 3,
+                    power: 9,
+                },
+            ))
+            .id();
+        // A 2x2 monster is in the contents of every tile it covers
+        let mut map = Map::new(10, 10, 1);
+        for (x, y) in [(4, 4), (5, 4), (4, 5), (5, 5)] {
+            let idx = map.xy_idx(x, y);
+            map.tile_content[idx].push(dragon);
+        }
+        world.insert_resource(map);
+        world.init_resource::<GameLog>();
+        world.init_resource::<ParticleBuilder>();
+        world.init_resource::<RunStats>();
+        world.init_resource::<PendingMagicMap>();
+        let mut events = Events::<EffectEvent>::default();
+        events.send(EffectEvent::damage(20, Targets::area(5, 5, 2)));
+        world.insert_resource(events);
+
+        world.run_system_once(effects_system).unwrap();
+        assert_eq!(world.get::<SufferDamage>(dragon).unwrap().amount, vec![20]);
+    }
+}
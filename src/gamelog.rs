@@ -1,14 +1,325 @@
+use std::collections::VecDeque;
+
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs::{self, OpenOptions};
+#[cfg(not(target_arch = "wasm32"))]
+use std::io::Write;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::saveload;
+
+/// How many entries the log keeps in memory; older ones are spilled to the run's archive file
+pub const MAX_LOG_ENTRIES: usize = 500;
+
+/// Color used for damage numbers
+pub const DAMAGE_COLOR: Color = Color::srgb(1.0, 0.2, 0.2);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum LogCategory {
+    Combat,
+    Item,
+    Status,
+    System,
+    Debug,
+}
+
+impl LogCategory {
+    pub fn label(&self) -> &'static str {
+        match self {
+            LogCategory::Combat => "Combat",
+            LogCategory::Item => "Item",
+            LogCategory::Status => "Status",
+            LogCategory::System => "System",
+            LogCategory::Debug => "Debug",
+        }
+    }
+
+    /// Color for plain text in entries of this category
+    pub fn color(&self) -> Color {
+        match self {
+            LogCategory::Combat => Color::WHITE,
+            LogCategory::Item => Color::srgb(0.6, 0.8, 1.0),
+            LogCategory::Status => Color::srgb(1.0, 0.8, 0.4),
+            LogCategory::System => Color::srgb(0.7, 0.7, 0.7),
+            LogCategory::Debug => Color::srgb(1.0, 0.4, 1.0),
+        }
+    }
+}
+
+/// A run of text in a single color
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LogSegment {
+    pub text: String,
+    /// sRGB components, stored raw so entries survive save/load
+    pub rgb: [f32; 3],
+}
+
+impl LogSegment {
+    pub fn new(text: impl Into<String>, color: Color) -> Self {
+        let srgba = color.to_srgba();
+        Self {
+            text: text.into(),
+            rgb: [srgba.red, srgba.green, srgba.blue],
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        Color::srgb(self.rgb[0], self.rgb[1], self.rgb[2])
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LogEntry {
+    pub turn: u32,
+    pub category: LogCategory,
+    pub segments: Vec<LogSegment>,
+}
+
+impl LogEntry {
+    /// The message without colors
+    pub fn text(&self) -> String {
+        self.segments.iter().map(|s| s.text.as_str()).collect()
+    }
+
+    /// One line for plain-text exports ("[   12] Combat  Orc hits Player for 3 hp.")
+    pub fn export_line(&self) -> String {
+        format!(
+            "[{:>5}] {:<7} {}",
+            self.turn,
+            self.category.label(),
+            self.text()
+        )
+    }
+}
 
 #[derive(Resource)]
 pub struct GameLog {
-    pub entries: Vec<String>,
+    pub entries: VecDeque<LogEntry>,
+    /// Turn number stamped on new entries
+    pub turn: u32,
+    /// File, kept beside the save, that entries beyond MAX_LOG_ENTRIES are appended to for this run
+    archive: Option<String>,
 }
 
 impl Default for GameLog {
     fn default() -> Self {
-        GameLog {
-            entries: vec!["Welcome to Rusty Roguelike".to_string()],
+        let mut log = GameLog {
+            entries: VecDeque::new(),
+            turn: 0,
+            archive: None,
+        };
+        log.log(LogCategory::System, "Welcome to Rusty Roguelike");
+        log
+    }
+}
+
+impl GameLog {
+    /// Start a new entry; finish it with `LogBuilder::log`
+    pub fn entry(&mut self, category: LogCategory) -> LogBuilder<'_> {
+        LogBuilder {
+            log: self,
+            category,
+            segments: Vec::new(),
+        }
+    }
+
+    /// Log a single line of plain text
+    pub fn log(&mut self, category: LogCategory, text: impl Into<String>) {
+        self.entry(category).text(text).log();
+    }
+
+    pub fn push(&mut self, entry: LogEntry) {
+        self.entries.push_back(entry);
+        while self.entries.len() > MAX_LOG_ENTRIES {
+            if let Some(oldest) = self.entries.pop_front() {
+                self.archive_entry(&oldest);
+            }
         }
     }
+
+    /// The most recent `count` entries, oldest first
+    pub fn recent(&self, count: usize) -> impl Iterator<Item = &LogEntry> {
+        self.entries
+            .iter()
+            .skip(self.entries.len().saturating_sub(count))
+    }
+
+    /// Forget the in-memory entries. The archive file is left alone.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.turn = 0;
+    }
+
+    /// Point the log at the archive file for the run with this seed
+    pub fn set_run(&mut self, seed: u64) {
+        self.archive = Some(format!("gamelog-{}.txt", seed));
+    }
+
+    /// Point the log at a new run's archive, dropping anything an earlier run with the same
+    /// seed left in it
+    pub fn start_run(&mut self, seed: u64) {
+        self.set_run(seed);
+        self.delete_archive();
+    }
+
+    /// Every entry of the run, including the ones spilled to the archive
+    pub fn export_lines(&self) -> Vec<String> {
+        let mut lines = self.read_archive();
+        lines.extend(self.entries.iter().map(|e| e.export_line()));
+        lines
+    }
+}
+
+// ============================================================================
+// Log Archive File
+// ============================================================================
+
+#[cfg(not(target_arch = "wasm32"))]
+impl GameLog {
+    fn archive_path(&self) -> Option<PathBuf> {
+        self.archive.as_deref().map(saveload::beside_save)
+    }
+
+    fn archive_entry(&self, entry: &LogEntry) {
+        let Some(path) = self.archive_path() else {
+            return;
+        };
+        let result = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| writeln!(file, "{}", entry.export_line()));
+        if let Err(e) = result {
+            error!("Failed to write log archive: {}", e);
+        }
+    }
+
+    fn read_archive(&self) -> Vec<String> {
+        self.archive_path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|text| text.lines().map(str::to_string).collect())
+            .unwrap_or_default()
+    }
+
+    /// Remove the run's archive once it has been exported
+    pub fn delete_archive(&self) {
+        if let Some(path) = self.archive_path() {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+// ============================================================================
+// WASM Stubs (no filesystem access, spilled entries are dropped)
+// ============================================================================
+
+#[cfg(target_arch = "wasm32")]
+impl GameLog {
+    fn archive_entry(&self, _entry: &LogEntry) {}
+
+    fn read_archive(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    pub fn delete_archive(&self) {}
+}
+
+/// Builds a log entry out of colored segments
+pub struct LogBuilder<'a> {
+    log: &'a mut GameLog,
+    category: LogCategory,
+    segments: Vec<LogSegment>,
+}
+
+impl LogBuilder<'_> {
+    /// Plain text in the category's color
+    pub fn text(self, text: impl Into<String>) -> Self {
+        let color = self.category.color();
+        self.colored(text, color)
+    }
+
+    pub fn colored(mut self, text: impl Into<String>, color: Color) -> Self {
+        self.segments.push(LogSegment::new(text, color));
+        self
+    }
+
+    /// A damage amount, in red
+    pub fn damage(self, amount: i32) -> Self {
+        self.colored(amount.to_string(), DAMAGE_COLOR)
+    }
+
+    pub fn log(self) {
+        let entry = LogEntry {
+            turn: self.log.turn,
+            category: self.category,
+            segments: self.segments,
+        };
+        self.log.push(entry);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry_segments_and_turn() {
+        let mut log = GameLog {
+            turn: 7,
+            ..default()
+        };
+        log.entry(LogCategory::Combat)
+            .colored("Orc", Color::srgb(1.0, 0.0, 0.0))
+            .text(" hits you for ")
+            .damage(3)
+            .text(" hp.")
+            .log();
+
+        let entry = log.entries.back().unwrap();
+        assert_eq!(entry.turn, 7);
+        assert_eq!(entry.category, LogCategory::Combat);
+        assert_eq!(entry.segments.len(), 4);
+        assert_eq!(entry.text(), "Orc hits you for 3 hp.");
+        assert_eq!(entry.segments[2].color(), DAMAGE_COLOR);
+    }
+
+    #[test]
+    fn test_log_is_capped() {
+        let mut log = GameLog::default();
+        for i in 0..MAX_LOG_ENTRIES + 10 {
+            log.log(LogCategory::System, format!("message {}", i));
+        }
+
+        assert_eq!(log.entries.len(), MAX_LOG_ENTRIES);
+        let newest: Vec<String> = log.recent(2).map(|e| e.text()).collect();
+        assert_eq!(
+            newest,
+            vec![
+                format!("message {}", MAX_LOG_ENTRIES + 8),
+                format!("message {}", MAX_LOG_ENTRIES + 9)
+            ]
+        );
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_new_run_drops_stale_archive() {
+        let seed = 0x5eed_0029;
+        let mut log = GameLog::default();
+        log.set_run(seed);
+        for i in 0..MAX_LOG_ENTRIES + 3 {
+            log.log(LogCategory::System, format!("old run {}", i));
+        }
+        assert_eq!(log.export_lines().len(), MAX_LOG_ENTRIES + 4);
+
+        let mut log = GameLog::default();
+        log.start_run(seed);
+        assert_eq!(log.export_lines().len(), 1);
+        log.delete_archive();
+    }
 }
@@ -2,14 +2,17 @@ use bevy::input::keyboard::KeyboardInput;
 use bevy::input::ButtonState;
 use bevy::prelude::*;
 
-use crate::components::{EntryTrigger, Item};
+use crate::components::{Door, EntryTrigger, Item, Lever, Npc};
 use crate::gamelog::GameLog;
-use crate::map::{Map, Position, Revealed, RevealedState, Tile, TileType, FONT_SIZE, MAP_WIDTH};
+use crate::journey::{ReturnJourney, SURFACE_DEPTH};
+use crate::lighting::{self, LightSource};
+use crate::map::{Map, FONT_SIZE};
 use crate::map_builders;
 use crate::monsters::Monster;
 use crate::player::Player;
 use crate::resources::{MenuBackground, UiFont};
 use crate::rng::GameRng;
+use crate::run_stats::RunStats;
 use crate::saveload;
 use crate::spawner;
 use crate::ui::{BuilderMenu, BuilderMenuText, MainMenu, MenuPage, ITEMS_PER_PAGE};
@@ -47,20 +50,39 @@ fn reset_menu_page(mut menu_page: ResMut<MenuPage>) {
 
 fn cleanup_game_entities(
     mut commands: Commands,
-    entities: Query<Entity, Or<(With<Player>, With<Monster>, With<Item>, With<Tile>)>>,
+    entities: Query<Entity, Or<(With<Player>, With<Monster>, With<Item>, With<LightSource>)>>,
+    townsfolk: Query<Entity, With<Npc>>,
+    doors: Query<Entity, With<Door>>,
+    levers: Query<Entity, With<Lever>>,
     mut map: ResMut<Map>,
     mut game_log: ResMut<GameLog>,
+    mut journey: ResMut<ReturnJourney>,
+    mut run_stats: ResMut<RunStats>,
+    mut rng: ResMut<GameRng>,
 ) {
     // Despawn all game entities
-    for entity in &entities {
+    for entity in entities.iter().chain(&townsfolk).chain(&doors).chain(&levers) {
         commands.entity(entity).despawn_recursive();
     }
 
     // Reset map to default (will be regenerated on New Game)
     *map = Map::default();
 
-    // Clear game log
-    game_log.entries.clear();
+    // Clear game log. A run that was saved keeps its archive for Continue; any other is over.
+    game_log.clear();
+    if !saveload::has_save_file() {
+        game_log.delete_archive();
+    }
+
+    // Every run starts on the way down with a clean slate and a fresh, recorded seed
+    journey.ascending = false;
+    let seed = rand::random::<u64>();
+    *rng = GameRng::seeded(seed);
+    game_log.start_run(seed);
+    *run_stats = RunStats {
+        seed,
+        ..default()
+    };
 }
 
 fn spawn_new_game_immediate(
@@ -69,8 +91,8 @@ fn spawn_new_game_immediate(
     rng: &mut ResMut<GameRng>,
     font: &Res<UiFont>,
 ) {
-    // Generate new map using default builder
-    let mut builder = map_builders::default_builder(1);
+    // Every run sets out from the town on the surface
+    let mut builder = map_builders::default_builder(SURFACE_DEPTH);
     builder.build_map(rng);
     *map.as_mut() = builder.get_map();
 
@@ -80,69 +102,24 @@ fn spawn_new_game_immediate(
         ..default()
     };
 
-    // Spawn map tiles
-    let mut y = 0;
-    let mut x = 0;
-    for tile in map.tiles.iter() {
-        match tile {
-            TileType::Floor => {
-                commands.spawn((
-                    Tile,
-                    Position { x, y },
-                    Text2d::new("."),
-                    text_font.clone(),
-                    TextColor(Color::srgb(0.5, 0.5, 0.5)),
-                    Revealed(RevealedState::Hidden),
-                ));
-            }
-            TileType::Wall => {
-                if map.is_adjacent_to_floor(x, y) {
-                    let glyph = map.wall_glyph_at(x, y);
-                    commands.spawn((
-                        Tile,
-                        Position { x, y },
-                        glyph,
-                        Text2d::new(glyph.to_char().to_string()),
-                        text_font.clone(),
-                        TextColor(Color::srgb(0.0, 1.0, 0.0)),
-                        Revealed(RevealedState::Hidden),
-                    ));
-                }
-            }
-            TileType::DownStairs => {
-                commands.spawn((
-                    Tile,
-                    Position { x, y },
-                    Text2d::new(">"),
-                    text_font.clone(),
-                    TextColor(Color::srgb(0.0, 1.0, 1.0)),
-                    Revealed(RevealedState::Hidden),
-                ));
-            }
-        }
-
-        x += 1;
-        if x > MAP_WIDTH as i32 - 1 {
-            x = 0;
-            y += 1;
-        }
-    }
-
     // Spawn player at starting position
     let (player_x, player_y) = builder.get_starting_position();
     spawner::spawn_player(commands, &text_font, player_x, player_y);
 
     // Spawn monsters and items via builder
     builder.spawn_entities(commands, rng, &text_font);
+    lighting::spawn_level_lights(commands, map, &text_font);
+    commands.insert_resource(builder.get_level_info());
+    commands.insert_resource(builder.get_region_graph());
 }
 
 fn spawn_main_menu(mut commands: Commands, font: Res<UiFont>, background: Res<MenuBackground>) {
     let has_save = saveload::has_save_file();
 
     let menu_text = if has_save {
-        "Rust Roguelike\n\n(N) New Game\n(C) Continue\n(V) Map Visualizer\n(Q) Quit"
+        "Rust Roguelike\n\n(N) New Game\n(C) Continue\n(H) High Scores\n(V) Map Visualizer\n(O) Options\n(Q) Quit"
     } else {
-        "Rust Roguelike\n\n(N) New Game\n(V) Map Visualizer\n(Q) Quit"
+        "Rust Roguelike\n\n(N) New Game\n(H) High Scores\n(V) Map Visualizer\n(O) Options\n(Q) Quit"
     };
 
     commands
@@ -195,9 +172,11 @@ fn handle_main_menu_input(
     mut next_state: ResMut<NextState<RunState>>,
     mut exit: EventWriter<AppExit>,
     // Resources needed for loading/new game
-    entities_to_despawn: Query<Entity, Or<(With<Player>, With<Monster>, With<Item>, With<Tile>, With<EntryTrigger>)>>,
+    entities_to_despawn: Query<Entity, Or<(With<Player>, With<Monster>, With<Item>, With<EntryTrigger>, With<Npc>, With<Door>, With<Lever>, With<LightSource>)>>,
     mut map: ResMut<Map>,
     mut game_log: ResMut<GameLog>,
+    mut journey: ResMut<ReturnJourney>,
+    mut run_stats: ResMut<RunStats>,
     mut rng: ResMut<crate::rng::GameRng>,
     font: Res<UiFont>,
     mut selected_builder: ResMut<SelectedBuilder>,
@@ -212,7 +191,7 @@ fn handle_main_menu_input(
                 // New Game - start game immediately (skip visualizer)
                 selected_builder.0 = None;
                 spawn_new_game_immediate(&mut commands, &mut map, &mut rng, &font);
-                next_state.set(RunState::PreRun);
+                next_state.set(RunState::LevelTransition);
             }
             KeyCode::KeyC => {
                 // Continue - load from save file
@@ -222,16 +201,24 @@ fn handle_main_menu_input(
                         &entities_to_despawn,
                         &mut map,
                         &mut game_log,
+                        &mut journey,
+                        &mut run_stats,
                         &font,
                     ) {
                         next_state.set(RunState::PreRun);
                     }
                 }
             }
+            KeyCode::KeyH => {
+                next_state.set(RunState::HighScores);
+            }
             KeyCode::KeyV => {
                 // Map Visualizer - go to builder selection
                 next_state.set(RunState::MapBuilderSelect);
             }
+            KeyCode::KeyO => {
+                next_state.set(RunState::Options);
+            }
             KeyCode::KeyQ => {
                 exit.send(AppExit::Success);
             }
@@ -430,7 +417,7 @@ fn start_visualizer(
     builder_name.0 = builder.get_name().to_string();
     builder.build_map(rng);
     *map.as_mut() = builder.get_map();
-    mapgen_history.0 = builder.get_snapshot_history();
+    mapgen_history.0 = builder.get_snapshots();
     spawn_data.starting_pos = builder.get_starting_position();
     spawn_data.spawn_regions = builder.get_spawn_regions();
     spawn_data.depth = 1;
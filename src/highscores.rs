@@ -0,0 +1,157 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(not(target_arch = "wasm32"))]
+use bevy::prelude::*;
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs;
+
+/// Lives next to savegame.json
+#[cfg(not(target_arch = "wasm32"))]
+const HIGH_SCORE_FILE: &str = "highscores.json";
+
+/// How many runs the table keeps
+pub const MAX_HIGH_SCORES: usize = 10;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HighScoreEntry {
+    pub score: i32,
+    pub deepest_depth: i32,
+    pub turns: u32,
+    pub kills: u32,
+    pub victory: bool,
+    pub cause_of_death: Option<String>,
+}
+
+impl HighScoreEntry {
+    /// One line for the high-score table
+    pub fn describe(&self) -> String {
+        let outcome = if self.victory {
+            "escaped with the Heart".to_string()
+        } else {
+            self.cause_of_death
+                .clone()
+                .unwrap_or_else(|| "died".to_string())
+        };
+        format!(
+            "{:>6}  depth {:>2}  {:>3} kills  {:>5} turns  {}",
+            self.score, self.deepest_depth, self.kills, self.turns, outcome
+        )
+    }
+}
+
+/// Format the table for display, best first
+pub fn table_text(scores: &[HighScoreEntry]) -> String {
+    if scores.is_empty() {
+        return "No runs recorded yet.".to_string();
+    }
+    scores
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| format!("{:>2}. {}", i + 1, entry.describe()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Insert a run into a score table, keeping it sorted and capped.
+/// Returns the rank (0-based) if the run made the table.
+fn insert(scores: &mut Vec<HighScoreEntry>, entry: HighScoreEntry) -> Option<usize> {
+    let rank = scores
+        .iter()
+        .position(|existing| entry.score > existing.score)
+        .unwrap_or(scores.len());
+    if rank >= MAX_HIGH_SCORES {
+        return None;
+    }
+    scores.insert(rank, entry);
+    scores.truncate(MAX_HIGH_SCORES);
+    Some(rank)
+}
+
+// ============================================================================
+// High Score File
+// ============================================================================
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load() -> Vec<HighScoreEntry> {
+    fs::read_to_string(HIGH_SCORE_FILE)
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// Add a finished run to the high-score file.
+/// Returns the rank (0-based) if the run made the table.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn record(entry: HighScoreEntry) -> Option<usize> {
+    let mut scores = load();
+    let rank = insert(&mut scores, entry)?;
+
+    match serde_json::to_string_pretty(&scores) {
+        Ok(json) => {
+            if let Err(e) = fs::write(HIGH_SCORE_FILE, json) {
+                error!("Failed to write high score file: {}", e);
+            }
+        }
+        Err(e) => {
+            error!("Failed to serialize high scores: {}", e);
+        }
+    }
+
+    Some(rank)
+}
+
+// ============================================================================
+// WASM Stubs (no filesystem access)
+// ============================================================================
+
+#[cfg(target_arch = "wasm32")]
+pub fn load() -> Vec<HighScoreEntry> {
+    Vec::new()
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn record(entry: HighScoreEntry) -> Option<usize> {
+    insert(&mut Vec::new(), entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(score: i32) -> HighScoreEntry {
+        HighScoreEntry {
+            score,
+            deepest_depth: 1,
+            turns: 0,
+            kills: 0,
+            victory: false,
+            cause_of_death: None,
+        }
+    }
+
+    #[test]
+    fn test_insert_keeps_best_first() {
+        let mut scores = Vec::new();
+        assert_eq!(insert(&mut scores, entry(100)), Some(0));
+        assert_eq!(insert(&mut scores, entry(300)), Some(0));
+        assert_eq!(insert(&mut scores, entry(200)), Some(1));
+
+        let ordered: Vec<i32> = scores.iter().map(|e| e.score).collect();
+        assert_eq!(ordered, vec![300, 200, 100]);
+    }
+
+    #[test]
+    fn test_insert_caps_table() {
+        let mut scores: Vec<HighScoreEntry> =
+            (0..MAX_HIGH_SCORES as i32).map(|i| entry(1000 - i)).collect();
+
+        // Too low to make the table
+        assert_eq!(insert(&mut scores, entry(0)), None);
+        assert_eq!(scores.len(), MAX_HIGH_SCORES);
+
+        // Pushes the lowest entry off the bottom
+        assert_eq!(insert(&mut scores, entry(996)), Some(5));
+        assert_eq!(scores.len(), MAX_HIGH_SCORES);
+        assert_eq!(scores.last().unwrap().score, 1000 - MAX_HIGH_SCORES as i32 + 2);
+    }
+}
@@ -3,13 +3,15 @@ use bevy::prelude::*;
 use crate::{
     combat::SufferDamage,
     components::{HungerClock, HungerState},
-    gamelog::GameLog,
+    gamelog::{GameLog, LogCategory},
     player::Player,
+    run_stats::RunStats,
 };
 
 pub fn hunger_system(
     mut commands: Commands,
     mut gamelog: ResMut<GameLog>,
+    mut run_stats: ResMut<RunStats>,
     mut query: Query<(Entity, &mut HungerClock), With<Player>>,
 ) {
     for (entity, mut clock) in &mut query {
@@ -19,23 +21,22 @@ pub fn hunger_system(
                 HungerState::WellFed => {
                     clock.state = HungerState::Normal;
                     clock.duration = 200;
-                    gamelog
-                        .entries
-                        .push("You are no longer well fed.".to_string());
+                    gamelog.log(LogCategory::Status, "You are no longer well fed.");
                 }
                 HungerState::Normal => {
                     clock.state = HungerState::Hungry;
                     clock.duration = 200;
-                    gamelog.entries.push("You are hungry.".to_string());
+                    gamelog.log(LogCategory::Status, "You are hungry.");
                 }
                 HungerState::Hungry => {
                     clock.state = HungerState::Starving;
                     clock.duration = 200;
-                    gamelog.entries.push("You are starving!".to_string());
+                    gamelog.log(LogCategory::Status, "You are starving!");
                 }
                 HungerState::Starving => {
                     // Deal 1 damage per turn
                     SufferDamage::new_damage(&mut commands, entity, 1);
+                    run_stats.last_damage_source = Some("Starved to death".to_string());
                 }
             }
         }
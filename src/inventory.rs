@@ -1,24 +1,24 @@
 use bevy::prelude::*;
 
 use crate::{
-    combat::{CombatStats, SufferDamage},
     components::{
-        AreaOfEffect, CausesConfusion, Confusion, Consumable, Equippable, Equipped, HungerClock,
-        HungerState, InBackpack, InflictsDamage, MagicMapper, Name, ProvidesFood, ProvidesHealing,
+        AreaOfEffect, CausesConfusion, Consumable, Equippable, Equipped, HeartOfTheAbyss,
+        InBackpack, InflictsDamage, MagicMapper, Name, ProvidesFood, ProvidesHealing,
         WantsToDropItem, WantsToPickupItem, WantsToRemoveItem, WantsToUseItem,
     },
-    distance::DistanceAlg,
-    gamelog::GameLog,
+    effects::{EffectEvent, EffectType, Status, Targets},
+    gamelog::{GameLog, LogCategory},
+    journey::ReturnJourney,
     map::Position,
-    particle::ParticleBuilder,
-    PendingMagicMap,
 };
 
 pub fn item_collection_system(
     mut commands: Commands,
     mut gamelog: ResMut<GameLog>,
+    mut journey: ResMut<ReturnJourney>,
     pickup_query: Query<(Entity, &WantsToPickupItem)>,
-    name_query: Query<&Name>,
+    name_query: Query<(&Name, &TextColor)>,
+    heart_query: Query<&HeartOfTheAbyss>,
 ) {
     for (entity, wants_pickup) in &pickup_query {
         // Remove position and hide item so it's no longer on the map
@@ -36,10 +36,22 @@ pub fn item_collection_system(
         commands.entity(entity).remove::<WantsToPickupItem>();
 
         // Log the pickup
-        if let Ok(name) = name_query.get(wants_pickup.item) {
+        if let Ok((name, color)) = name_query.get(wants_pickup.item) {
             gamelog
-                .entries
-                .push(format!("You pick up the {}.", name.name));
+                .entry(LogCategory::Item)
+                .text("You pick up the ")
+                .colored(&name.name, color.0)
+                .text(".")
+                .log();
+        }
+
+        // Claiming the Heart turns the run around
+        if heart_query.get(wants_pickup.item).is_ok() && !journey.ascending {
+            journey.ascending = true;
+            gamelog.log(
+                LogCategory::Status,
+                "The Heart of the Abyss pulses in your hands. The Abyss stirs; the way back will be harder.",
+            );
         }
     }
 }
@@ -47,24 +59,25 @@ pub fn item_collection_system(
 pub fn item_use_system(
     mut commands: Commands,
     mut gamelog: ResMut<GameLog>,
-    mut particle_builder: ResMut<ParticleBuilder>,
-    mut pending_magic_map: ResMut<PendingMagicMap>,
-    use_query: Query<(Entity, &WantsToUseItem, &Position)>,
-    consumable_query: Query<&Consumable>,
-    healing_query: Query<(&ProvidesHealing, &Name)>,
-    damage_query: Query<(&InflictsDamage, &Name)>,
-    confusion_query: Query<(&CausesConfusion, &Name)>,
+    mut effects: EventWriter<EffectEvent>,
+    use_query: Query<(Entity, &WantsToUseItem)>,
+    item_query: Query<(&Name, &TextColor, Option<&Consumable>)>,
+    healing_query: Query<&ProvidesHealing>,
+    damage_query: Query<&InflictsDamage>,
+    confusion_query: Query<&CausesConfusion>,
     food_mapper_query: Query<(Option<&ProvidesFood>, Option<&MagicMapper>)>,
     aoe_query: Query<&AreaOfEffect>,
-    equippable_query: Query<(&Equippable, &Name)>,
+    equippable_query: Query<&Equippable>,
     equipped_query: Query<(Entity, &Equipped, &Name)>,
-    mut stats_query: Query<&mut CombatStats>,
-    mut hunger_query: Query<&mut HungerClock>,
-    target_query: Query<(Entity, &Position, &Name), With<CombatStats>>,
 ) {
-    for (entity, wants_use, user_pos) in &use_query {
+    for (entity, wants_use) in &use_query {
+        let Ok((item_name, item_color, consumable)) = item_query.get(wants_use.item) else {
+            commands.entity(entity).remove::<WantsToUseItem>();
+            continue;
+        };
+
         // Handle equippable items
-        if let Ok((equippable, item_name)) = equippable_query.get(wants_use.item) {
+        if let Ok(equippable) = equippable_query.get(wants_use.item) {
             let target_slot = equippable.slot;
 
             // Find and unequip any item in the same slot owned by this entity
@@ -75,9 +88,10 @@ pub fn item_use_system(
                     commands
                         .entity(equipped_entity)
                         .insert(InBackpack { owner: entity });
-                    gamelog
-                        .entries
-                        .push(format!("You unequip the {}.", equipped_name.name));
+                    gamelog.log(
+                        LogCategory::Item,
+                        format!("You unequip the {}.", equipped_name.name),
+                    );
                 }
             }
 
@@ -87,149 +101,100 @@ pub fn item_use_system(
                 owner: entity,
                 slot: target_slot,
             });
-            gamelog
-                .entries
-                .push(format!("You equip the {}.", item_name.name));
+            gamelog.log(LogCategory::Item, format!("You equip the {}.", item_name.name));
 
             // Remove the intent and continue to next item
             commands.entity(entity).remove::<WantsToUseItem>();
             continue;
         }
-        // Apply healing if the item provides it
-        if let Ok((healing, item_name)) = healing_query.get(wants_use.item) {
-            if let Ok(mut stats) = stats_query.get_mut(entity) {
-                stats.hp = (stats.hp + healing.heal_amount).min(stats.max_hp);
-                gamelog.entries.push(format!(
+
+        // Healing
+        if let Ok(healing) = healing_query.get(wants_use.item) {
+            effects.send(EffectEvent::new(
+                EffectType::Heal {
+                    amount: healing.heal_amount,
+                },
+                Targets::Single(entity),
+            ));
+            effects.send(EffectEvent::particle(
+                "♥",
+                Color::srgb(0.0, 1.0, 0.0), // Green
+                Targets::Single(entity),
+            ));
+            gamelog.log(
+                LogCategory::Item,
+                format!(
                     "You drink the {}, healing {} hp.",
                     item_name.name, healing.heal_amount
-                ));
-
-                // Spawn healing particle
-                particle_builder.request(
-                    user_pos.x,
-                    user_pos.y,
-                    "♥",
-                    Color::srgb(0.0, 1.0, 0.0), // Green
-                    200.0,
-                );
-            }
+                ),
+            );
         }
 
-        // Apply food or magic mapper effects
+        // Food and magic mapping
         if let Ok((food, mapper)) = food_mapper_query.get(wants_use.item) {
             if food.is_some() {
-                if let Ok(mut hunger) = hunger_query.get_mut(entity) {
-                    hunger.state = HungerState::WellFed;
-                    hunger.duration = 200;
-                    gamelog.entries.push("You eat the rations.".to_string());
-                }
+                effects.send(EffectEvent::new(
+                    EffectType::ApplyStatus(Status::WellFed),
+                    Targets::Single(entity),
+                ));
+                gamelog.log(LogCategory::Item, "You eat the rations.");
             }
             if mapper.is_some() {
-                gamelog
-                    .entries
-                    .push("The map is revealed to your mind!".to_string());
-                pending_magic_map.0 = true;
+                effects.send(EffectEvent::new(EffectType::RevealMap, Targets::Single(entity)));
+                gamelog.log(LogCategory::Item, "The map is revealed to your mind!");
             }
         }
 
-        // Apply effects if the item has a target position
+        // Targeted effects land on a tile, or everything around it for area items
         if let Some((target_x, target_y)) = wants_use.target {
-            // Apply damage if the item inflicts it
-            if let Ok((inflicts, item_name)) = damage_query.get(wants_use.item) {
-                // Check if this is an AoE item
-                if let Ok(aoe) = aoe_query.get(wants_use.item) {
-                    // Spawn AOE particles for the blast zone
-                    let radius = aoe.radius as i32;
-                    for dx in -radius..=radius {
-                        for dy in -radius..=radius {
-                            let distance = DistanceAlg::Euclidean
-                                .distance2d(Vec2::ZERO, Vec2::new(dx as f32, dy as f32));
-                            if distance <= aoe.radius as f32 {
-                                particle_builder.request(
-                                    target_x + dx,
-                                    target_y + dy,
-                                    "░",
-                                    Color::srgb(1.0, 0.5, 0.0), // Orange
-                                    200.0,
-                                );
-                            }
-                        }
-                    }
-
-                    // Find all entities within the AoE radius
-                    let mut hit_count = 0;
-                    for (target_entity, pos, target_name) in &target_query {
-                        let distance = DistanceAlg::Euclidean.distance2d(
-                            Vec2::new(target_x as f32, target_y as f32),
-                            Vec2::new(pos.x as f32, pos.y as f32),
-                        );
-                        if distance <= aoe.radius as f32 {
-                            SufferDamage::new_damage(&mut commands, target_entity, inflicts.damage);
-                            gamelog.entries.push(format!(
-                                "{} hits {} for {} hp.",
-                                item_name.name, target_name.name, inflicts.damage
-                            ));
-                            hit_count += 1;
-                        }
-                    }
-                    if hit_count == 0 {
-                        gamelog
-                            .entries
-                            .push(format!("{} explodes, but hits nothing.", item_name.name));
-                    }
-                } else {
-                    // Single target - find entity at exact position
-                    for (target_entity, pos, target_name) in &target_query {
-                        if pos.x == target_x && pos.y == target_y {
-                            SufferDamage::new_damage(&mut commands, target_entity, inflicts.damage);
-                            gamelog.entries.push(format!(
-                                "You use {} on {}, inflicting {} hp.",
-                                item_name.name, target_name.name, inflicts.damage
-                            ));
+            let area = aoe_query.get(wants_use.item).ok();
+            let targets = match area {
+                Some(aoe) => Targets::area(target_x, target_y, aoe.radius),
+                None => Targets::Tile {
+                    x: target_x,
+                    y: target_y,
+                },
+            };
 
-                            // Spawn damage particle
-                            particle_builder.request(
-                                pos.x,
-                                pos.y,
-                                "‼",
-                                Color::srgb(1.0, 0.0, 0.0), // Red
-                                200.0,
-                            );
-                            break;
-                        }
-                    }
-                }
+            if let Ok(inflicts) = damage_query.get(wants_use.item) {
+                effects.send(
+                    EffectEvent::damage(inflicts.damage, targets.clone())
+                        .with_source(entity, &item_name.name, item_color.0),
+                );
+                let (glyph, color) = match area {
+                    Some(_) => ("░", Color::srgb(1.0, 0.5, 0.0)), // Orange blast
+                    None => ("‼", Color::srgb(1.0, 0.0, 0.0)),    // Red
+                };
+                effects.send(EffectEvent::particle(glyph, color, targets.clone()));
+                effects.send(EffectEvent::new(
+                    EffectType::Flash {
+                        radius: area.map_or(2, |aoe| aoe.radius + 2),
+                        color,
+                    },
+                    Targets::Tile {
+                        x: target_x,
+                        y: target_y,
+                    },
+                ));
             }
 
-            // Apply confusion if the item causes it
-            if let Ok((causes_confusion, item_name)) = confusion_query.get(wants_use.item) {
-                // Find entity at target position
-                for (target_entity, pos, target_name) in &target_query {
-                    if pos.x == target_x && pos.y == target_y {
-                        commands.entity(target_entity).insert(Confusion {
-                            turns: causes_confusion.turns,
-                        });
-                        gamelog.entries.push(format!(
-                            "You use {} on {}, confusing them for {} turns.",
-                            item_name.name, target_name.name, causes_confusion.turns
-                        ));
-
-                        // Spawn confusion particle
-                        particle_builder.request(
-                            pos.x,
-                            pos.y,
-                            "?",
-                            Color::srgb(1.0, 0.0, 1.0), // Magenta
-                            200.0,
-                        );
-                        break;
-                    }
-                }
+            if let Ok(causes_confusion) = confusion_query.get(wants_use.item) {
+                effects.send(EffectEvent::new(
+                    EffectType::ApplyStatus(Status::Confusion {
+                        turns: causes_confusion.turns,
+                    }),
+                    targets.clone(),
+                ));
+                effects.send(EffectEvent::particle(
+                    "?",
+                    Color::srgb(1.0, 0.0, 1.0), // Magenta
+                    targets,
+                ));
             }
         }
 
         // If consumable, destroy the item
-        if consumable_query.get(wants_use.item).is_ok() {
+        if consumable.is_some() {
             commands.entity(wants_use.item).despawn();
         }
 
@@ -242,7 +207,7 @@ pub fn item_drop_system(
     mut commands: Commands,
     mut gamelog: ResMut<GameLog>,
     drop_query: Query<(Entity, &WantsToDropItem, &Position)>,
-    name_query: Query<&Name>,
+    name_query: Query<(&Name, &TextColor)>,
 ) {
     for (entity, wants_drop, dropper_pos) in &drop_query {
         // Remove from backpack and make visible again
@@ -258,8 +223,13 @@ pub fn item_drop_system(
         });
 
         // Log the drop
-        if let Ok(name) = name_query.get(wants_drop.item) {
-            gamelog.entries.push(format!("You drop the {}.", name.name));
+        if let Ok((name, color)) = name_query.get(wants_drop.item) {
+            gamelog
+                .entry(LogCategory::Item)
+                .text("You drop the ")
+                .colored(&name.name, color.0)
+                .text(".")
+                .log();
         }
 
         // Remove intent component
@@ -282,9 +252,7 @@ pub fn item_remove_system(
 
         // Log the removal
         if let Ok(name) = name_query.get(wants_remove.item) {
-            gamelog
-                .entries
-                .push(format!("You unequip the {}.", name.name));
+            gamelog.log(LogCategory::Item, format!("You unequip the {}.", name.name));
         }
 
         // Remove intent component
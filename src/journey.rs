@@ -0,0 +1,47 @@
+use bevy::prelude::*;
+
+use crate::components::{HeartOfTheAbyss, InBackpack};
+use crate::map::Map;
+use crate::player::Player;
+use crate::saveload;
+use crate::RunState;
+
+/// Depth of the town on the surface, where every run starts and a successful one ends.
+pub const SURFACE_DEPTH: i32 = 0;
+
+/// Depth of the Heart Chamber, the bottom of the Abyss.
+pub const HEART_DEPTH: i32 = 12;
+
+/// Tracks whether the player has claimed the Heart and is fighting back to the surface.
+#[derive(Resource, Default)]
+pub struct ReturnJourney {
+    pub ascending: bool,
+}
+
+impl ReturnJourney {
+    /// Depth used for spawn tables. On the way back up every floor is treated as
+    /// deeper than the Heart Chamber, getting harder the closer you get to the surface.
+    pub fn spawn_depth(&self, depth: i32) -> i32 {
+        if self.ascending {
+            HEART_DEPTH + (HEART_DEPTH - depth)
+        } else {
+            depth
+        }
+    }
+}
+
+/// Walking back into the town with the Heart wins the run
+pub fn check_homecoming(
+    map: Res<Map>,
+    mut next_state: ResMut<NextState<RunState>>,
+    player: Query<Entity, With<Player>>,
+    heart: Query<&InBackpack, With<HeartOfTheAbyss>>,
+) {
+    let Ok(player) = player.get_single() else {
+        return;
+    };
+    if map.depth == SURFACE_DEPTH && heart.iter().any(|backpack| backpack.owner == player) {
+        saveload::delete_save_file();
+        next_state.set(RunState::Victory);
+    }
+}
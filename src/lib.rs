@@ -0,0 +1,696 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::time::{Timer, TimerMode};
+use map::{MapPlugin, GRID_PX, MAP_WIDTH};
+use monsters::MonstersPlugin;
+use player::PlayerPlugin;
+use resources::ResourcesPlugin;
+use viewshed::ViewshedPlugin;
+
+mod camera;
+mod combat;
+mod components;
+mod console;
+mod debug;
+mod distance;
+mod doors;
+mod effects;
+mod gamelog;
+mod gui;
+mod highscores;
+mod hunger;
+mod inventory;
+mod journey;
+mod lighting;
+mod map;
+mod map_builders;
+mod map_indexing;
+mod mapgen;
+mod monsters;
+mod morgue;
+mod particle;
+mod pathfinding;
+mod player;
+mod resources;
+mod rng;
+mod run_stats;
+mod saveload;
+mod shapes;
+mod spawner;
+mod theme;
+mod tilemap;
+mod tileset;
+mod traps;
+#[cfg(all(feature = "tui", not(target_arch = "wasm32")))]
+mod tui;
+mod ui;
+mod viewshed;
+
+const SCREEN_HEIGHT: usize = 50;
+const RESOLUTION: Vec2 = Vec2 {
+    x: MAP_WIDTH as f32 * GRID_PX.x,
+    y: SCREEN_HEIGHT as f32 * GRID_PX.y,
+};
+
+pub const SHOW_MAPGEN_VISUALIZER: bool = true;
+
+#[derive(States, Clone, Copy, Default, Eq, PartialEq, Debug, Hash)]
+pub enum RunState {
+    #[default]
+    MainMenu,
+    MapBuilderSelect,
+    MapGeneration,
+    PreRun,
+    AwaitingInput,
+    PlayerTurn,
+    MonsterTurn,
+    ShowInventory,
+    ShowDropItem,
+    ShowRemoveItem,
+    ShowTargeting,
+    ChooseDirection,
+    ShowLog,
+    NextLevel,
+    PreviousLevel,
+    LevelTransition,
+    MagicMapReveal,
+    GameOver,
+    Victory,
+    HighScores,
+    Options,
+}
+
+#[derive(Resource, Default)]
+pub struct MagicMapRevealRow(pub i32);
+
+#[derive(Resource, Default)]
+pub struct PendingMagicMap(pub bool);
+
+#[derive(Resource, Default)]
+pub struct TargetingInfo {
+    pub range: i32,
+    pub item: Option<Entity>,
+}
+
+// Map generation visualization resources
+#[derive(Resource, Default)]
+pub struct MapGenHistory(pub Vec<map_builders::Snapshot>);
+
+#[derive(Resource, Default)]
+pub struct MapGenIndex(pub usize);
+
+#[derive(Resource)]
+pub struct MapGenTimer(pub Timer);
+
+impl Default for MapGenTimer {
+    fn default() -> Self {
+        Self(Timer::from_seconds(0.5, TimerMode::Repeating))
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct MapGenSpawnData {
+    pub starting_pos: (i32, i32),
+    pub spawn_regions: Vec<shapes::Rect>,
+    pub depth: i32,
+    pub pending: bool,
+}
+
+#[derive(Resource, Default)]
+pub struct MapGenBuilderName(pub String);
+
+/// None = random builder, Some(index) = specific builder
+#[derive(Resource, Default)]
+pub struct SelectedBuilder(pub Option<usize>);
+
+/// Where the game is shown and played
+pub enum Frontend {
+    Window,
+    /// The terminal the game was started from, with no window or GPU
+    #[cfg(all(feature = "tui", not(target_arch = "wasm32")))]
+    Terminal,
+}
+
+/// The whole game, ready to run in the given frontend
+pub fn build_app(frontend: Frontend) -> App {
+    let mut app = App::new();
+    match frontend {
+        Frontend::Window => {
+            app.add_plugins(DefaultPlugins.set(WindowPlugin {
+                primary_window: Some(Window {
+                    title: "Rust Roguelike".into(),
+                    resolution: RESOLUTION.into(),
+                    ..default()
+                }),
+                ..default()
+            }));
+        }
+        #[cfg(all(feature = "tui", not(target_arch = "wasm32")))]
+        Frontend::Terminal => {
+            app.add_plugins(tui::TuiPlugin);
+        }
+    }
+
+    app.init_state::<RunState>()
+        .init_resource::<gamelog::GameLog>()
+        .init_resource::<rng::GameRng>()
+        .init_resource::<TargetingInfo>()
+        .init_resource::<MagicMapRevealRow>()
+        .init_resource::<PendingMagicMap>()
+        .init_resource::<journey::ReturnJourney>()
+        .init_resource::<run_stats::RunStats>()
+        .init_resource::<particle::ParticleBuilder>()
+        .init_resource::<MapGenHistory>()
+        .init_resource::<MapGenIndex>()
+        .init_resource::<MapGenTimer>()
+        .init_resource::<MapGenSpawnData>()
+        .init_resource::<MapGenBuilderName>()
+        .init_resource::<SelectedBuilder>()
+        .add_event::<AppExit>()
+        .add_event::<effects::EffectEvent>()
+        .add_plugins((
+            ResourcesPlugin,
+            PlayerPlugin,
+            ViewshedPlugin,
+            lighting::LightingPlugin,
+            MapPlugin,
+            (console::ConsolePlugin, tilemap::TileMapPlugin, tileset::TilesetPlugin),
+            MonstersPlugin,
+            doors::DoorsPlugin,
+            camera::CameraPlugin,
+            gui::GuiPlugin,
+            ui::HudPlugin,
+            ui::TooltipPlugin,
+            ui::TargetingPlugin,
+            (
+                ui::OptionsPlugin,
+                ui::GameOverPlugin,
+                ui::VictoryPlugin,
+                ui::HighScoresPlugin,
+                ui::InventoryPlugin,
+                ui::LogViewerPlugin,
+                ui::LevelTransitionPlugin,
+            ),
+            (debug::DebugPlugin, mapgen::MapGenPlugin),
+        ))
+        .add_systems(Startup, setup)
+        .add_systems(Update, handle_exit)
+        .add_systems(
+            Update,
+            (
+                map_indexing::map_indexing_system,
+                particle::particle_spawn_system,
+                particle::particle_cull_system,
+                traps::reveal_hidden_system,
+            )
+                .run_if(not(in_state(RunState::MapGeneration))),
+        )
+        // MapGeneration: visualize map building
+        .add_systems(OnEnter(RunState::MapGeneration), setup_mapgen_visualization)
+        .add_systems(
+            Update,
+            mapgen_input.run_if(in_state(RunState::MapGeneration)),
+        )
+        .add_systems(OnExit(RunState::MapGeneration), finalize_mapgen)
+        // PreRun: run systems then transition to AwaitingInput
+        .add_systems(
+            Update,
+            // Arriving in town with the Heart ends the run instead
+            (transition_to_awaiting_input, journey::check_homecoming)
+                .chain()
+                .run_if(in_state(RunState::PreRun)),
+        )
+        // PlayerTurn: run combat and item systems then transition to MonsterTurn
+        .add_systems(
+            Update,
+            (
+                traps::trap_trigger_system,
+                traps::search_system,
+                inventory::item_collection_system,
+                run_stats::count_items_used,
+                inventory::item_use_system,
+                inventory::item_drop_system,
+                inventory::item_remove_system,
+                combat::melee_combat_system,
+                effects::effects_system,
+                combat::damage_system,
+                combat::delete_the_dead,
+                hunger::hunger_system,
+                run_stats::count_turns,
+                transition_to_monster_turn,
+            )
+                .chain()
+                .run_if(in_state(RunState::PlayerTurn)),
+        )
+        // MonsterTurn: run monster AI then transition to AwaitingInput
+        .add_systems(
+            Update,
+            (
+                monsters::monster_ai,
+                traps::trap_trigger_system,
+                combat::melee_combat_system,
+                effects::effects_system,
+                combat::damage_system,
+                combat::delete_the_dead,
+                transition_to_awaiting_input,
+            )
+                .chain()
+                .run_if(in_state(RunState::MonsterTurn)),
+        )
+        // NextLevel/PreviousLevel: generate new level and transition to PreRun
+        .add_systems(
+            Update,
+            change_level.run_if(
+                in_state(RunState::NextLevel).or(in_state(RunState::PreviousLevel)),
+            ),
+        )
+        // Effects sent outside a turn (e.g. from the debug console)
+        .add_systems(PostUpdate, effects::effects_system)
+        // MagicMapReveal: reveal map row by row
+        .add_systems(OnEnter(RunState::MagicMapReveal), reset_magic_map_row)
+        .add_systems(
+            Update,
+            magic_map_reveal.run_if(in_state(RunState::MagicMapReveal)),
+        );
+    app
+}
+
+fn setup(mut commands: Commands) {
+    commands.spawn(Camera2d);
+}
+
+fn handle_exit(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut exit: EventWriter<AppExit>,
+    state: Res<State<RunState>>,
+    map: Res<map::Map>,
+    game_log: Res<gamelog::GameLog>,
+    journey: Res<journey::ReturnJourney>,
+    run_stats: Res<run_stats::RunStats>,
+    player_query: Query<
+        (
+            Entity,
+            &map::Position,
+            &components::Name,
+            &combat::CombatStats,
+            &viewshed::Viewshed,
+            &components::HungerClock,
+        ),
+        With<player::Player>,
+    >,
+    monster_query: Query<
+        (
+            &map::Position,
+            &components::Name,
+            &combat::CombatStats,
+            &viewshed::Viewshed,
+            &Text2d,
+            Option<&components::Confusion>,
+            Option<&map::Footprint>,
+            Has<components::OpensDoors>,
+        ),
+        With<monsters::Monster>,
+    >,
+    item_query: Query<
+        (
+            Entity,
+            &components::Name,
+            &Text2d,
+            &TextColor,
+            Option<&map::Position>,
+            Option<&components::InBackpack>,
+            Option<&components::Consumable>,
+            Option<&components::ProvidesHealing>,
+            Option<&components::ProvidesFood>,
+            Option<&components::Ranged>,
+            Option<&components::InflictsDamage>,
+            Option<&components::AreaOfEffect>,
+            Option<&components::Targeting>,
+            Option<&components::CausesConfusion>,
+            (
+                Option<&components::MagicMapper>,
+                Option<&components::HeartOfTheAbyss>,
+                Option<&components::Key>,
+            ),
+        ),
+        With<components::Item>,
+    >,
+    trap_query: Query<
+        (
+            &map::Position,
+            &components::Name,
+            &Text2d,
+            &TextColor,
+            &components::InflictsDamage,
+            Option<&components::Hidden>,
+            Option<&components::SingleActivation>,
+        ),
+        With<components::EntryTrigger>,
+    >,
+    npc_query: Query<(&map::Position, &components::Name), With<components::Npc>>,
+    door_query: Query<(&map::Position, &components::Door, Has<components::SecretDoor>)>,
+    lever_query: Query<(&map::Position, &components::Lever)>,
+) {
+    if keyboard.just_released(KeyCode::KeyQ) {
+        // Only save if we're in-game (not in MainMenu, Victory, HighScores or Options) and player is alive
+        if !matches!(
+            state.get(),
+            RunState::MainMenu | RunState::Victory | RunState::HighScores | RunState::Options
+        ) {
+            let player_alive = player_query
+                .get_single()
+                .map(|(_, _, _, stats, _, _)| stats.hp > 0)
+                .unwrap_or(false);
+
+            if player_alive {
+                saveload::save_game(
+                    map,
+                    game_log,
+                    journey,
+                    run_stats,
+                    player_query,
+                    monster_query,
+                    item_query,
+                    trap_query,
+                    npc_query,
+                    door_query,
+                    lever_query,
+                );
+            }
+        }
+        exit.send(AppExit::Success);
+    }
+}
+
+fn transition_to_awaiting_input(
+    mut next_state: ResMut<NextState<RunState>>,
+    player_query: Query<&combat::CombatStats, With<player::Player>>,
+) {
+    // Don't transition if player is dead (GameOver state should take priority)
+    if let Ok(stats) = player_query.get_single() {
+        if stats.hp > 0 {
+            next_state.set(RunState::AwaitingInput);
+        }
+    }
+}
+
+fn transition_to_monster_turn(
+    mut next_state: ResMut<NextState<RunState>>,
+    mut pending_magic_map: ResMut<PendingMagicMap>,
+    player_query: Query<&combat::CombatStats, With<player::Player>>,
+) {
+    // Don't transition if player is dead (GameOver state should take priority)
+    if let Ok(stats) = player_query.get_single() {
+        if stats.hp > 0 {
+            // Check if magic map reveal is pending
+            if pending_magic_map.0 {
+                pending_magic_map.0 = false;
+                next_state.set(RunState::MagicMapReveal);
+            } else {
+                next_state.set(RunState::MonsterTurn);
+            }
+        }
+    }
+}
+
+fn change_level(
+    mut commands: Commands,
+    mut map: ResMut<map::Map>,
+    mut gamelog: ResMut<gamelog::GameLog>,
+    state: Res<State<RunState>>,
+    mut next_state: ResMut<NextState<RunState>>,
+    font: Res<resources::UiFont>,
+    mut rng: ResMut<rng::GameRng>,
+    journey: Res<journey::ReturnJourney>,
+    mut player_query: Query<(Entity, &mut combat::CombatStats), With<player::Player>>,
+    backpack_query: Query<(Entity, &components::InBackpack, Has<components::Key>)>,
+    entities_to_delete: Query<
+        Entity,
+        Or<(
+            With<monsters::Monster>,
+            With<components::Item>,
+            With<components::EntryTrigger>,
+            With<components::Door>,
+            With<components::Lever>,
+            With<components::Npc>,
+            With<lighting::LightSource>,
+        )>,
+    >,
+) {
+    let ascending = *state.get() == RunState::PreviousLevel;
+    let new_depth = if ascending { map.depth - 1 } else { map.depth + 1 };
+
+    // Get player entity and items in their backpack
+    let Ok((player_entity, mut player_stats)) = player_query.get_single_mut() else {
+        return;
+    };
+    // Keys only fit locks on the level they were found on, so they stay behind
+    let player_items: Vec<Entity> = backpack_query
+        .iter()
+        .filter(|(_, backpack, key)| backpack.owner == player_entity && !key)
+        .map(|(entity, _, _)| entity)
+        .collect();
+
+    // Delete all entities except player and their backpack items
+    for entity in &entities_to_delete {
+        if entity != player_entity && !player_items.contains(&entity) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+
+    // Generate the new map using the default builder
+    let mut builder = map_builders::default_builder(new_depth);
+    builder.set_spawn_depth(journey.spawn_depth(new_depth));
+    builder.build_map(&mut rng);
+    *map = builder.get_map();
+
+    let text_font = TextFont {
+        font: font.0.clone(),
+        font_size: map::FONT_SIZE,
+        ..default()
+    };
+
+    // Spawn monsters and items via builder
+    builder.spawn_entities(&mut commands, &mut rng, &text_font);
+    lighting::spawn_level_lights(&mut commands, &map, &text_font);
+    let mut level_info = builder.get_level_info();
+    if ascending && new_depth == journey::SURFACE_DEPTH {
+        level_info.arrival = Some(
+            "You climb out of the Abyss into the daylight, the Heart in your hands. The townsfolk \
+             come out of their doors to see it."
+                .to_string(),
+        );
+    }
+
+    // Move player to starting position; coming up, the player arrives on the down stairs
+    let down_stairs = map
+        .tiles
+        .iter()
+        .position(|tile| *tile == map::TileType::DownStairs);
+    let (player_x, player_y) = match down_stairs {
+        Some(idx) if ascending => ((idx % MAP_WIDTH) as i32, (idx / MAP_WIDTH) as i32),
+        _ => builder.get_starting_position(),
+    };
+    commands.entity(player_entity).insert(map::Position {
+        x: player_x,
+        y: player_y,
+    });
+
+    // Heal player (restore up to 50% of max HP)
+    let heal_amount = player_stats.max_hp / 2;
+    player_stats.hp = (player_stats.hp + heal_amount).min(player_stats.max_hp);
+
+    // Mark player's viewshed as dirty to recalculate visibility
+    commands.entity(player_entity).insert(viewshed::Viewshed::default());
+
+    if new_depth == journey::SURFACE_DEPTH {
+        gamelog.log(gamelog::LogCategory::System, "You climb back up into the town.");
+    } else {
+        gamelog.log(
+            gamelog::LogCategory::System,
+            format!(
+                "You {} to level {}. You feel slightly rejuvenated.",
+                if ascending { "climb" } else { "descend" },
+                new_depth
+            ),
+        );
+    }
+    if let Some(feeling) = &level_info.feeling {
+        gamelog.log(gamelog::LogCategory::System, feeling.clone());
+    }
+    commands.insert_resource(level_info);
+    commands.insert_resource(builder.get_region_graph());
+
+    next_state.set(RunState::LevelTransition);
+}
+
+fn reset_magic_map_row(mut reveal_row: ResMut<MagicMapRevealRow>) {
+    reveal_row.0 = 0;
+}
+
+fn magic_map_reveal(
+    mut reveal_row: ResMut<MagicMapRevealRow>,
+    mut map: ResMut<map::Map>,
+    mut next_state: ResMut<NextState<RunState>>,
+) {
+    let row = reveal_row.0;
+
+    if row >= map::MAP_HEIGHT as i32 {
+        // Done revealing, return to awaiting input
+        next_state.set(RunState::AwaitingInput);
+        return;
+    }
+
+    // Reveal all tiles in this row
+    for x in 0..map::MAP_WIDTH as i32 {
+        let idx = map.xy_idx(x, row);
+        map.revealed_tiles[idx] = true;
+    }
+
+    reveal_row.0 += 1;
+}
+
+fn setup_mapgen_visualization(
+    mut commands: Commands,
+    mut index: ResMut<MapGenIndex>,
+    mut timer: ResMut<MapGenTimer>,
+    // Despawn all text entities (except the tile map's cells) to ensure clean slate
+    text_query: Query<Entity, (With<Text2d>, Without<map::Tile>)>,
+) {
+    index.0 = 0;
+    timer.0.reset();
+
+    // Despawn any existing text entities before visualization starts
+    for entity in &text_query {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn finalize_mapgen(
+    mut commands: Commands,
+    mut spawn_data: ResMut<MapGenSpawnData>,
+    mut rng: ResMut<rng::GameRng>,
+    font: Res<resources::UiFont>,
+    map: Res<map::Map>,
+    mut preview: ResMut<tilemap::MapPreview>,
+) {
+    // Back to drawing the real map, still hidden under the fog
+    preview.0 = None;
+
+    if !spawn_data.pending {
+        return;
+    }
+
+    let text_font = TextFont {
+        font: font.0.clone(),
+        font_size: map::FONT_SIZE,
+        ..default()
+    };
+
+    // Spawn player at starting position
+    let (player_x, player_y) = spawn_data.starting_pos;
+    spawner::spawn_player(&mut commands, &text_font, player_x, player_y);
+
+    // Spawn monsters and items in rooms (skip first room - player spawn)
+    let mut monster_id: usize = 0;
+    for room in spawn_data.spawn_regions.iter().skip(1) {
+        spawner::spawn_room(
+            &mut commands,
+            &mut rng,
+            &text_font,
+            room,
+            &mut monster_id,
+            spawn_data.depth,
+        );
+    }
+    lighting::spawn_level_lights(&mut commands, &map, &text_font);
+
+    spawn_data.pending = false;
+}
+
+fn mapgen_input(
+    mut evr_kbd: EventReader<bevy::input::keyboard::KeyboardInput>,
+    mut next_state: ResMut<NextState<RunState>>,
+    mut map: ResMut<map::Map>,
+    mut rng: ResMut<rng::GameRng>,
+    mut mapgen_history: ResMut<MapGenHistory>,
+    mut spawn_data: ResMut<MapGenSpawnData>,
+    mut builder_name: ResMut<MapGenBuilderName>,
+    mut index: ResMut<MapGenIndex>,
+    mut timer: ResMut<MapGenTimer>,
+    selected_builder: Res<SelectedBuilder>,
+) {
+    use bevy::input::ButtonState;
+
+    for ev in evr_kbd.read() {
+        if ev.state != ButtonState::Pressed {
+            continue;
+        }
+
+        match ev.key_code {
+            KeyCode::Escape => {
+                // Go back to builder selection menu
+                spawn_data.pending = false;
+                next_state.set(RunState::MapBuilderSelect);
+            }
+            KeyCode::Space => {
+                // Regenerate map
+                // Generate new map using selected builder or random
+                // Preserve pending state (true for new game, false for visualizer)
+                let was_pending = spawn_data.pending;
+                let mut builder = match selected_builder.0 {
+                    Some(idx) => map_builders::builder_by_index(idx, 1),
+                    None => map_builders::default_builder(1),
+                };
+                builder_name.0 = builder.get_name().to_string();
+                builder.build_map(&mut rng);
+                *map = builder.get_map();
+                mapgen_history.0 = builder.get_snapshots();
+                spawn_data.starting_pos = builder.get_starting_position();
+                spawn_data.spawn_regions = builder.get_spawn_regions();
+                spawn_data.depth = 1;
+                spawn_data.pending = was_pending;
+
+                // Reset visualization
+                index.0 = 0;
+                timer.0.reset();
+            }
+            _ => {}
+        }
+    }
+}
+
+fn run_loop(mut app: App) -> AppExit {
+    //let mut exit_event_reader = app.world().resource_mut::<Events<AppExit>>().get_cursor();
+
+    loop {
+        //let run_state = app.world().resource::<State<RunState>>();
+        //if run_state.get() == &RunState::Running {
+        app.update();
+        //}
+
+        // Check if we got an exit event, etc...
+        if let Some(exit) = app.should_exit() {
+            return exit;
+        }
+
+        // Check if we should exit
+        {
+            //let exit_events = app.world().resource::<Events<AppExit>>();
+            //for exit in exit_event_reader.read(exit_events) {
+            //    return exit.clone();
+            //}
+
+            //if !exit_events.is_empty() {
+            //    // Return the first exit event found.
+            //    if let Some(exit) = exit_events.iter().next() {
+            //        return exit.clone();
+            //    }
+            //}
+        }
+
+        // Small sleep to avoid busy-looping (adjust as needed)
+        std::thread::sleep(Duration::from_millis(16));
+    }
+}
@@ -0,0 +1,362 @@
+use bevy::prelude::*;
+
+use crate::components::{Name, RenderOrder, RenderableBundle};
+use crate::journey::SURFACE_DEPTH;
+use crate::map::{Map, Position, TileType};
+use crate::viewshed::calculate_fov;
+use crate::RunState;
+
+/// Below this light level nothing further than a step away can be made out
+pub const DARK_THRESHOLD: f32 = 0.25;
+
+/// Dimmest tint applied to a color, so unlit tiles fade out rather than turn black
+const MIN_TINT: f32 = 0.2;
+
+#[derive(Component, Clone, Copy, Debug)]
+pub struct LightSource {
+    pub radius: i32,
+    pub color: Color,
+    /// Exponent applied to the linear falloff: 1.0 fades evenly, higher values drop off faster
+    pub falloff: f32,
+}
+
+impl LightSource {
+    /// The light the player carries
+    pub fn lantern() -> Self {
+        Self {
+            radius: 5,
+            color: Color::srgb(1.0, 0.9, 0.7),
+            falloff: 1.0,
+        }
+    }
+
+    pub fn torch() -> Self {
+        Self {
+            radius: 6,
+            color: Color::srgb(1.0, 0.65, 0.3),
+            falloff: 1.5,
+        }
+    }
+
+    pub fn fungus() -> Self {
+        Self {
+            radius: 3,
+            color: Color::srgb(0.3, 1.0, 0.5),
+            falloff: 1.0,
+        }
+    }
+
+    /// Brightness `distance` tiles away: 1.0 at the source, fading to nothing past the radius
+    pub fn intensity(&self, distance: f32) -> f32 {
+        let t = (1.0 - distance / (self.radius as f32 + 1.0)).max(0.0);
+        t.powf(self.falloff)
+    }
+}
+
+/// The unlit color of a tile or entity, captured the first time lighting sees it
+#[derive(Component, Debug)]
+pub struct BaseColor(pub Color);
+
+/// Light reaching every tile of the current map, as per-channel color multipliers
+#[derive(Resource, PartialEq)]
+pub struct LightMap {
+    width: i32,
+    height: i32,
+    pub ambient: f32,
+    light: Vec<[f32; 3]>,
+}
+
+impl Default for LightMap {
+    fn default() -> Self {
+        Self {
+            width: 0,
+            height: 0,
+            ambient: 1.0,
+            light: Vec::new(),
+        }
+    }
+}
+
+impl LightMap {
+    /// Ambient light plus every source, each cast with the FOV algorithm so walls throw shadows
+    pub fn compute<'a>(
+        map: &Map,
+        sources: impl Iterator<Item = (i32, i32, &'a LightSource)>,
+    ) -> Self {
+        let ambient = ambient_light(map.depth);
+        let mut light = vec![[ambient; 3]; (map.width * map.height) as usize];
+
+        for (x, y, source) in sources {
+            let color = source.color.to_srgba();
+            let lit = calculate_fov(x, y, source.radius, map.width, map.height, |x, y| {
+                map.is_opaque(x, y)
+            });
+            for (tx, ty) in lit.iter() {
+                let distance = (((tx - x).pow(2) + (ty - y).pow(2)) as f32).sqrt();
+                let intensity = source.intensity(distance);
+                let tile = &mut light[map.xy_idx(tx, ty)];
+                tile[0] = (tile[0] + color.red * intensity).min(1.0);
+                tile[1] = (tile[1] + color.green * intensity).min(1.0);
+                tile[2] = (tile[2] + color.blue * intensity).min(1.0);
+            }
+        }
+
+        Self {
+            width: map.width,
+            height: map.height,
+            ambient,
+            light,
+        }
+    }
+
+    /// Light at a tile; off the map there is only ambient light
+    pub fn light_at(&self, x: i32, y: i32) -> [f32; 3] {
+        if x < 0 || x >= self.width || y < 0 || y >= self.height {
+            return [self.ambient; 3];
+        }
+        self.light[(y * self.width + x) as usize]
+    }
+
+    /// The brightest channel of the light at a tile
+    pub fn brightness(&self, x: i32, y: i32) -> f32 {
+        let [r, g, b] = self.light_at(x, y);
+        r.max(g).max(b)
+    }
+
+    pub fn is_dark(&self, x: i32, y: i32) -> bool {
+        self.brightness(x, y) < DARK_THRESHOLD
+    }
+
+    /// A color as it appears under the light at a tile, keeping its alpha
+    pub fn tint(&self, base: Color, x: i32, y: i32) -> Color {
+        let base = base.to_srgba();
+        let [r, g, b] = self.light_at(x, y);
+        Color::srgba(
+            base.red * r.max(MIN_TINT),
+            base.green * g.max(MIN_TINT),
+            base.blue * b.max(MIN_TINT),
+            base.alpha,
+        )
+    }
+}
+
+/// Ambient light by depth: broad daylight in town, some still leaks into the cellars, the Dark
+/// Realm has none
+pub fn ambient_light(depth: i32) -> f32 {
+    match depth {
+        ..=SURFACE_DEPTH => 1.0,
+        1..=2 => 0.7,
+        3..=5 => 0.45,
+        6..=7 => 0.2,
+        8..=9 => 0.3,
+        10..=11 => 0.1,
+        _ => 0.25,
+    }
+}
+
+/// Chance per thousand of a wall torch on a wall bordering the floor
+fn torch_chance(depth: i32) -> u64 {
+    match depth {
+        ..=SURFACE_DEPTH => 0,
+        1..=2 => 25,
+        3..=5 => 15,
+        6..=9 => 5,
+        10..=11 => 0,
+        _ => 10,
+    }
+}
+
+/// Chance per thousand of glowing fungi on a floor tile
+fn fungus_chance(depth: i32) -> u64 {
+    match depth {
+        6..=7 => 12,
+        3..=5 | 8..=9 => 2,
+        _ => 0,
+    }
+}
+
+/// A stable per-tile roll in 0..1000, so a level gets the same lights after a reload
+fn tile_roll(depth: i32, idx: usize) -> u64 {
+    let mut h = (idx as u64) ^ ((depth as u64) << 32);
+    h = (h ^ (h >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    h = (h ^ (h >> 27)).wrapping_mul(0x94d049bb133111eb);
+    (h ^ (h >> 31)) % 1000
+}
+
+/// Places wall torches and glowing fungi for the current map
+pub fn spawn_level_lights(commands: &mut Commands, map: &Map, font: &TextFont) {
+    for (idx, tile) in map.tiles.iter().enumerate() {
+        let x = idx as i32 % map.width;
+        let y = idx as i32 / map.width;
+        let roll = tile_roll(map.depth, idx);
+
+        match tile {
+            TileType::Wall if map.is_adjacent_to_floor(x, y) && roll < torch_chance(map.depth) => {
+                commands.spawn((
+                    Name {
+                        name: "Torch".to_string(),
+                    },
+                    Position { x, y },
+                    LightSource::torch(),
+                ));
+            }
+            TileType::Floor if roll < fungus_chance(map.depth) => {
+                commands.spawn((
+                    Name {
+                        name: "Glowing Fungus".to_string(),
+                    },
+                    Position { x, y },
+                    LightSource::fungus(),
+                    RenderableBundle::new(
+                        "\"",
+                        Color::srgb(0.3, 1.0, 0.5),
+                        Color::BLACK,
+                        RenderOrder::ITEM,
+                        font,
+                    ),
+                ));
+            }
+            _ => {}
+        }
+    }
+}
+
+pub struct LightingPlugin;
+
+impl Plugin for LightingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LightMap>().add_systems(
+            Update,
+            (update_light_map, capture_base_colors, apply_lighting)
+                .chain()
+                .after(crate::map_indexing::map_indexing_system)
+                .run_if(not(in_state(RunState::MapGeneration))),
+        );
+    }
+}
+
+/// Recasts every light when one moves, appears or goes out, or when the map changes (a door
+/// opening lets light through). Only a different result counts as a change for the systems
+/// that draw with it.
+fn update_light_map(
+    map: Res<Map>,
+    mut light_map: ResMut<LightMap>,
+    sources: Query<(&Position, &LightSource)>,
+    changed: Query<(), (With<LightSource>, Or<(Changed<Position>, Changed<LightSource>)>)>,
+    mut removed: RemovedComponents<LightSource>,
+) {
+    let removed = removed.read().count() > 0;
+    if !map.is_changed() && changed.is_empty() && !removed {
+        return;
+    }
+    light_map.set_if_neq(LightMap::compute(
+        &map,
+        sources.iter().map(|(pos, light)| (pos.x, pos.y, light)),
+    ));
+}
+
+fn capture_base_colors(
+    mut commands: Commands,
+    query: Query<(Entity, &TextColor), (With<RenderOrder>, Without<BaseColor>)>,
+) {
+    for (entity, color) in &query {
+        commands.entity(entity).insert(BaseColor(color.0));
+    }
+}
+
+/// Tints what moved, or everything when the light changed, leaving colors that come out the
+/// same untouched so nothing is redrawn for them
+fn apply_lighting(
+    light_map: Res<LightMap>,
+    mut query: Query<(Ref<Position>, Ref<BaseColor>, &mut TextColor)>,
+) {
+    let relight_all = light_map.is_changed();
+    for (pos, base, mut color) in &mut query {
+        if !relight_all && !pos.is_changed() && !base.is_changed() {
+            continue;
+        }
+        // Alpha belongs to fog of war, so only the color channels come from the light
+        let alpha = color.0.alpha();
+        let lit = light_map.tint(base.0, pos.x, pos.y).with_alpha(alpha);
+        if color.0 != lit {
+            color.0 = lit;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_light_falls_off_and_is_blocked_by_walls() {
+        // Deep enough that ambient light is low, with a wall splitting the map at x = 10
+        let mut map = Map::new(20, 5, 10);
+        for idx in 0..map.tiles.len() {
+            map.tiles[idx] = TileType::Floor;
+        }
+        for y in 0..5 {
+            let idx = map.xy_idx(10, y);
+            map.tiles[idx] = TileType::Wall;
+        }
+
+        let torch = LightSource {
+            radius: 6,
+            color: Color::WHITE,
+            falloff: 1.0,
+        };
+        let light_map = LightMap::compute(&map, [(7, 2, &torch)].into_iter());
+
+        assert!(light_map.brightness(7, 2) > light_map.brightness(5, 2));
+        assert!(light_map.brightness(5, 2) > light_map.brightness(2, 2));
+        assert!(!light_map.is_dark(9, 2));
+        // The wall itself is lit, but nothing behind it
+        assert!(!light_map.is_dark(10, 2));
+        assert!(light_map.is_dark(11, 2));
+        assert_eq!(light_map.brightness(11, 2), ambient_light(10));
+    }
+
+    #[test]
+    fn test_tint_keeps_alpha_and_never_goes_black() {
+        let map = Map::new(5, 5, 10);
+        let light_map = LightMap::compute(&map, std::iter::empty());
+
+        let tinted = light_map
+            .tint(Color::srgba(1.0, 1.0, 1.0, 0.5), 2, 2)
+            .to_srgba();
+        assert_eq!(tinted.alpha, 0.5);
+        assert_eq!(tinted.red, MIN_TINT);
+    }
+
+    #[test]
+    fn test_colors_are_only_touched_when_something_changes() {
+        let mut map = Map::new(20, 5, 10);
+        for idx in 0..map.tiles.len() {
+            map.tiles[idx] = TileType::Floor;
+        }
+        let torch = LightSource {
+            radius: 6,
+            color: Color::WHITE,
+            falloff: 1.0,
+        };
+        let mut app = App::new();
+        app.insert_resource(LightMap::compute(&map, [(2, 2, &torch)].into_iter()))
+            .add_systems(Update, apply_lighting);
+        let entity = app
+            .world_mut()
+            .spawn((Position { x: 3, y: 2 }, BaseColor(Color::WHITE), TextColor(Color::WHITE)))
+            .id();
+        let last_changed = |app: &App| app.world().entity(entity).get_ref::<TextColor>().unwrap().last_changed();
+
+        app.update();
+        let lit = last_changed(&app);
+        app.update();
+        app.update();
+        assert_eq!(last_changed(&app), lit);
+
+        // Walking off into the dark tints it again
+        app.world_mut().get_mut::<Position>(entity).unwrap().x = 15;
+        app.update();
+        assert_ne!(last_changed(&app), lit);
+    }
+}
@@ -1,12 +1,16 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use bevy::prelude::*;
+use bevy::render::view::RenderLayers;
 
 use crate::camera::Camera;
 use crate::components::{Hidden, RenderOrder};
 use crate::debug::DebugState;
 use crate::distance::DistanceAlg;
+use crate::lighting::LightMap;
 use crate::player::Player;
+use crate::theme::Theme;
+use crate::tileset::HIDDEN_LAYER;
 use crate::viewshed::Viewshed;
 use crate::RunState;
 
@@ -25,6 +29,40 @@ pub struct Position {
     pub y: i32,
 }
 
+/// How many tiles across a large monster is. Its Position is the top-left tile of a square this
+/// size, and its glyph has a line per row of the square. Without one, an entity covers one tile.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Footprint(pub i32);
+
+impl Default for Footprint {
+    fn default() -> Self {
+        Footprint(1)
+    }
+}
+
+impl Footprint {
+    /// Every tile covered with the top-left corner at `pos`
+    pub fn cells(self, pos: Position) -> impl Iterator<Item = (i32, i32)> {
+        (0..self.0).flat_map(move |dy| (0..self.0).map(move |dx| (pos.x + dx, pos.y + dy)))
+    }
+
+    /// Chebyshev distance from the nearest covered tile to (x, y); 1 means next to it
+    pub fn distance_to(self, pos: Position, x: i32, y: i32) -> i32 {
+        let dx = (pos.x - x).max(x - (pos.x + self.0 - 1)).max(0);
+        let dy = (pos.y - y).max(y - (pos.y + self.0 - 1)).max(0);
+        dx.max(dy)
+    }
+}
+
+/// The tiles a glyph is drawn on: one per character, a line of the text per row
+pub fn glyph_cells(pos: Position, glyph: &str) -> impl Iterator<Item = (i32, i32, char)> + '_ {
+    glyph.lines().enumerate().flat_map(move |(dy, row)| {
+        row.chars()
+            .enumerate()
+            .map(move |(dx, c)| (pos.x + dx as i32, pos.y + dy as i32, c))
+    })
+}
+
 #[derive(Component)]
 pub struct Tile;
 
@@ -80,18 +118,75 @@ pub enum TileType {
     Floor,
     Wall,
     DownStairs,
+    UpStairs,
+    ShallowWater,
+    DeepWater,
+    Lava,
+    Chasm,
+    Bridge,
+    Grass,
+    Gravel,
+    Stalagmite,
+    WoodFloor,
+    Road,
+}
+
+impl TileType {
+    /// Whether anything can stand on this tile
+    pub fn is_walkable(self) -> bool {
+        !matches!(
+            self,
+            TileType::Wall
+                | TileType::DeepWater
+                | TileType::Lava
+                | TileType::Chasm
+                | TileType::Stalagmite
+        )
+    }
+
+    /// Whether this tile blocks sight and light
+    pub fn is_opaque(self) -> bool {
+        matches!(self, TileType::Wall | TileType::Stalagmite)
+    }
+
+    /// Cost of stepping onto this tile, relative to bare floor
+    pub fn movement_cost(self) -> f32 {
+        match self {
+            TileType::Road => 0.8,
+            TileType::Grass => 1.1,
+            TileType::Gravel => 1.2,
+            TileType::ShallowWater => 1.5,
+            _ => 1.0,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TileType::Floor => "Floor",
+            TileType::Wall => "Wall",
+            TileType::DownStairs => "Stairs",
+            TileType::UpStairs => "Up Stairs",
+            TileType::ShallowWater => "Shallow Water",
+            TileType::DeepWater => "Deep Water",
+            TileType::Lava => "Lava",
+            TileType::Chasm => "Chasm",
+            TileType::Bridge => "Bridge",
+            TileType::Grass => "Grass",
+            TileType::Gravel => "Gravel",
+            TileType::Stalagmite => "Stalagmite",
+            TileType::WoodFloor => "Wooden Floor",
+            TileType::Road => "Road",
+        }
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RevealedState {
     Explored,
     Hidden,
     Visible,
 }
 
-#[derive(Component)]
-pub struct Revealed(pub RevealedState);
-
 #[derive(Component)]
 pub struct BloodstainMarker;
 
@@ -107,6 +202,8 @@ pub struct Map {
     pub view_blocked: HashSet<usize>,
     pub tile_content: Vec<Vec<Entity>>,
     pub bloodstains: HashSet<usize>,
+    /// The glyph and color of what was last seen standing on each tile, for tiles out of view
+    pub remembered: HashMap<usize, (char, Color)>,
 }
 
 impl Map {
@@ -123,6 +220,7 @@ impl Map {
             view_blocked: HashSet::new(),
             tile_content: vec![Vec::new(); size],
             bloodstains: HashSet::new(),
+            remembered: HashMap::new(),
         }
     }
 
@@ -130,13 +228,24 @@ impl Map {
         (y as usize * self.width as usize) + x as usize
     }
 
-    /// Check if a wall at (x, y) is adjacent to at least one floor tile (including diagonals)
+    /// Whether a tile is in view, remembered from earlier, or never seen
+    pub fn revealed_state(&self, idx: usize) -> RevealedState {
+        if self.visible_tiles[idx] {
+            RevealedState::Visible
+        } else if self.revealed_tiles[idx] {
+            RevealedState::Explored
+        } else {
+            RevealedState::Hidden
+        }
+    }
+
+    /// Check if a wall at (x, y) is adjacent to at least one non-wall tile (including diagonals)
     pub fn is_adjacent_to_floor(&self, x: i32, y: i32) -> bool {
         let check = |tx: i32, ty: i32| -> bool {
             if tx < 0 || tx >= self.width || ty < 0 || ty >= self.height {
                 return false;
             }
-            self.tiles[self.xy_idx(tx, ty)] == TileType::Floor
+            self.tiles[self.xy_idx(tx, ty)] != TileType::Wall
         };
         // Cardinal directions
         check(x, y - 1) || check(x, y + 1) || check(x - 1, y) || check(x + 1, y) ||
@@ -150,17 +259,28 @@ impl Map {
         }
         let idx = self.xy_idx(x, y);
 
-        !self.blocked_tiles[idx]
+        self.tiles[idx].is_walkable() && !self.blocked_tiles[idx]
     }
 
-    /// Check if a tile is walkable (ignores entities, only checks walls)
+    /// Check if a tile is walkable (ignores entities, only checks terrain)
     fn is_walkable(&self, x: i32, y: i32) -> bool {
         if x < 1 || x > self.width - 1 || y < 1 || y > self.height - 1 {
             return false;
         }
         let idx = self.xy_idx(x, y);
 
-        self.tiles[idx] != TileType::Wall
+        self.tiles[idx].is_walkable()
+    }
+
+    /// Whether a tile blocks sight and light: opaque terrain, plus anything with BlocksVisibility
+    pub fn is_opaque(&self, x: i32, y: i32) -> bool {
+        let idx = self.xy_idx(x, y);
+        self.tiles[idx].is_opaque() || self.view_blocked.contains(&idx)
+    }
+
+    /// Path cost of stepping onto a tile, scaled by its terrain
+    fn step_cost(&self, idx: usize, base: f32) -> f32 {
+        base * self.tiles[idx].movement_cost()
     }
 
     /// Get available exits ignoring entity blocking (for pathfinding)
@@ -172,30 +292,30 @@ impl Map {
 
         // Cardinal directions
         if self.is_walkable(x - 1, y) {
-            exits.push((idx - 1, 1.0))
+            exits.push((idx - 1, self.step_cost(idx - 1, 1.0)))
         };
         if self.is_walkable(x + 1, y) {
-            exits.push((idx + 1, 1.0))
+            exits.push((idx + 1, self.step_cost(idx + 1, 1.0)))
         };
         if self.is_walkable(x, y - 1) {
-            exits.push((idx - w, 1.0))
+            exits.push((idx - w, self.step_cost(idx - w, 1.0)))
         };
         if self.is_walkable(x, y + 1) {
-            exits.push((idx + w, 1.0))
+            exits.push((idx + w, self.step_cost(idx + w, 1.0)))
         };
 
         // Diagonal directions
         if self.is_walkable(x - 1, y - 1) {
-            exits.push(((idx - w) - 1, 1.45))
+            exits.push(((idx - w) - 1, self.step_cost((idx - w) - 1, 1.45)))
         };
         if self.is_walkable(x + 1, y - 1) {
-            exits.push(((idx - w) + 1, 1.45))
+            exits.push(((idx - w) + 1, self.step_cost((idx - w) + 1, 1.45)))
         };
         if self.is_walkable(x - 1, y + 1) {
-            exits.push(((idx + w) - 1, 1.45))
+            exits.push(((idx + w) - 1, self.step_cost((idx + w) - 1, 1.45)))
         };
         if self.is_walkable(x + 1, y + 1) {
-            exits.push(((idx + w) + 1, 1.45))
+            exits.push(((idx + w) + 1, self.step_cost((idx + w) + 1, 1.45)))
         };
 
         exits
@@ -209,35 +329,79 @@ impl Map {
 
         // Cardinal directions
         if self.is_exit_valid(x - 1, y) {
-            exits.push((idx - 1, 1.0))
+            exits.push((idx - 1, self.step_cost(idx - 1, 1.0)))
         };
         if self.is_exit_valid(x + 1, y) {
-            exits.push((idx + 1, 1.0))
+            exits.push((idx + 1, self.step_cost(idx + 1, 1.0)))
         };
         if self.is_exit_valid(x, y - 1) {
-            exits.push((idx - w, 1.0))
+            exits.push((idx - w, self.step_cost(idx - w, 1.0)))
         };
         if self.is_exit_valid(x, y + 1) {
-            exits.push((idx + w, 1.0))
+            exits.push((idx + w, self.step_cost(idx + w, 1.0)))
         };
 
         // Diagonal directions
         if self.is_exit_valid(x - 1, y - 1) {
-            exits.push(((idx - w) - 1, 1.45))
+            exits.push(((idx - w) - 1, self.step_cost((idx - w) - 1, 1.45)))
         };
         if self.is_exit_valid(x + 1, y - 1) {
-            exits.push(((idx - w) + 1, 1.45))
+            exits.push(((idx - w) + 1, self.step_cost((idx - w) + 1, 1.45)))
         };
         if self.is_exit_valid(x - 1, y + 1) {
-            exits.push(((idx + w) - 1, 1.45))
+            exits.push(((idx + w) - 1, self.step_cost((idx + w) - 1, 1.45)))
         };
         if self.is_exit_valid(x + 1, y + 1) {
-            exits.push(((idx + w) + 1, 1.45))
+            exits.push(((idx + w) + 1, self.step_cost((idx + w) + 1, 1.45)))
         };
 
         exits
     }
 
+    /// For every tile, how large a square of walkable terrain has its top-left corner there. A
+    /// monster with a Footprint fits wherever this is at least its size.
+    pub fn clearance(&self) -> Vec<i32> {
+        let mut clearance = vec![0; self.tiles.len()];
+        for y in (0..self.height).rev() {
+            for x in (0..self.width).rev() {
+                if !self.is_walkable(x, y) {
+                    continue;
+                }
+                let at = |x: i32, y: i32| {
+                    if x < self.width && y < self.height {
+                        clearance[self.xy_idx(x, y)]
+                    } else {
+                        0
+                    }
+                };
+                let size = 1 + at(x + 1, y).min(at(x, y + 1)).min(at(x + 1, y + 1));
+                clearance[self.xy_idx(x, y)] = size;
+            }
+        }
+        clearance
+    }
+
+    /// Exits for a monster `size` tiles across standing with its top-left corner at `idx`,
+    /// ignoring entities
+    pub fn get_available_exits_for_size(&self, idx: usize, clearance: &[i32], size: i32) -> Vec<(usize, f32)> {
+        let x = idx as i32 % self.width;
+        let y = idx as i32 / self.width;
+
+        let mut exits = Vec::new();
+        for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1), (-1, -1), (1, -1), (-1, 1), (1, 1)] {
+            let (nx, ny) = (x + dx, y + dy);
+            if nx < 0 || nx >= self.width || ny < 0 || ny >= self.height {
+                continue;
+            }
+            let neighbor = self.xy_idx(nx, ny);
+            if clearance[neighbor] >= size {
+                let base = if dx != 0 && dy != 0 { 1.45 } else { 1.0 };
+                exits.push((neighbor, self.step_cost(neighbor, base)));
+            }
+        }
+        exits
+    }
+
     pub fn get_pathing_distance(&self, idx1: usize, idx2: usize) -> f32 {
         let w = self.width as usize;
         let p1 = Vec2::new((idx1 % w) as f32, (idx1 / w) as f32);
@@ -248,7 +412,7 @@ impl Map {
 
     pub fn populate_blocked(&mut self) {
         for (i, tile) in self.tiles.iter().enumerate() {
-            self.blocked_tiles[i] = *tile == TileType::Wall;
+            self.blocked_tiles[i] = !tile.is_walkable();
         }
     }
 
@@ -260,12 +424,12 @@ impl Map {
 
     /// Calculate wall glyph for a given position based on neighbors
     pub fn wall_glyph_at(&self, x: i32, y: i32) -> WallGlyph {
-        // Check if a tile is a floor
+        // Check if a tile is open (anything but solid wall)
         let is_floor_at = |tx: i32, ty: i32| -> bool {
             if tx < 0 || tx >= self.width || ty < 0 || ty >= self.height {
                 return false;
             }
-            self.tiles[self.xy_idx(tx, ty)] == TileType::Floor
+            self.tiles[self.xy_idx(tx, ty)] != TileType::Wall
         };
 
         // Check if a wall at (nx, ny) is a "boundary wall" (adjacent to at least one floor, including diagonals)
@@ -318,6 +482,9 @@ impl Plugin for MapPlugin {
         // Run condition: only during actual gameplay (not menus, game over, or map generation)
         let in_gameplay = not(in_state(RunState::MainMenu))
             .and(not(in_state(RunState::GameOver)))
+            .and(not(in_state(RunState::Victory)))
+            .and(not(in_state(RunState::HighScores)))
+            .and(not(in_state(RunState::Options)))
             .and(not(in_state(RunState::MapGeneration)));
 
         app.init_resource::<Map>().add_systems(
@@ -326,11 +493,14 @@ impl Plugin for MapPlugin {
                 // translate_positions always runs (needed for visualization too)
                 translate_positions,
                 // These only run during actual gameplay
-                update_revealed_state.run_if(in_gameplay.clone()),
-                update_revealed_tiles.run_if(in_gameplay.clone()),
-                update_visible_tiles.run_if(in_gameplay.clone()),
-                update_renderable_visibility.run_if(in_gameplay.clone()),
+                (update_revealed_tiles, update_visible_tiles)
+                    .chain()
+                    .run_if(in_gameplay.clone()),
+                (update_renderable_visibility, update_map_memory)
+                    .chain()
+                    .run_if(in_gameplay.clone()),
                 update_bloodstains.run_if(in_gameplay.clone()),
+                draw_footprint_glyphs,
             ),
         );
     }
@@ -340,65 +510,63 @@ pub fn xy_idx(x: i32, y: i32) -> usize {
     (y as usize * MAP_WIDTH) + x as usize
 }
 
-fn update_revealed_tiles(
-    mut map: ResMut<Map>,
-    debug_state: Res<DebugState>,
-    query: Query<&Viewshed, With<Player>>,
-    mut tiles_query: Query<(&Position, &mut Revealed), With<Tile>>,
-) {
+/// How a map tile is drawn. Solid rock away from any open tile isn't drawn at all.
+pub fn tile_glyph(map: &Map, x: i32, y: i32) -> Option<(char, Color)> {
+    let tile = map.tiles[map.xy_idx(x, y)];
+    let theme = Theme::for_depth(map.depth);
+    if let Some(style) = theme.tile_style(tile) {
+        return Some(style);
+    }
+    let glyph = match tile {
+        TileType::Wall if !map.is_adjacent_to_floor(x, y) => return None,
+        TileType::Wall => (map.wall_glyph_at(x, y).to_char(), theme.palette().wall),
+        TileType::Floor => ('.', Color::srgb(0.5, 0.5, 0.5)),
+        TileType::DownStairs => ('>', Color::srgb(0.0, 1.0, 1.0)),
+        TileType::UpStairs => ('<', Color::srgb(0.0, 1.0, 1.0)),
+        TileType::ShallowWater => ('~', Color::srgb(0.3, 0.6, 1.0)),
+        TileType::DeepWater => ('≈', Color::srgb(0.1, 0.2, 0.9)),
+        TileType::Lava => ('≈', Color::srgb(1.0, 0.35, 0.0)),
+        TileType::Chasm => (':', Color::srgb(0.3, 0.3, 0.5)),
+        TileType::Bridge => ('=', Color::srgb(0.6, 0.4, 0.2)),
+        TileType::Grass => ('"', Color::srgb(0.2, 0.8, 0.2)),
+        TileType::Gravel => (',', Color::srgb(0.6, 0.6, 0.55)),
+        TileType::Stalagmite => ('▲', Color::srgb(0.6, 0.5, 0.4)),
+        TileType::WoodFloor => ('.', Color::srgb(0.6, 0.45, 0.25)),
+        TileType::Road => ('≡', Color::srgb(0.7, 0.7, 0.6)),
+    };
+    Some(glyph)
+}
+
+fn update_revealed_tiles(mut map: ResMut<Map>, query: Query<&Viewshed, With<Player>>) {
     let Ok(viewshed) = query.get_single() else {
         return;
     };
 
-    for (pos, mut revealed) in &mut tiles_query {
-        let idx = map.xy_idx(pos.x, pos.y);
-        let point = (pos.x, pos.y);
-        if viewshed.visible_tiles.contains(&point) {
+    for (x, y) in viewshed.visible_tiles.iter() {
+        let idx = map.xy_idx(x, y);
+        if !map.revealed_tiles[idx] {
             map.revealed_tiles[idx] = true;
-            revealed.0 = RevealedState::Visible;
-        } else if debug_state.no_fog && map.revealed_tiles[idx] {
-            // No fog mode: keep revealed tiles fully visible
-            revealed.0 = RevealedState::Visible;
-        } else if matches!(revealed.0, RevealedState::Visible) {
-            revealed.0 = RevealedState::Explored;
-        }
-    }
-}
-
-fn update_revealed_state(mut tiles_query: Query<(&mut TextColor, &Revealed), With<Tile>>) {
-    for (mut text_color, revealed) in &mut tiles_query {
-        match revealed.0 {
-            RevealedState::Explored => {
-                text_color.0.set_alpha(0.1);
-            }
-            RevealedState::Visible => {
-                text_color.0.set_alpha(1.0);
-            }
-            RevealedState::Hidden => {
-                text_color.0.set_alpha(0.0);
-            }
         }
     }
 }
 
 fn translate_positions(
-    mut commands: Commands,
     window: Single<&Window>,
     camera: Res<Camera>,
-    query: Query<(Entity, &Position, Option<&RenderOrder>)>,
+    mut query: Query<(&Position, Option<&RenderOrder>, &mut Transform)>,
 ) {
     let half_height = window.height() / 2.;
     let half_width = window.width() / 2.;
 
-    for (entity, position, render_order) in &query {
+    for (position, render_order, mut transform) in &mut query {
         let z = render_order.map(|r| r.0 as f32 * 0.1).unwrap_or(0.0);
 
         // Convert world position to screen position using camera
         let (screen_x, screen_y) = camera.world_to_screen(position.x, position.y);
 
         // Map screen coords to pixel coords. Y runs in the opposite direction to the pixel
-        // coords. Use try_insert to handle entities that may be despawned during load.
-        commands.entity(entity).try_insert(Transform::from_xyz(
+        // coords. Only write on a change so nothing else sees every entity move each frame.
+        transform.set_if_neq(Transform::from_xyz(
             (screen_x as f32) * GRID_PX.x + (GRID_PX.x / 2.) - half_width,
             (screen_y as f32) * -GRID_PX.y - (GRID_PX.y / 2.) + half_height,
             z,
@@ -406,6 +574,54 @@ fn translate_positions(
     }
 }
 
+/// One tile of a large monster's glyph in the window, a child of the monster
+#[derive(Component)]
+struct FootprintGlyph;
+
+/// Bevy spaces lines of text further apart than the grid, so a large monster's glyph can't be
+/// drawn as one text in the window. Each tile gets a text of its own instead, over the monster's
+/// own text, which is hidden.
+fn draw_footprint_glyphs(
+    mut commands: Commands,
+    monsters: Query<(Entity, Ref<Text2d>, &TextFont, Ref<TextColor>, Option<&Children>), With<Footprint>>,
+    mut glyphs: Query<&mut TextColor, (With<FootprintGlyph>, Without<Footprint>)>,
+) {
+    for (entity, text, font, color, children) in &monsters {
+        let existing: Vec<Entity> = children
+            .into_iter()
+            .flatten()
+            .copied()
+            .filter(|child| glyphs.contains(*child))
+            .collect();
+
+        if text.is_changed() {
+            for child in existing {
+                commands.entity(child).despawn_recursive();
+            }
+            commands
+                .entity(entity)
+                .insert(RenderLayers::layer(HIDDEN_LAYER))
+                .with_children(|parent| {
+                    for (dx, dy, glyph) in glyph_cells(Position { x: 0, y: 0 }, &text.0) {
+                        parent.spawn((
+                            FootprintGlyph,
+                            Text2d::new(glyph.to_string()),
+                            font.clone(),
+                            TextColor(color.0),
+                            Transform::from_xyz(dx as f32 * GRID_PX.x, dy as f32 * -GRID_PX.y, 0.0),
+                        ));
+                    }
+                });
+        } else if color.is_changed() {
+            for child in existing {
+                if let Ok(mut glyph_color) = glyphs.get_mut(child) {
+                    glyph_color.0 = color.0;
+                }
+            }
+        }
+    }
+}
+
 fn update_visible_tiles(
     mut map: ResMut<Map>,
     debug_state: Res<DebugState>,
@@ -414,42 +630,128 @@ fn update_visible_tiles(
     let Ok(viewshed) = player.get_single() else {
         return;
     };
-    map.visible_tiles = vec![false; MAP_WIDTH * MAP_HEIGHT];
-
-    if debug_state.no_fog {
+    let visible = if debug_state.no_fog {
         // No fog mode: all revealed tiles are visible
-        for idx in 0..map.revealed_tiles.len() {
-            if map.revealed_tiles[idx] {
-                map.visible_tiles[idx] = true;
-            }
-        }
+        map.revealed_tiles.clone()
     } else {
         // Normal mode: only viewshed tiles are visible
+        let mut visible = vec![false; map.tiles.len()];
         for (pos_x, pos_y) in viewshed.visible_tiles.iter() {
-            let idx = map.xy_idx(*pos_x, *pos_y);
-            map.visible_tiles[idx] = true;
+            visible[map.xy_idx(pos_x, pos_y)] = true;
         }
+        visible
+    };
+
+    // Standing still shouldn't mark the map changed, or everything drawn from it redraws
+    if map.visible_tiles != visible {
+        map.visible_tiles = visible;
     }
 }
 
 fn update_renderable_visibility(
     map: Res<Map>,
-    mut query: Query<(&Position, &mut Visibility, Option<&Hidden>), (With<RenderOrder>, Without<Player>)>,
+    light_map: Res<LightMap>,
+    debug_state: Res<DebugState>,
+    player: Query<&Position, With<Player>>,
+    mut query: Query<
+        (&Position, Option<&Footprint>, &mut Visibility, Option<&Hidden>),
+        (With<RenderOrder>, Without<Player>),
+    >,
 ) {
-    for (pos, mut visibility, hidden) in &mut query {
+    let player_pos = player.get_single().ok();
+
+    for (pos, footprint, mut visibility, hidden) in &mut query {
         // Hidden entities are always invisible
         if hidden.is_some() {
-            *visibility = Visibility::Hidden;
+            visibility.set_if_neq(Visibility::Hidden);
             continue;
         }
 
-        let idx = map.xy_idx(pos.x, pos.y);
-        if map.visible_tiles[idx] {
-            *visibility = Visibility::Visible;
+        // A large monster shows as soon as any part of it can be made out
+        let mut cells = footprint.copied().unwrap_or_default().cells(*pos);
+        if cells.any(|(x, y)| can_make_out(&map, &light_map, debug_state.no_fog, player_pos, x, y)) {
+            visibility.set_if_neq(Visibility::Visible);
         } else {
-            *visibility = Visibility::Hidden;
+            visibility.set_if_neq(Visibility::Hidden);
+        }
+    }
+}
+
+/// Whether the player can make out what stands on a tile: it has to be in view, and things
+/// standing in the dark can only be made out from right next to them
+fn can_make_out(
+    map: &Map,
+    light_map: &LightMap,
+    no_fog: bool,
+    player_pos: Option<&Position>,
+    x: i32,
+    y: i32,
+) -> bool {
+    let adjacent = player_pos.is_some_and(|p| (p.x - x).abs() <= 1 && (p.y - y).abs() <= 1);
+    let unlit = light_map.is_dark(x, y) && !adjacent && !no_fog;
+    map.visible_tiles[map.xy_idx(x, y)] && !unlit
+}
+
+/// Remembers the topmost visible thing on every tile the player can make out, and forgets
+/// tiles that turn out to be empty. Tiles out of view keep what was last seen there.
+///
+/// Only looks again when the view changes: the player's viewshed, the visible tiles, the light,
+/// what stands where (the map's content index is part of the map), or something coming into or
+/// going out of sight.
+fn update_map_memory(
+    mut map: ResMut<Map>,
+    light_map: Res<LightMap>,
+    debug_state: Res<DebugState>,
+    player: Query<(&Position, Ref<Viewshed>), With<Player>>,
+    things: Query<(&Position, &Text2d, &TextColor, &RenderOrder, &Visibility), Without<Player>>,
+    sighted: Query<(), (Changed<Visibility>, Without<Player>)>,
+) {
+    let player = player.get_single().ok();
+    let looked = player.as_ref().is_some_and(|(_, viewshed)| viewshed.is_changed());
+    let unchanged = !map.is_changed() && !light_map.is_changed() && !debug_state.is_changed();
+    if !looked && unchanged && sighted.is_empty() {
+        return;
+    }
+    let player_pos = player.map(|(pos, _)| pos);
+
+    let mut seen: HashMap<usize, (i32, char, Color)> = HashMap::new();
+    for (pos, text, color, order, visibility) in &things {
+        if *visibility == Visibility::Hidden {
+            continue;
+        }
+        for (x, y, glyph) in glyph_cells(*pos, &text.0) {
+            if x >= map.width || y >= map.height {
+                continue;
+            }
+            let idx = map.xy_idx(x, y);
+            if seen.get(&idx).is_none_or(|(top, ..)| order.0 > *top) {
+                seen.insert(idx, (order.0, glyph, color.0));
+            }
         }
     }
+
+    // Only mark the map changed when the memory actually changed, so nothing redraws for it
+    let mut changed = false;
+    for y in 0..map.height {
+        for x in 0..map.width {
+            if !can_make_out(&map, &light_map, debug_state.no_fog, player_pos, x, y) {
+                continue;
+            }
+            let idx = map.xy_idx(x, y);
+            let now = seen.get(&idx).map(|&(_, glyph, color)| (glyph, color));
+            let memory = &mut map.bypass_change_detection().remembered;
+            if memory.get(&idx).copied() != now {
+                match now {
+                    Some(look) => memory.insert(idx, look),
+                    None => memory.remove(&idx),
+                };
+                changed = true;
+            }
+        }
+    }
+    if changed {
+        map.set_changed();
+    }
 }
 
 fn update_bloodstains(
@@ -683,4 +985,72 @@ mod tests {
         );
         assert_eq!(glyph.to_char(), '┼', "Cross should be ┼");
     }
+
+    #[test]
+    fn test_exits_respect_terrain() {
+        // Row of floor with water, road and a stalagmite around the center at (2, 2)
+        let mut map = create_test_map(5, 5);
+        for y in 1..4 {
+            for x in 1..4 {
+                let idx = map.xy_idx(x, y);
+                map.tiles[idx] = TileType::Floor;
+            }
+        }
+        let set = |map: &mut Map, x: i32, y: i32, tile: TileType| {
+            let idx = map.xy_idx(x, y);
+            map.tiles[idx] = tile;
+        };
+        set(&mut map, 1, 2, TileType::DeepWater);
+        set(&mut map, 3, 2, TileType::Road);
+        set(&mut map, 2, 1, TileType::Stalagmite);
+        map.populate_blocked();
+
+        let center = map.xy_idx(2, 2);
+        let exits = map.get_available_exits(center);
+        let cost_to = |x: i32, y: i32| {
+            exits
+                .iter()
+                .find(|(idx, _)| *idx == map.xy_idx(x, y))
+                .map(|(_, cost)| *cost)
+        };
+
+        assert_eq!(cost_to(1, 2), None, "deep water can't be entered");
+        assert_eq!(cost_to(2, 1), None, "stalagmites block movement");
+        assert_eq!(cost_to(3, 2), Some(0.8), "roads are quicker");
+        assert_eq!(cost_to(2, 3), Some(1.0));
+        assert!(map.is_opaque(2, 1));
+        assert!(!map.is_opaque(1, 2));
+    }
+
+    #[test]
+    fn test_clearance_shrinks_near_walls() {
+        let mut map = create_test_map(6, 5);
+        // A 3x2 room with its top-left corner at (1, 1)
+        for y in 1..3 {
+            for x in 1..4 {
+                let idx = map.xy_idx(x, y);
+                map.tiles[idx] = TileType::Floor;
+            }
+        }
+        let clearance = map.clearance();
+
+        assert_eq!(clearance[map.xy_idx(1, 1)], 2);
+        assert_eq!(clearance[map.xy_idx(2, 1)], 2);
+        // Against the east wall and the south wall only a single tile fits
+        assert_eq!(clearance[map.xy_idx(3, 1)], 1);
+        assert_eq!(clearance[map.xy_idx(1, 2)], 1);
+        assert_eq!(clearance[map.xy_idx(0, 0)], 0);
+    }
+
+    #[test]
+    fn test_footprint_reach_counts_from_nearest_tile() {
+        let dragon = Footprint(2);
+        let corner = Position { x: 5, y: 5 };
+
+        assert_eq!(dragon.cells(corner).count(), 4);
+        assert_eq!(dragon.distance_to(corner, 6, 6), 0);
+        // Next to the bottom-right tile, two away from the corner
+        assert_eq!(dragon.distance_to(corner, 7, 7), 1);
+        assert_eq!(dragon.distance_to(corner, 3, 5), 2);
+    }
 }
@@ -1,6 +1,9 @@
+use serde::Deserialize;
+
 use crate::map::{TileType, MAP_HEIGHT, MAP_WIDTH};
 use crate::pathfinding::dijkstra_map;
 use crate::rng::GameRng;
+use crate::theme::Theme;
 
 use super::{BuilderMap, MetaMapBuilder};
 
@@ -8,12 +11,14 @@ use super::{BuilderMap, MetaMapBuilder};
 // Area-Based Starting Position
 // ============================================================================
 
+#[derive(Clone, Copy, Debug, Deserialize)]
 pub enum XStart {
     Left,
     Center,
     Right,
 }
 
+#[derive(Clone, Copy, Debug, Deserialize)]
 pub enum YStart {
     Top,
     Center,
@@ -200,17 +205,15 @@ impl MetaMapBuilder for VoronoiSpawning {
                     let num_spawns = rng.0.gen_range(0..=2);
                     for _ in 0..num_spawns {
                         let spawn_idx = region_tiles[rng.0.gen_range(0..region_tiles.len())];
-                        let roll = rng.0.gen_range(0..100);
-                        let name = if roll < 10 {
-                            "Health Potion"
-                        } else if roll < 20 {
-                            "Magic Missile Scroll"
-                        } else if roll < 60 {
-                            "Goblin"
+                        let theme = Theme::for_depth(build_data.spawn_depth);
+                        let table = if rng.0.gen_range(0..100) < 20 {
+                            theme.item_table(build_data.spawn_depth)
                         } else {
-                            "Orc"
+                            theme.monster_table(build_data.spawn_depth)
                         };
-                        build_data.spawn_list.push((spawn_idx, name.to_string()));
+                        if let Some(name) = table.roll(rng) {
+                            build_data.spawn_list.push((spawn_idx, name));
+                        }
                     }
                 }
             }
@@ -1,7 +1,9 @@
+use serde::Deserialize;
+
 use crate::map::{Map, TileType, MAP_HEIGHT, MAP_WIDTH};
 use crate::shapes::Rect;
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
 pub enum Symmetry {
     None,
     Horizontal,
@@ -1,5 +1,6 @@
 use bevy::prelude::*;
 use rand::Rng;
+use serde::Deserialize;
 
 use crate::map::{Map, TileType, MAP_HEIGHT, MAP_WIDTH};
 use crate::pathfinding::dijkstra_map;
@@ -11,7 +12,7 @@ use crate::viewshed::bresenham_line;
 use super::common::{paint, Symmetry};
 use super::{BuilderMap, InitialMapBuilder, MapBuilder};
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
 pub enum DLAAlgorithm {
     WalkInwards,
     WalkOutwards,
@@ -31,7 +32,7 @@ pub struct DLABuilder {
 }
 
 impl DLABuilder {
-    fn new(
+    pub fn new(
         depth: i32,
         algorithm: DLAAlgorithm,
         brush_size: i32,
@@ -17,7 +17,7 @@ impl DoorPlacement {
     }
 
     /// Check if a door can be placed at this position
-    /// Valid positions have walls on opposite sides and floors on the other two sides
+    /// Valid positions have walls on opposite sides and open ground on the other two sides
     fn door_possible(build_data: &BuilderMap, idx: usize) -> bool {
         let x = (idx % MAP_WIDTH) as i32;
         let y = (idx / MAP_WIDTH) as i32;
@@ -43,17 +43,17 @@ impl DoorPlacement {
         let east = build_data.map.tiles[east_idx];
         let west = build_data.map.tiles[west_idx];
 
-        // Check for horizontal doorway (walls N+S, floors E+W)
+        // Check for horizontal doorway (walls N+S, open E+W)
         let horizontal_door = north == TileType::Wall
             && south == TileType::Wall
-            && east == TileType::Floor
-            && west == TileType::Floor;
+            && east.is_walkable()
+            && west.is_walkable();
 
-        // Check for vertical doorway (walls E+W, floors N+S)
+        // Check for vertical doorway (walls E+W, open N+S)
         let vertical_door = east == TileType::Wall
             && west == TileType::Wall
-            && north == TileType::Floor
-            && south == TileType::Floor;
+            && north.is_walkable()
+            && south.is_walkable();
 
         horizontal_door || vertical_door
     }
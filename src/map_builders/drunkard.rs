@@ -1,5 +1,6 @@
 use bevy::prelude::*;
 use rand::Rng;
+use serde::Deserialize;
 
 use crate::map::{Map, TileType, MAP_HEIGHT, MAP_WIDTH};
 use crate::pathfinding::dijkstra_map;
@@ -10,13 +11,13 @@ use crate::spawner;
 use super::common::{paint, Symmetry};
 use super::{BuilderMap, InitialMapBuilder, MapBuilder};
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, Deserialize)]
 pub enum DrunkSpawnMode {
     StartingPoint,
     Random,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, Deserialize)]
 pub struct DrunkardSettings {
     pub spawn_mode: DrunkSpawnMode,
     pub lifetime: i32,
@@ -35,7 +36,7 @@ pub struct DrunkardsWalkBuilder {
 }
 
 impl DrunkardsWalkBuilder {
-    fn new(depth: i32, settings: DrunkardSettings) -> Self {
+    pub fn new(depth: i32, settings: DrunkardSettings) -> Self {
         Self {
             map: Map::new(MAP_WIDTH, MAP_HEIGHT, depth),
             starting_position: (MAP_WIDTH as i32 / 2, MAP_HEIGHT as i32 / 2),
@@ -92,6 +92,14 @@ impl DrunkardsWalkEroder {
         })
     }
 
+    pub fn with_settings(floor_percent: f32, brush_size: i32, symmetry: Symmetry) -> Box<Self> {
+        Box::new(Self {
+            floor_percent,
+            brush_size,
+            symmetry,
+        })
+    }
+
     pub fn light() -> Box<Self> {
         Box::new(Self {
             floor_percent: 0.35,
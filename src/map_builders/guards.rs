@@ -0,0 +1,88 @@
+use rand::Rng;
+
+use crate::map::TileType;
+use crate::rng::GameRng;
+use crate::theme::Theme;
+
+use super::{BuilderMap, MetaMapBuilder};
+
+// ============================================================================
+// ChokePointGuards - Monsters posted where the way down can't be walked around
+// ============================================================================
+
+/// Posts a monster in every passage on the critical path that is the only way between the two
+/// sides of the map, so the way from the start to the stairs down is watched where the player
+/// has to go through. Passages with nowhere free to stand are left unguarded.
+pub struct ChokePointGuards;
+
+impl ChokePointGuards {
+    pub fn new() -> Box<Self> {
+        Box::new(Self)
+    }
+}
+
+impl MetaMapBuilder for ChokePointGuards {
+    fn build_map(&mut self, rng: &mut GameRng, build_data: &mut BuilderMap) {
+        let graph = build_data.region_graph().clone();
+        let depth = build_data.spawn_depth;
+        let table = Theme::for_depth(depth).monster_table(depth);
+        let path = graph.critical_path();
+        let on_path = |from: usize, to: usize| {
+            path.windows(2)
+                .any(|step| step == [from, to] || step == [to, from])
+        };
+
+        for i in graph.choke_points() {
+            let passage = &graph.passages[i];
+            if !on_path(passage.from, passage.to) {
+                continue;
+            }
+            let spots: Vec<usize> = passage
+                .tiles
+                .iter()
+                .copied()
+                .filter(|&idx| {
+                    build_data.map.tiles[idx] == TileType::Floor
+                        && !build_data.spawn_list.iter().any(|&(taken, _)| taken == idx)
+                })
+                .collect();
+            if spots.is_empty() {
+                continue;
+            }
+            let idx = spots[rng.0.gen_range(0..spots.len())];
+            if let Some(name) = table.roll(rng) {
+                build_data.spawn_list.push((idx, name));
+            }
+        }
+        build_data.take_snapshot();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map_builders::regions::tests::rooms_in_a_row;
+
+    #[test]
+    fn test_guards_stand_in_the_corridors_to_the_stairs() {
+        let (map, rooms) = rooms_in_a_row();
+        let mut build_data = BuilderMap::new(3);
+        build_data.map = map;
+        build_data.starting_position = Some((4, 4));
+        build_data.rooms = Some(rooms);
+
+        ChokePointGuards.build_map(&mut GameRng::seeded(1), &mut build_data);
+        let mut rows: Vec<(i32, bool)> = build_data
+            .spawn_list
+            .iter()
+            .map(|&(idx, _)| {
+                let x = idx as i32 % build_data.map.width;
+                let y = idx as i32 / build_data.map.width;
+                (y, x < 12)
+            })
+            .collect();
+        rows.sort_unstable();
+        // One in each corridor along the top, none in the one down to the side room
+        assert_eq!(rows, vec![(4, false), (4, true)]);
+    }
+}
@@ -0,0 +1,49 @@
+use crate::map::TileType;
+use crate::rng::GameRng;
+
+use super::{BuilderMap, LevelFeature, MetaMapBuilder};
+
+// ============================================================================
+// HeartChamber - Places the Heart of the Abyss in the last room
+// ============================================================================
+
+pub struct HeartChamber;
+
+impl HeartChamber {
+    pub fn new() -> Box<Self> {
+        Box::new(Self)
+    }
+}
+
+impl MetaMapBuilder for HeartChamber {
+    fn build_map(&mut self, _rng: &mut GameRng, build_data: &mut BuilderMap) {
+        let Some(chamber) = build_data.rooms.as_ref().and_then(|rooms| rooms.last().cloned()) else {
+            return;
+        };
+
+        // The Heart rests in the middle of the chamber
+        let (cx, cy) = chamber.center();
+        let heart_idx = build_data.map.xy_idx(cx, cy);
+        build_data.map.tiles[heart_idx] = TileType::Floor;
+        build_data
+            .spawn_list
+            .push((heart_idx, "Heart of the Abyss".to_string()));
+
+        // Guardians stand on each side of it
+        for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+            let idx = build_data.map.xy_idx(cx + dx, cy + dy);
+            if build_data.map.tiles[idx] == TileType::Floor {
+                build_data.spawn_list.push((idx, "Orc".to_string()));
+            }
+        }
+
+        build_data.level.add_feature(LevelFeature::Boss);
+        build_data.level.arrival = Some(
+            "The stone here is warm, and it pulses, slow and heavy, like a heartbeat. Somewhere \
+             on this level lies the Heart of the Abyss, and whatever has kept it all this time."
+                .to_string(),
+        );
+
+        build_data.take_snapshot();
+    }
+}
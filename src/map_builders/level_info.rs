@@ -0,0 +1,129 @@
+use bevy::prelude::*;
+
+use crate::theme::Theme;
+
+use super::{BuilderMap, RoomType};
+
+// ============================================================================
+// Level Info - What builders tell the player about a level as they arrive
+// ============================================================================
+
+/// Something out of the ordinary a builder put on a level
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LevelFeature {
+    /// A prefab vault or section
+    Vault,
+    /// A room a room vault was stamped into
+    Room(RoomType),
+    /// Something guarding the level worth fearing
+    Boss,
+    /// A room only reachable through a secret door
+    HiddenVault,
+}
+
+/// The name, mood and notable features of the level being played
+#[derive(Resource, Clone, Debug, Default)]
+pub struct LevelInfo {
+    /// Name of the band of the Abyss the level belongs to
+    pub name: String,
+    /// A line hinting at what waits on the level
+    pub feeling: Option<String>,
+    pub features: Vec<LevelFeature>,
+    /// A scene to read on arrival before play goes on
+    pub arrival: Option<String>,
+}
+
+impl LevelInfo {
+    pub fn new(depth: i32) -> Self {
+        Self {
+            name: Theme::for_depth(depth).name().to_string(),
+            ..default()
+        }
+    }
+
+    pub fn add_feature(&mut self, feature: LevelFeature) {
+        if !self.features.contains(&feature) {
+            self.features.push(feature);
+        }
+    }
+}
+
+/// A feeling for a level from the features and inhabitants its builders gave it, the most
+/// remarkable first. Quiet levels have none.
+pub fn level_feeling(build_data: &BuilderMap) -> Option<String> {
+    let has = |feature| build_data.level.features.contains(&feature);
+    let count = |name: &str| {
+        build_data
+            .spawn_list
+            .iter()
+            .filter(|(_, spawn)| spawn == name)
+            .count()
+    };
+
+    let feeling = if has(LevelFeature::Boss) {
+        "Something ancient waits for you here."
+    } else if count("Dragon") > 0 {
+        "The air smells of smoke and old bones."
+    } else if has(LevelFeature::Room(RoomType::Treasure)) {
+        "You sense riches nearby."
+    } else if has(LevelFeature::Room(RoomType::Shrine)) {
+        "A hush hangs over this place, as if something holy lingers."
+    } else if has(LevelFeature::Room(RoomType::Armory)) {
+        "You hear the ring of steel somewhere ahead."
+    } else if has(LevelFeature::Room(RoomType::Library)) {
+        "You smell old paper and candle wax."
+    } else if has(LevelFeature::HiddenVault) {
+        "You feel a faint draft from somewhere."
+    } else if has(LevelFeature::Vault) {
+        "You feel you are being watched."
+    } else if count("Orc") >= 3 {
+        "You hear the clank of armored orcs."
+    } else if count("Goblin") >= 4 {
+        "You hear goblins squabbling in the dark."
+    } else {
+        return None;
+    };
+    Some(feeling.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_feeling_goes_to_the_most_remarkable_thing() {
+        let mut build_data = BuilderMap::new(3);
+        assert_eq!(build_data.level.name, "Limestone Caverns");
+        assert_eq!(level_feeling(&build_data), None);
+
+        build_data.spawn_list = (0..3).map(|idx| (idx, "Orc".to_string())).collect();
+        assert_eq!(
+            level_feeling(&build_data).as_deref(),
+            Some("You hear the clank of armored orcs.")
+        );
+
+        build_data
+            .level
+            .add_feature(LevelFeature::Room(RoomType::Shrine));
+        build_data.level.add_feature(LevelFeature::Vault);
+        assert!(level_feeling(&build_data).unwrap().contains("holy"));
+    }
+
+    #[test]
+    fn test_feeling_counts_the_monsters_rolled_for_rooms() {
+        use crate::map_builders::{simple_map::SimpleMapBuilder, BuilderChain, MapBuilder};
+        use crate::rng::GameRng;
+
+        let mut chain =
+            BuilderChain::new(5, "Simple Map").start_with(Box::new(SimpleMapBuilder::new(5)));
+        chain.build_map(&mut GameRng::seeded(7));
+
+        let monsters = ["Goblin", "Orc", "Dragon"];
+        let build_data = &chain.build_data;
+        assert!(build_data
+            .spawn_list
+            .iter()
+            .any(|(_, name)| monsters.contains(&name.as_str())));
+        assert_eq!(build_data.level.feeling, level_feeling(build_data));
+    }
+}
@@ -0,0 +1,259 @@
+use rand::Rng;
+
+use crate::map::{TileType, MAP_HEIGHT, MAP_WIDTH};
+use crate::pathfinding::dijkstra_map;
+use crate::rng::GameRng;
+use crate::spawner::paired_name;
+
+use super::{BuilderMap, MetaMapBuilder, RegionGraph};
+
+// ============================================================================
+// LockAndKey - Locked doors with their keys, portcullises with their levers
+// ============================================================================
+
+/// Fewest tiles a door has to shut away from the start to be worth locking
+const MIN_SHUT_AWAY: usize = 12;
+
+/// How a lock is opened
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Opener {
+    Key,
+    Lever,
+}
+
+/// Locks a door and puts a portcullis in another, if there are doors that shut part of the
+/// level away, and gives the "Locked Door" spawns prefabs asked for a key. Keys and levers go in
+/// the regions that can be reached from the start without opening any of the locks, so the level
+/// can always be solved; the more passages away from the start, the likelier.
+///
+/// Runs once terrain is down, so nothing lands in a pool.
+pub struct LockAndKey;
+
+impl LockAndKey {
+    pub fn new() -> Box<Self> {
+        Box::new(Self)
+    }
+
+    /// Distances from the start with the given tiles walled up
+    fn distances_without(build_data: &BuilderMap, shut: &[usize]) -> Vec<f32> {
+        let mut map = build_data.map.clone();
+        for &idx in shut {
+            map.tiles[idx] = TileType::Wall;
+        }
+        dijkstra_map(&map, &[build_data.start_idx()])
+    }
+
+    fn reachable(distances: &[f32]) -> usize {
+        distances.iter().filter(|&&d| d < f32::MAX).count()
+    }
+
+    /// A door that shuts a good part of the level away on top of the locks already chosen
+    fn pick_door(rng: &mut GameRng, build_data: &BuilderMap, shut: &[usize]) -> Option<usize> {
+        let before = Self::reachable(&Self::distances_without(build_data, shut));
+        let candidates: Vec<usize> = build_data
+            .spawn_list
+            .iter()
+            .filter(|(_, name)| name == "Door")
+            .map(|&(idx, _)| idx)
+            .filter(|idx| {
+                let shut: Vec<usize> = shut.iter().copied().chain([*idx]).collect();
+                Self::reachable(&Self::distances_without(build_data, &shut)) + MIN_SHUT_AWAY
+                    <= before
+            })
+            .collect();
+        if candidates.is_empty() {
+            None
+        } else {
+            Some(candidates[rng.0.gen_range(0..candidates.len())])
+        }
+    }
+
+    /// Somewhere in the farther half of the given spots
+    fn far_spot(rng: &mut GameRng, mut spots: Vec<(usize, f32)>) -> Option<usize> {
+        if spots.is_empty() {
+            return None;
+        }
+        spots.sort_by(|a, b| a.1.total_cmp(&b.1));
+        let half = spots.len() / 2;
+        Some(spots[rng.0.gen_range(half..spots.len())].0)
+    }
+
+    /// Passages from the start to the region a tile is in, if it can be reached
+    fn hops_to(graph: &RegionGraph, hops: &[Option<usize>], idx: usize) -> Option<f32> {
+        graph.region_of[idx].and_then(|region| hops[region]).map(|hops| hops as f32)
+    }
+
+    /// A reachable tile to leave a key on
+    fn key_spot(
+        rng: &mut GameRng,
+        build_data: &BuilderMap,
+        graph: &RegionGraph,
+        hops: &[Option<usize>],
+    ) -> Option<usize> {
+        let taken: Vec<usize> = build_data.spawn_list.iter().map(|&(idx, _)| idx).collect();
+        let spots = (0..build_data.map.tiles.len())
+            .filter(|&idx| {
+                let tile = build_data.map.tiles[idx];
+                tile.is_walkable() && tile != TileType::DownStairs && !taken.contains(&idx)
+            })
+            .filter_map(|idx| Some((idx, Self::hops_to(graph, hops, idx)?)))
+            .collect();
+        Self::far_spot(rng, spots)
+    }
+
+    /// A wall beside a reachable tile to set a lever into
+    fn lever_spot(
+        rng: &mut GameRng,
+        build_data: &BuilderMap,
+        graph: &RegionGraph,
+        hops: &[Option<usize>],
+    ) -> Option<usize> {
+        let map = &build_data.map;
+        let taken: Vec<usize> = build_data.spawn_list.iter().map(|&(idx, _)| idx).collect();
+        let mut spots = Vec::new();
+        for y in 1..MAP_HEIGHT as i32 - 1 {
+            for x in 1..MAP_WIDTH as i32 - 1 {
+                let idx = map.xy_idx(x, y);
+                if map.tiles[idx] != TileType::Wall || taken.contains(&idx) {
+                    continue;
+                }
+                let nearest = [(0, -1), (0, 1), (-1, 0), (1, 0)]
+                    .iter()
+                    .filter_map(|(dx, dy)| Self::hops_to(graph, hops, map.xy_idx(x + dx, y + dy)))
+                    .reduce(f32::min);
+                if let Some(nearest) = nearest {
+                    spots.push((idx, nearest));
+                }
+            }
+        }
+        Self::far_spot(rng, spots)
+    }
+}
+
+impl MetaMapBuilder for LockAndKey {
+    fn build_map(&mut self, rng: &mut GameRng, build_data: &mut BuilderMap) {
+        // Locks prefabs placed, then the doors locked here
+        let mut locks: Vec<(usize, Opener)> = build_data
+            .spawn_list
+            .iter()
+            .filter(|(_, name)| name == "Locked Door")
+            .map(|&(idx, _)| (idx, Opener::Key))
+            .collect();
+        for opener in [Opener::Key, Opener::Lever] {
+            let shut: Vec<usize> = locks.iter().map(|&(idx, _)| idx).collect();
+            if let Some(idx) = Self::pick_door(rng, build_data, &shut) {
+                locks.push((idx, opener));
+            }
+        }
+
+        let shut: Vec<usize> = locks.iter().map(|&(idx, _)| idx).collect();
+        let graph = build_data.region_graph().clone();
+        let hops = match graph.entrance {
+            Some(entrance) => graph.distances_without(entrance, &shut),
+            None => vec![None; graph.regions.len()],
+        };
+        for (id, (lock_idx, opener)) in locks.into_iter().enumerate() {
+            let id = id as u32;
+            let (lock, spot, opener) = match opener {
+                Opener::Key => (
+                    "Locked Door",
+                    Self::key_spot(rng, build_data, &graph, &hops),
+                    "Key",
+                ),
+                Opener::Lever => (
+                    "Portcullis",
+                    Self::lever_spot(rng, build_data, &graph, &hops),
+                    "Lever",
+                ),
+            };
+            let Some(spot) = spot else {
+                // Nowhere to put the way in; a prefab's lock goes back to being a plain door
+                for (idx, name) in build_data.spawn_list.iter_mut() {
+                    if *idx == lock_idx && name == "Locked Door" {
+                        *name = "Door".to_string();
+                    }
+                }
+                continue;
+            };
+            for (idx, name) in build_data.spawn_list.iter_mut() {
+                if *idx == lock_idx && (name == "Door" || name == "Locked Door") {
+                    *name = paired_name(lock, id);
+                }
+            }
+            build_data.spawn_list.push((spot, paired_name(opener, id)));
+        }
+        build_data.take_snapshot();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map_builders::{
+        BuilderChain, DoglegCorridors, DoorPlacement, MapBuilder, RoomBasedStairs,
+        RoomBasedStartingPosition, SimpleMapRoomsBuilder,
+    };
+
+    #[test]
+    fn test_every_lock_opens_from_the_side_the_player_starts_on() {
+        let mut locked = 0;
+        for seed in 0..10 {
+            let mut chain = BuilderChain::new(1, "Locks")
+                .start_with(Box::new(SimpleMapRoomsBuilder::new(1)))
+                .with(DoglegCorridors::new())
+                .with(DoorPlacement::new())
+                .with(RoomBasedStartingPosition::new())
+                .with(RoomBasedStairs::new())
+                .with(LockAndKey::new());
+            chain.build_map(&mut GameRng::seeded(seed));
+
+            let build_data = &chain.build_data;
+            let paired = |kind: &str| -> Vec<(usize, u32)> {
+                build_data
+                    .spawn_list
+                    .iter()
+                    .filter_map(|(idx, name)| {
+                        let (k, id) = name.split_once('/')?;
+                        (k == kind).then(|| (*idx, id.parse().unwrap()))
+                    })
+                    .collect()
+            };
+            let locks: Vec<(usize, u32)> = [paired("Locked Door"), paired("Portcullis")].concat();
+            let openers: Vec<(usize, u32)> = [paired("Key"), paired("Lever")].concat();
+            assert_eq!(locks.len(), openers.len(), "seed {}", seed);
+            locked += locks.len();
+
+            let shut: Vec<usize> = locks.iter().map(|&(idx, _)| idx).collect();
+            let distances = LockAndKey::distances_without(build_data, &shut);
+            for (_, id) in locks {
+                let &(spot, _) = openers.iter().find(|&&(_, opens)| opens == id).unwrap();
+                // Levers sit in walls, so the tile in front of them has to be reachable
+                let x = (spot % MAP_WIDTH) as i32;
+                let y = (spot / MAP_WIDTH) as i32;
+                let reachable = [(0, 0), (0, -1), (0, 1), (-1, 0), (1, 0)]
+                    .iter()
+                    .any(|(dx, dy)| distances[build_data.map.xy_idx(x + dx, y + dy)] < f32::MAX);
+                assert!(reachable, "seed {}", seed);
+            }
+        }
+        assert!(locked > 0);
+    }
+
+    #[test]
+    fn test_lock_without_room_for_a_key_stays_a_door() {
+        let mut build_data = BuilderMap::new(1);
+        let start = build_data.map.xy_idx(10, 10);
+        let doorway = build_data.map.xy_idx(11, 10);
+        build_data.map.tiles[start] = TileType::Floor;
+        build_data.map.tiles[doorway] = TileType::Floor;
+        build_data.starting_position = Some((10, 10));
+        // The only open tile the player can reach already holds something
+        build_data.spawn_list = vec![
+            (start, "Health Potion".to_string()),
+            (doorway, "Locked Door".to_string()),
+        ];
+
+        LockAndKey.build_map(&mut GameRng::seeded(1), &mut build_data);
+        assert!(build_data.spawn_list.contains(&(doorway, "Door".to_string())));
+    }
+}
@@ -8,22 +8,34 @@ mod dla;
 mod doors;
 mod drunkard;
 mod erosion;
+mod guards;
+mod heart_chamber;
+mod level_info;
+mod locks;
 mod maze;
 mod prefab;
+mod prefab_file;
+mod recipe;
+mod regions;
 mod room_based;
 mod room_modifiers;
 mod room_sorter;
 mod rooms_only;
+mod secrets;
 mod simple_map;
+mod terrain;
+mod town;
+mod treasure;
 mod voronoi;
 mod wfc;
 
 use bevy::prelude::*;
 use rand::Rng;
 
-use crate::map::Map;
+use crate::map::{Map, TileType};
 use crate::rng::GameRng;
 use crate::shapes::Rect;
+use crate::theme::Theme;
 
 pub use area_based::{
     AreaStartingPosition, CullUnreachable, DistantExit, VoronoiSpawning, XStart, YStart,
@@ -32,19 +44,29 @@ pub use bsp_dungeon::BspDungeonBuilder;
 pub use bsp_interior::BspInteriorBuilder;
 pub use cellular_automata::CellularAutomataBuilder;
 pub use corridors::{BspCorridors, CorridorSpawner, DoglegCorridors, NearestCorridors, StraightLineCorridors};
-pub use dla::DLABuilder;
+pub use dla::{DLAAlgorithm, DLABuilder};
 pub use doors::DoorPlacement;
-pub use drunkard::DrunkardsWalkBuilder;
+pub use drunkard::{DrunkardSettings, DrunkardsWalkBuilder};
 pub use erosion::{CellularAutomataEroder, DrunkardsWalkEroder};
+pub use guards::ChokePointGuards;
+pub use heart_chamber::HeartChamber;
+pub use level_info::{LevelFeature, LevelInfo};
+pub use locks::LockAndKey;
 pub use maze::MazeBuilder;
-pub use prefab::{PrefabBuilder, PrefabMetaBuilder, CORNER_FORT};
+pub use prefab::{section, PrefabMetaBuilder, RoomType};
+pub use recipe::recipe_builder;
+pub use regions::{RegionGraph, RegionKind};
 pub use room_based::{RoomBasedSpawner, RoomBasedStairs, RoomBasedStartingPosition};
 pub use room_modifiers::{RoomCornerRounder, RoomDrawer, RoomExploder, RoomShape};
 pub use room_sorter::{RoomSort, RoomSorter};
 pub use rooms_only::{BspRoomsBuilder, SimpleMapRoomsBuilder};
+pub use secrets::SecretDoors;
 pub use simple_map::SimpleMapBuilder;
+pub use terrain::{TerrainFeatures, TerrainKind};
+pub use town::TownBuilder;
+pub use treasure::DeadEndTreasure;
 pub use voronoi::VoronoiCellBuilder;
-pub use wfc::WfcBuilder;
+pub use wfc::{WfcBuilder, WfcSourceType};
 
 /// All available map builder types
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -72,6 +94,7 @@ pub enum BuilderType {
     WfcDla,
     PrefabVaults,
     PrefabSectional,
+    TerrainCaverns,
 }
 
 impl BuilderType {
@@ -100,6 +123,7 @@ impl BuilderType {
         BuilderType::WfcDla,
         BuilderType::PrefabVaults,
         BuilderType::PrefabSectional,
+        BuilderType::TerrainCaverns,
     ];
 
     /// Get the display name for this builder type
@@ -128,6 +152,7 @@ impl BuilderType {
             BuilderType::WfcDla => "WFC (DLA)",
             BuilderType::PrefabVaults => "Prefab (Vaults)",
             BuilderType::PrefabSectional => "Prefab (Sectional)",
+            BuilderType::TerrainCaverns => "Terrain Caverns",
         }
     }
 
@@ -252,7 +277,19 @@ impl BuilderType {
             BuilderType::PrefabSectional => Box::new(
                 BuilderChain::new(depth, "Prefab (Sectional)")
                     .start_with(Box::new(CellularAutomataBuilder::new(depth)))
-                    .with(PrefabMetaBuilder::sectional(CORNER_FORT.clone())),
+                    .with(PrefabMetaBuilder::sectional(
+                        section("corner_fort").expect("assets/prefabs/corner_fort.txt"),
+                    )),
+            ),
+
+            // Natural caves dressed with water, rubble and grass
+            BuilderType::TerrainCaverns => Box::new(
+                BuilderChain::new(depth, "Terrain Caverns")
+                    .start_with(Box::new(CellularAutomataBuilder::new(depth)))
+                    .with(TerrainFeatures::water())
+                    .with(TerrainFeatures::rubble())
+                    .with(TerrainFeatures::vegetation())
+                    .with(VoronoiSpawning::new()),
             ),
         }
     }
@@ -281,9 +318,35 @@ pub trait MapBuilder {
     fn get_map(&self) -> Map;
     fn get_starting_position(&self) -> (i32, i32);
     fn get_snapshot_history(&self) -> Vec<Map>;
+    /// The snapshot history with what each step knew about the map; builders that don't keep
+    /// any more than the maps label every snapshot with their own name
+    fn get_snapshots(&self) -> Vec<Snapshot> {
+        self.get_snapshot_history()
+            .into_iter()
+            .map(|map| Snapshot {
+                label: self.get_name().to_string(),
+                map,
+                rooms: Vec::new(),
+                corridors: Vec::new(),
+                starting_position: None,
+            })
+            .collect()
+    }
     fn take_snapshot(&mut self);
     fn get_spawn_regions(&self) -> Vec<Rect>;
     fn get_name(&self) -> &'static str;
+    /// Override the depth used for spawn tables (e.g. harder floors on the return journey)
+    fn set_spawn_depth(&mut self, _spawn_depth: i32) {}
+    /// What the player is told about the level on arrival
+    fn get_level_info(&self) -> LevelInfo {
+        LevelInfo::default()
+    }
+    /// Rooms or areas of the finished level and the ways between them
+    fn get_region_graph(&self) -> RegionGraph {
+        let map = self.get_map();
+        let (x, y) = self.get_starting_position();
+        RegionGraph::from_map(&map, Some(&self.get_spawn_regions()), map.xy_idx(x, y))
+    }
 }
 
 // ============================================================================
@@ -292,6 +355,17 @@ pub trait MapBuilder {
 
 use crate::map::{MAP_HEIGHT, MAP_WIDTH};
 
+/// The map partway through building, with the rooms, corridors and start known at the time
+#[derive(Clone)]
+pub struct Snapshot {
+    /// The step of the chain that took it
+    pub label: String,
+    pub map: Map,
+    pub rooms: Vec<Rect>,
+    pub corridors: Vec<Vec<usize>>,
+    pub starting_position: Option<(i32, i32)>,
+}
+
 /// Shared state container for the builder chain
 pub struct BuilderMap {
     pub map: Map,
@@ -299,8 +373,18 @@ pub struct BuilderMap {
     pub rooms: Option<Vec<Rect>>,
     pub corridors: Option<Vec<Vec<usize>>>,
     pub spawn_list: Vec<(usize, String)>,
-    pub history: Vec<Map>,
+    pub history: Vec<Snapshot>,
+    /// The step of the chain that is running, which labels the snapshots it takes
+    pub step: String,
     pub depth: i32,
+    /// Depth used when rolling spawn tables; differs from `depth` while ascending
+    pub spawn_depth: i32,
+    /// Rooms a room vault was stamped into, and what they became
+    pub special_rooms: Vec<(Rect, RoomType)>,
+    /// Name, feeling and features of the level, for the way in
+    pub level: LevelInfo,
+    /// Rooms or areas and the ways between them, as of the last `region_graph` call
+    pub regions: RegionGraph,
 }
 
 impl BuilderMap {
@@ -312,23 +396,76 @@ impl BuilderMap {
             corridors: None,
             spawn_list: Vec::new(),
             history: Vec::new(),
+            step: String::new(),
             depth,
+            spawn_depth: depth,
+            special_rooms: Vec::new(),
+            level: LevelInfo::new(depth),
+            regions: RegionGraph::default(),
         }
     }
 
+    /// What a room was made into by a room vault, if anything
+    pub fn room_type(&self, room: &Rect) -> Option<RoomType> {
+        self.special_rooms
+            .iter()
+            .find(|(special, _)| special == room)
+            .map(|&(_, room_type)| room_type)
+    }
+
+    /// Map index of where the player starts, or the middle of the map if that isn't decided yet
+    pub fn start_idx(&self) -> usize {
+        let (x, y) = self
+            .starting_position
+            .unwrap_or((MAP_WIDTH as i32 / 2, MAP_HEIGHT as i32 / 2));
+        self.map.xy_idx(x, y)
+    }
+
+    /// The region graph of the map as it is now. Rebuilt on every ask, since each step may have
+    /// changed the map, and kept in `regions`.
+    pub fn region_graph(&mut self) -> &RegionGraph {
+        self.regions = RegionGraph::new(self);
+        &self.regions
+    }
+
     pub fn take_snapshot(&mut self) {
-        self.history.push(self.map.clone());
+        self.take_snapshot_of(self.map.clone());
+    }
+
+    /// Records a map the step is working on away from `map`, as if it were a snapshot of it
+    pub fn take_snapshot_of(&mut self, map: Map) {
+        self.history.push(Snapshot {
+            label: self.step.clone(),
+            map,
+            rooms: self.rooms.clone().unwrap_or_default(),
+            corridors: self.corridors.clone().unwrap_or_default(),
+            starting_position: self.starting_position,
+        });
     }
 }
 
+/// The last part of a builder's type name, for labelling its snapshots
+fn step_name<T: ?Sized>() -> &'static str {
+    let name = std::any::type_name::<T>();
+    name.rsplit("::").next().unwrap_or(name)
+}
+
 /// Trait for builders that create a map from scratch
 pub trait InitialMapBuilder {
     fn build_map(&mut self, rng: &mut GameRng, build_data: &mut BuilderMap);
+    /// What the step is called in the map generation debugger
+    fn name(&self) -> &'static str {
+        step_name::<Self>()
+    }
 }
 
 /// Trait for builders that modify an existing map
 pub trait MetaMapBuilder {
     fn build_map(&mut self, rng: &mut GameRng, build_data: &mut BuilderMap);
+    /// What the step is called in the map generation debugger
+    fn name(&self) -> &'static str {
+        step_name::<Self>()
+    }
 }
 
 /// Orchestrates the builder pipeline
@@ -362,43 +499,85 @@ impl BuilderChain {
     fn run_build(&mut self, rng: &mut GameRng) {
         // Run the initial builder
         if let Some(ref mut starter) = self.starter {
+            self.build_data.step = format!("1. {}", starter.name());
             starter.build_map(rng, &mut self.build_data);
         }
 
         // Run each meta builder in sequence
-        for builder in &mut self.builders {
+        let first = if self.starter.is_some() { 2 } else { 1 };
+        for (i, builder) in self.builders.iter_mut().enumerate() {
+            self.build_data.step = format!("{}. {}", first + i, builder.name());
             builder.build_map(rng, &mut self.build_data);
         }
     }
+
+    /// Rolls monsters and items for every room after the first that isn't a vault
+    fn roll_room_inhabitants(&mut self, rng: &mut GameRng) {
+        let Some(rooms) = self.build_data.rooms.clone() else {
+            return;
+        };
+        let depth = self.build_data.spawn_depth;
+        // Room vaults bring their own inhabitants
+        let ordinary = rooms
+            .iter()
+            .skip(1)
+            .filter(|room| self.build_data.room_type(room).is_none());
+        let mut rolled = Vec::new();
+        for room in ordinary {
+            let map = &self.build_data.map;
+            // Rooms with pools or rock in them only spawn on the tiles you can stand on
+            let tiles: Vec<usize> = (room.y1 + 1..=room.y2)
+                .flat_map(|y| (room.x1 + 1..=room.x2).map(move |x| map.xy_idx(x, y)))
+                .collect();
+            let open: Vec<usize> = tiles
+                .iter()
+                .copied()
+                .filter(|&idx| map.tiles[idx].is_walkable())
+                .collect();
+            if open.len() == tiles.len() {
+                rolled.extend(crate::spawner::roll_room(rng, room, depth));
+            } else {
+                rolled.extend(crate::spawner::roll_region(rng, &open, depth));
+            }
+        }
+        self.build_data.spawn_list.extend(rolled);
+    }
 }
 
 // Implement MapBuilder for BuilderChain so it can be used with existing game code
 impl MapBuilder for BuilderChain {
     fn build_map(&mut self, rng: &mut GameRng) {
         self.run_build(rng);
-    }
 
-    fn spawn_entities(&self, commands: &mut Commands, rng: &mut GameRng, font: &TextFont) {
-        // Spawn from rooms if available
-        if let Some(ref rooms) = self.build_data.rooms {
-            let mut monster_id: usize = 0;
-            for room in rooms.iter().skip(1) {
-                crate::spawner::spawn_room(
-                    commands,
-                    rng,
-                    font,
-                    room,
-                    &mut monster_id,
-                    self.build_data.depth,
-                );
+        // The way back up is where the player arrives
+        if let Some((x, y)) = self.build_data.starting_position {
+            let idx = self.build_data.map.xy_idx(x, y);
+            if self.build_data.map.tiles[idx] == TileType::Floor {
+                self.build_data.map.tiles[idx] = TileType::UpStairs;
             }
         }
 
-        // Also spawn from spawn_list (for prefabs, etc.)
+        // Ordinary rooms get their inhabitants now, so the level's feeling knows who lives there
+        self.roll_room_inhabitants(rng);
+
+        if self.build_data.level.feeling.is_none() {
+            self.build_data.level.feeling = level_info::level_feeling(&self.build_data);
+        }
+        self.build_data.region_graph();
+    }
+
+    fn spawn_entities(&self, commands: &mut Commands, _rng: &mut GameRng, font: &TextFont) {
+        let mut monster_id: usize = 0;
+        let clearance = self.build_data.map.clearance();
         for (idx, name) in &self.build_data.spawn_list {
+            // Large monsters only where all of them fits
+            let footprint = crate::spawner::footprint_of(name);
+            if footprint.0 > 1 && clearance[*idx] < footprint.0 {
+                continue;
+            }
             let x = (*idx % MAP_WIDTH) as i32;
             let y = (*idx / MAP_WIDTH) as i32;
-            prefab::spawn_by_name(commands, font, x, y, name, &mut 0);
+            prefab::spawn_by_name(commands, font, x, y, name, &mut monster_id);
         }
     }
 
@@ -413,6 +592,14 @@ impl MapBuilder for BuilderChain {
     }
 
     fn get_snapshot_history(&self) -> Vec<Map> {
+        self.build_data
+            .history
+            .iter()
+            .map(|snapshot| snapshot.map.clone())
+            .collect()
+    }
+
+    fn get_snapshots(&self) -> Vec<Snapshot> {
         self.build_data.history.clone()
     }
 
@@ -427,6 +614,18 @@ impl MapBuilder for BuilderChain {
     fn get_name(&self) -> &'static str {
         self.name
     }
+
+    fn set_spawn_depth(&mut self, spawn_depth: i32) {
+        self.build_data.spawn_depth = spawn_depth;
+    }
+
+    fn get_level_info(&self) -> LevelInfo {
+        self.build_data.level.clone()
+    }
+
+    fn get_region_graph(&self) -> RegionGraph {
+        self.build_data.regions.clone()
+    }
 }
 
 pub fn random_builder(depth: i32, rng: &mut GameRng) -> Box<dyn MapBuilder> {
@@ -574,17 +773,8 @@ fn random_complex_builder(depth: i32, rng: &mut GameRng) -> Box<dyn MapBuilder>
     Box::new(chain)
 }
 
-/// The default builder used for new games and level transitions.
-/// Change this one line to use a different map generator everywhere.
+/// The default builder used for new games and level transitions: whatever the depth's theme
+/// lays out
 pub fn default_builder(depth: i32) -> Box<dyn MapBuilder> {
-    Box::new(
-        BuilderChain::new(depth, "Layered Dungeon")
-            .start_with(Box::new(SimpleMapRoomsBuilder::new(depth)))
-            .with(RoomSorter::new(RoomSort::Central))
-            .with(DoglegCorridors::new())
-            .with(DoorPlacement::new())
-            .with(RoomBasedStartingPosition::new())
-            .with(RoomBasedStairs::new())
-            .with(RoomBasedSpawner::new()),
-    )
+    Theme::for_depth(depth).builder(depth)
 }
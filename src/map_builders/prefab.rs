@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use bevy::prelude::*;
 use rand::Rng;
 
@@ -6,20 +8,21 @@ use crate::rng::GameRng;
 use crate::shapes::Rect;
 use crate::spawner;
 
-use super::{BuilderMap, MapBuilder, MetaMapBuilder};
+use super::prefab_file::prefabs;
+use super::{BuilderMap, LevelFeature, MetaMapBuilder};
 
 // ============================================================================
 // Placement Enums
 // ============================================================================
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum HorizontalPlacement {
     Left,
     Center,
     Right,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum VerticalPlacement {
     Top,
     Center,
@@ -27,19 +30,65 @@ pub enum VerticalPlacement {
 }
 
 // ============================================================================
-// Prefab Definitions
+// Prefab Definitions - Loaded from assets/prefabs (see prefab_file.rs)
 // ============================================================================
 
-/// Pure visual definition - just the ASCII art and dimensions
-#[derive(Clone, Copy)]
+/// Pure visual definition - the character art, row by row
+#[derive(Clone, Debug, PartialEq)]
 pub struct PrefabTemplate {
-    pub template: &'static str,
+    pub tiles: Vec<char>,
     pub width: usize,
     pub height: usize,
 }
 
+impl PrefabTemplate {
+    /// Reads rows of art, padding short rows with blanks
+    pub fn from_rows(rows: &[&str]) -> Self {
+        let width = rows.iter().map(|row| row.chars().count()).max().unwrap_or(0);
+        let tiles = rows
+            .iter()
+            .flat_map(|row| row.chars().chain(std::iter::repeat(' ')).take(width))
+            .collect();
+        Self {
+            tiles,
+            width,
+            height: rows.len(),
+        }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> char {
+        self.tiles[y * self.width + x]
+    }
+
+    /// The art turned a quarter clockwise
+    pub fn rotated(&self) -> Self {
+        let (width, height) = (self.height, self.width);
+        let tiles = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| self.get(y, self.height - 1 - x))
+            .collect();
+        Self {
+            tiles,
+            width,
+            height,
+        }
+    }
+
+    /// The art flipped left to right
+    pub fn mirrored(&self) -> Self {
+        let tiles = (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+            .map(|(x, y)| self.get(self.width - 1 - x, y))
+            .collect();
+        Self {
+            tiles,
+            ..self.clone()
+        }
+    }
+}
+
 /// Placement constraints - when/where a prefab can appear
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct VaultConstraints {
     pub min_depth: i32,
     pub max_depth: i32,
@@ -58,457 +107,116 @@ impl Default for VaultConstraints {
     }
 }
 
-/// A vault combines a template with its constraints
-#[derive(Clone, Copy)]
-pub struct PrefabVault {
-    pub template: PrefabTemplate,
-    pub constraints: VaultConstraints,
-}
-
-/// A larger section placed at map edges
-#[derive(Clone)]
-pub struct PrefabSection {
-    pub template: PrefabTemplate,
-    pub placement: (HorizontalPlacement, VerticalPlacement),
+/// What a room vault makes of the room it is stamped into, or what a town building is
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RoomType {
+    Library,
+    Armory,
+    Shrine,
+    Treasure,
+    Tavern,
+    Temple,
+    Blacksmith,
+    Alchemist,
 }
 
-// ============================================================================
-// Example Prefabs
-// ============================================================================
-
-// Templates (pure visual definitions)
-pub const TOTALLY_NOT_A_TRAP_TEMPLATE: PrefabTemplate = PrefabTemplate {
-    template: "
-
- ^^^
- ^!^
- ^^^
-
-",
-    width: 5,
-    height: 5,
-};
-
-pub const MONSTER_DEN_TEMPLATE: PrefabTemplate = PrefabTemplate {
-    template: "
-
- gggg
- g!!g
- gggg
-
-",
-    width: 6,
-    height: 5,
-};
-
-pub const CHECKERBOARD_TRAP_TEMPLATE: PrefabTemplate = PrefabTemplate {
-    template: "
-
- ^.^.^
- .^.^.
- ^.^.^
-
-",
-    width: 7,
-    height: 5,
-};
-
-pub const CORNER_FORT_TEMPLATE: PrefabTemplate = PrefabTemplate {
-    template: "
-#########
-#.......#
-#.ggggg.#
-#.g!!!g.#
-#.g!o!g.#
-#.g!!!g.#
-#.ggggg.#
-#.......#
-###+#####
-",
-    width: 9,
-    height: 9,
-};
-
-// Vaults (template + constraints)
-pub const TOTALLY_NOT_A_TRAP: PrefabVault = PrefabVault {
-    template: TOTALLY_NOT_A_TRAP_TEMPLATE,
-    constraints: VaultConstraints {
-        min_depth: 1,
-        max_depth: i32::MAX,
-        spawn_chance: 1.0,
-        min_floor_percent: 80,
-    },
-};
-
-pub const MONSTER_DEN: PrefabVault = PrefabVault {
-    template: MONSTER_DEN_TEMPLATE,
-    constraints: VaultConstraints {
-        min_depth: 2,          // Only appears on level 2+
-        max_depth: i32::MAX,
-        spawn_chance: 0.7,     // 70% chance to spawn
-        min_floor_percent: 80,
-    },
-};
-
-pub const CHECKERBOARD_TRAP: PrefabVault = PrefabVault {
-    template: CHECKERBOARD_TRAP_TEMPLATE,
-    constraints: VaultConstraints {
-        min_depth: 1,
-        max_depth: 5,          // Only on early levels
-        spawn_chance: 0.5,     // 50% chance
-        min_floor_percent: 80,
-    },
-};
-
-/// All available vaults
-pub const VAULTS: &[PrefabVault] = &[TOTALLY_NOT_A_TRAP, MONSTER_DEN, CHECKERBOARD_TRAP];
-
-// Sections (template + placement)
-pub const CORNER_FORT: PrefabSection = PrefabSection {
-    template: CORNER_FORT_TEMPLATE,
-    placement: (HorizontalPlacement::Right, VerticalPlacement::Top),
-};
-
-// ============================================================================
-// Prefab Mode
-// ============================================================================
-
-#[derive(Clone)]
-pub enum PrefabMode {
-    /// Insert random vaults into the map
-    RoomVaults,
-    /// Insert a section at a specific position
-    Sectional { section: PrefabSection },
-}
-
-// ============================================================================
-// PrefabBuilder
-// ============================================================================
-
-pub struct PrefabBuilder {
-    map: Map,
-    starting_position: (i32, i32),
-    depth: i32,
-    history: Vec<Map>,
-    spawn_list: Vec<(usize, String)>,
-    spawn_regions: Vec<Vec<usize>>,
-    previous_builder: Option<Box<dyn MapBuilder>>,
-    mode: PrefabMode,
-}
-
-impl PrefabBuilder {
-    fn new(depth: i32, builder: Box<dyn MapBuilder>, mode: PrefabMode) -> Self {
-        Self {
-            map: Map::new(MAP_WIDTH, MAP_HEIGHT, depth),
-            starting_position: (MAP_WIDTH as i32 / 2, MAP_HEIGHT as i32 / 2),
-            depth,
-            history: Vec::new(),
-            spawn_list: Vec::new(),
-            spawn_regions: Vec::new(),
-            previous_builder: Some(builder),
-            mode,
-        }
-    }
-
-    /// Create a prefab builder that adds random vaults to a base builder
-    pub fn vaults(depth: i32, builder: Box<dyn MapBuilder>) -> Self {
-        Self::new(depth, builder, PrefabMode::RoomVaults)
-    }
-
-    /// Create a prefab builder that adds a section to a base builder
-    pub fn sectional(depth: i32, builder: Box<dyn MapBuilder>, section: PrefabSection) -> Self {
-        Self::new(depth, builder, PrefabMode::Sectional { section })
-    }
-
-    /// Convert a prefab character to map tile and/or spawn entry
-    fn char_to_map(&mut self, ch: char, idx: usize) {
-        match ch {
-            '#' => self.map.tiles[idx] = TileType::Wall,
-            '.' | ' ' => self.map.tiles[idx] = TileType::Floor,
-            '+' => self.map.tiles[idx] = TileType::Floor, // Door (just floor for now)
-            '>' => self.map.tiles[idx] = TileType::DownStairs,
-            '@' => {
-                self.map.tiles[idx] = TileType::Floor;
-                let x = (idx % MAP_WIDTH) as i32;
-                let y = (idx / MAP_WIDTH) as i32;
-                self.starting_position = (x, y);
-            }
-            // Monsters
-            'g' => {
-                self.map.tiles[idx] = TileType::Floor;
-                self.spawn_list.push((idx, "Goblin".to_string()));
-            }
-            'o' => {
-                self.map.tiles[idx] = TileType::Floor;
-                self.spawn_list.push((idx, "Orc".to_string()));
-            }
-            // Items
-            '!' => {
-                self.map.tiles[idx] = TileType::Floor;
-                self.spawn_list.push((idx, "Health Potion".to_string()));
-            }
-            '%' => {
-                self.map.tiles[idx] = TileType::Floor;
-                self.spawn_list.push((idx, "Rations".to_string()));
-            }
-            ')' => {
-                self.map.tiles[idx] = TileType::Floor;
-                self.spawn_list.push((idx, "Magic Missile Scroll".to_string()));
-            }
-            // Traps
-            '^' => {
-                self.map.tiles[idx] = TileType::Floor;
-                self.spawn_list.push((idx, "Bear Trap".to_string()));
-            }
-            // Equipment
-            '/' => {
-                self.map.tiles[idx] = TileType::Floor;
-                self.spawn_list.push((idx, "Dagger".to_string()));
-            }
-            '(' => {
-                self.map.tiles[idx] = TileType::Floor;
-                self.spawn_list.push((idx, "Shield".to_string()));
-            }
-            _ => self.map.tiles[idx] = TileType::Floor,
-        }
-    }
-
-    /// Parse a prefab template string into a character vector
-    fn read_template(template: &str, width: usize, height: usize) -> Vec<char> {
-        let mut chars: Vec<char> = Vec::with_capacity(width * height);
-
-        // Skip leading newline if present
-        let template = template.strip_prefix('\n').unwrap_or(template);
-
-        for line in template.lines().take(height) {
-            // Pad or truncate each line to the expected width
-            let line_chars: Vec<char> = line.chars().collect();
-            for x in 0..width {
-                if x < line_chars.len() {
-                    chars.push(line_chars[x]);
-                } else {
-                    chars.push(' ');
-                }
-            }
-        }
-
-        // Pad remaining rows if template is shorter than expected
-        while chars.len() < width * height {
-            chars.push(' ');
+impl RoomType {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "library" => Some(RoomType::Library),
+            "armory" => Some(RoomType::Armory),
+            "shrine" => Some(RoomType::Shrine),
+            "treasure" => Some(RoomType::Treasure),
+            "tavern" => Some(RoomType::Tavern),
+            "temple" => Some(RoomType::Temple),
+            "blacksmith" => Some(RoomType::Blacksmith),
+            "alchemist" => Some(RoomType::Alchemist),
+            _ => None,
         }
-
-        chars
     }
+}
 
-    /// Apply a vault at a specific position
-    fn apply_vault(&mut self, vault: &PrefabVault, start_x: i32, start_y: i32) {
-        let template = &vault.template;
-        let chars = Self::read_template(template.template, template.width, template.height);
-
-        for y in 0..template.height as i32 {
-            for x in 0..template.width as i32 {
-                let map_x = start_x + x;
-                let map_y = start_y + y;
-
-                if map_x >= 0
-                    && map_x < MAP_WIDTH as i32
-                    && map_y >= 0
-                    && map_y < MAP_HEIGHT as i32
-                {
-                    let map_idx = self.map.xy_idx(map_x, map_y);
-                    let char_idx = (y as usize) * template.width + (x as usize);
-                    self.char_to_map(chars[char_idx], map_idx);
-                }
-            }
-        }
-    }
-
-    /// Apply a section at its specified placement
-    fn apply_section(&mut self, section: &PrefabSection) {
-        let template = &section.template;
-        let start_x = match section.placement.0 {
-            HorizontalPlacement::Left => 1,
-            HorizontalPlacement::Center => (MAP_WIDTH as i32 / 2) - (template.width as i32 / 2),
-            HorizontalPlacement::Right => MAP_WIDTH as i32 - template.width as i32 - 1,
-        };
-
-        let start_y = match section.placement.1 {
-            VerticalPlacement::Top => 1,
-            VerticalPlacement::Center => (MAP_HEIGHT as i32 / 2) - (template.height as i32 / 2),
-            VerticalPlacement::Bottom => MAP_HEIGHT as i32 - template.height as i32 - 1,
-        };
-
-        let chars = Self::read_template(template.template, template.width, template.height);
-
-        // Clear spawn regions that overlap with the section
-        let section_left = start_x;
-        let section_right = start_x + template.width as i32;
-        let section_top = start_y;
-        let section_bottom = start_y + template.height as i32;
-
-        for region in &mut self.spawn_regions {
-            region.retain(|&idx| {
-                let x = (idx % MAP_WIDTH) as i32;
-                let y = (idx / MAP_WIDTH) as i32;
-                x < section_left || x >= section_right || y < section_top || y >= section_bottom
-            });
-        }
-
-        // Apply the section
-        for y in 0..template.height as i32 {
-            for x in 0..template.width as i32 {
-                let map_x = start_x + x;
-                let map_y = start_y + y;
+/// A room or section of map read from a prefab file
+#[derive(Clone, Debug)]
+pub struct Prefab {
+    pub name: String,
+    pub template: PrefabTemplate,
+    pub constraints: VaultConstraints,
+    /// Characters of the art that spawn something, on top of the default legend
+    pub legend: HashMap<char, String>,
+    /// Where a section goes; vaults have none and go wherever they fit
+    pub placement: Option<(HorizontalPlacement, VerticalPlacement)>,
+    /// Makes it a room vault, stamped inside a room of a room-based map
+    pub room: Option<RoomType>,
+    /// Whether a vault may be turned a random number of quarters when placed
+    pub rotate: bool,
+    /// Whether a vault may be flipped when placed
+    pub mirror: bool,
+}
 
-                if map_x >= 0
-                    && map_x < MAP_WIDTH as i32
-                    && map_y >= 0
-                    && map_y < MAP_HEIGHT as i32
-                {
-                    let map_idx = self.map.xy_idx(map_x, map_y);
-                    let char_idx = (y as usize) * template.width + (x as usize);
-                    self.char_to_map(chars[char_idx], map_idx);
-                }
-            }
-        }
+impl Prefab {
+    /// The name spawned by a character of the art, if it spawns anything
+    pub fn spawn_for(&self, ch: char) -> Option<&str> {
+        self.legend
+            .get(&ch)
+            .map(String::as_str)
+            .or_else(|| default_legend(ch))
     }
 
-    /// Find suitable locations and apply random vaults
-    fn apply_random_vaults(&mut self, rng: &mut GameRng) {
-        // Filter vaults by depth constraints
-        let eligible_vaults: Vec<&PrefabVault> = VAULTS
-            .iter()
-            .filter(|vault| {
-                self.depth >= vault.constraints.min_depth
-                    && self.depth <= vault.constraints.max_depth
-            })
-            .collect();
-
-        if eligible_vaults.is_empty() {
-            return;
-        }
-
-        // Try to place 1-3 vaults
-        let num_vaults = rng.0.gen_range(1..=3);
-
-        for _ in 0..num_vaults {
-            // Pick a random vault from eligible ones
-            let vault_idx = rng.0.gen_range(0..eligible_vaults.len());
-            let vault = eligible_vaults[vault_idx];
-
-            // Check spawn probability
-            if rng.0.gen::<f32>() > vault.constraints.spawn_chance {
-                continue;
-            }
-
-            // Try to find a valid placement (up to 50 attempts)
-            let template = &vault.template;
-            for _ in 0..50 {
-                let x = rng.0.gen_range(2..MAP_WIDTH as i32 - template.width as i32 - 2);
-                let y = rng.0.gen_range(2..MAP_HEIGHT as i32 - template.height as i32 - 2);
-
-                if self.can_place_vault(vault, x, y) {
-                    self.apply_vault(vault, x, y);
-                    break;
-                }
+    /// The art as it goes down this time, turned and flipped if the vault allows it
+    fn random_orientation(&self, rng: &mut GameRng) -> PrefabTemplate {
+        let mut template = self.template.clone();
+        if self.rotate {
+            for _ in 0..rng.0.gen_range(0..4) {
+                template = template.rotated();
             }
         }
-    }
-
-    /// Check if a vault can be placed at a position (needs mostly floor tiles)
-    fn can_place_vault(&self, vault: &PrefabVault, start_x: i32, start_y: i32) -> bool {
-        let template = &vault.template;
-        let mut floor_count = 0;
-        let total_tiles = template.width * template.height;
-
-        for y in 0..template.height as i32 {
-            for x in 0..template.width as i32 {
-                let map_x = start_x + x;
-                let map_y = start_y + y;
-
-                if map_x < 0 || map_x >= MAP_WIDTH as i32 || map_y < 0 || map_y >= MAP_HEIGHT as i32
-                {
-                    return false;
-                }
-
-                let idx = self.map.xy_idx(map_x, map_y);
-                if self.map.tiles[idx] == TileType::Floor {
-                    floor_count += 1;
-                }
-            }
+        if self.mirror && rng.0.gen_bool(0.5) {
+            template = template.mirrored();
         }
-
-        // Check against the vault's minimum floor percentage constraint
-        floor_count * 100 / total_tiles >= vault.constraints.min_floor_percent as usize
+        template
     }
 }
 
-impl MapBuilder for PrefabBuilder {
-    fn build_map(&mut self, rng: &mut GameRng) {
-        // Build the base map first
-        if let Some(ref mut builder) = self.previous_builder {
-            builder.build_map(rng);
-            self.map = builder.get_map();
-            self.starting_position = builder.get_starting_position();
-            self.history = builder.get_snapshot_history();
-        }
-
-        self.take_snapshot();
-
-        // Apply prefabs based on mode
-        match self.mode.clone() {
-            PrefabMode::RoomVaults => {
-                self.apply_random_vaults(rng);
-            }
-            PrefabMode::Sectional { section } => {
-                self.apply_section(&section);
-            }
-        }
-
-        self.take_snapshot();
-    }
-
-    fn spawn_entities(&self, commands: &mut Commands, rng: &mut GameRng, font: &TextFont) {
-        // First spawn from spawn_regions (from base builder)
-        let mut monster_id: usize = 0;
-        for region in &self.spawn_regions {
-            spawner::spawn_region(commands, rng, font, region, &mut monster_id, self.depth);
-        }
-
-        // Then spawn entities from prefab spawn_list
-        for (idx, name) in &self.spawn_list {
-            let x = (*idx % MAP_WIDTH) as i32;
-            let y = (*idx / MAP_WIDTH) as i32;
-            spawn_by_name(commands, font, x, y, name, &mut monster_id);
-        }
-    }
-
-    fn get_map(&self) -> Map {
-        self.map.clone()
-    }
-
-    fn get_starting_position(&self) -> (i32, i32) {
-        self.starting_position
-    }
-
-    fn get_snapshot_history(&self) -> Vec<Map> {
-        self.history.clone()
-    }
+/// What the characters of any prefab spawn unless its legend says otherwise
+fn default_legend(ch: char) -> Option<&'static str> {
+    let name = match ch {
+        // Monsters
+        'g' => "Goblin",
+        'o' => "Orc",
+        // Items
+        '!' => "Health Potion",
+        '%' => "Rations",
+        ')' => "Magic Missile Scroll",
+        // Traps
+        '^' => "Bear Trap",
+        // Equipment
+        '/' => "Dagger",
+        '(' => "Shield",
+        _ => return None,
+    };
+    Some(name)
+}
 
-    fn take_snapshot(&mut self) {
-        self.history.push(self.map.clone());
-    }
+/// The section prefab of this name
+pub fn section(name: &str) -> Option<&'static Prefab> {
+    prefabs()
+        .iter()
+        .find(|prefab| prefab.name == name && prefab.placement.is_some())
+}
 
-    fn get_spawn_regions(&self) -> Vec<Rect> {
-        Vec::new()
-    }
+// ============================================================================
+// Prefab Mode
+// ============================================================================
 
-    fn get_name(&self) -> &'static str {
-        match self.mode {
-            PrefabMode::RoomVaults => "Prefab (Vaults)",
-            PrefabMode::Sectional { .. } => "Prefab (Sectional)",
-        }
-    }
+#[derive(Clone)]
+pub enum PrefabMode {
+    /// Insert random vaults into the map
+    RoomVaults,
+    /// Insert a section at a specific position
+    Sectional { section: &'static Prefab },
+    /// Turn rooms of a room-based map into special rooms
+    SpecialRooms,
 }
 
 // ============================================================================
@@ -523,6 +231,21 @@ pub fn spawn_by_name(
     name: &str,
     monster_id: &mut usize,
 ) {
+    // Locks, keys, levers and portcullises carry the ID that pairs them up
+    if let Some((kind, id)) = name
+        .split_once('/')
+        .and_then(|(kind, id)| Some((kind, id.parse().ok()?)))
+    {
+        match kind {
+            "Locked Door" => spawner::spawn_locked_door(commands, font, x, y, id),
+            "Key" => spawner::spawn_key(commands, font, x, y, id),
+            "Portcullis" => spawner::spawn_portcullis(commands, font, x, y, id),
+            "Lever" => spawner::spawn_lever(commands, font, x, y, id, false),
+            _ => {}
+        }
+        return;
+    }
+
     match name {
         // Monsters
         "Goblin" => {
@@ -533,6 +256,10 @@ pub fn spawn_by_name(
             spawn_monster(commands, font, x, y, "o", &format!("Orc #{}", monster_id));
             *monster_id += 1;
         }
+        "Dragon" => {
+            spawner::spawn_dragon(commands, font, x, y, *monster_id);
+            *monster_id += 1;
+        }
         // Items
         "Health Potion" => spawner::spawn_health_potion(commands, font, x, y),
         "Rations" => spawner::spawn_rations(commands, font, x, y),
@@ -549,6 +276,13 @@ pub fn spawn_by_name(
         "Bear Trap" => spawner::spawn_bear_trap(commands, font, x, y),
         // Doors
         "Door" => spawner::spawn_door(commands, font, x, y),
+        "Secret Door" => spawner::spawn_secret_door(commands, font, x, y),
+        // Townsfolk
+        "Barkeep" | "Priest" | "Blacksmith" | "Alchemist" | "Townsperson" => {
+            spawner::spawn_npc(commands, font, x, y, name)
+        }
+        // Artifacts
+        "Heart of the Abyss" => spawner::spawn_heart_of_the_abyss(commands, font, x, y),
         _ => {}
     }
 }
@@ -557,7 +291,7 @@ pub fn spawn_by_name(
 fn spawn_monster(commands: &mut Commands, font: &TextFont, x: i32, y: i32, glyph: &str, name: &str) {
     use bevy::color::palettes;
     use crate::combat::CombatStats;
-    use crate::components::{BlocksTile, Name, RenderOrder, RenderableBundle};
+    use crate::components::{BlocksTile, Name, OpensDoors, RenderOrder, RenderableBundle};
     use crate::map::Position;
     use crate::monsters::Monster;
     use crate::viewshed::Viewshed;
@@ -569,6 +303,7 @@ fn spawn_monster(commands: &mut Commands, font: &TextFont, x: i32, y: i32, glyph
         },
         Position { x, y },
         BlocksTile,
+        OpensDoors,
         CombatStats {
             max_hp: 16,
             hp: 16,
@@ -605,85 +340,51 @@ impl PrefabMetaBuilder {
         })
     }
 
-    pub fn sectional(section: PrefabSection) -> Box<Self> {
+    pub fn sectional(section: &'static Prefab) -> Box<Self> {
         Box::new(Self {
             mode: PrefabMode::Sectional { section },
         })
     }
 
+    pub fn special_rooms() -> Box<Self> {
+        Box::new(Self {
+            mode: PrefabMode::SpecialRooms,
+        })
+    }
+
     /// Convert a prefab character to map tile and/or spawn entry
-    fn char_to_map(
-        ch: char,
-        idx: usize,
-        map: &mut Map,
-        spawn_list: &mut Vec<(usize, String)>,
-        starting_position: &mut Option<(i32, i32)>,
-    ) {
+    fn char_to_map(prefab: &Prefab, ch: char, idx: usize, build_data: &mut BuilderMap) {
+        if let Some(name) = prefab.spawn_for(ch) {
+            build_data.map.tiles[idx] = TileType::Floor;
+            build_data.spawn_list.push((idx, name.to_string()));
+            return;
+        }
         match ch {
-            '#' => map.tiles[idx] = TileType::Wall,
-            '.' | ' ' => map.tiles[idx] = TileType::Floor,
-            '+' => map.tiles[idx] = TileType::Floor, // Door (just floor for now)
-            '>' => map.tiles[idx] = TileType::DownStairs,
+            '#' => build_data.map.tiles[idx] = TileType::Wall,
+            '+' => build_data.map.tiles[idx] = TileType::Floor, // Door (just floor for now)
+            '>' => build_data.map.tiles[idx] = TileType::DownStairs,
             '@' => {
-                map.tiles[idx] = TileType::Floor;
+                build_data.map.tiles[idx] = TileType::Floor;
                 let x = (idx % MAP_WIDTH) as i32;
                 let y = (idx / MAP_WIDTH) as i32;
-                *starting_position = Some((x, y));
-            }
-            // Monsters
-            'g' => {
-                map.tiles[idx] = TileType::Floor;
-                spawn_list.push((idx, "Goblin".to_string()));
-            }
-            'o' => {
-                map.tiles[idx] = TileType::Floor;
-                spawn_list.push((idx, "Orc".to_string()));
-            }
-            // Items
-            '!' => {
-                map.tiles[idx] = TileType::Floor;
-                spawn_list.push((idx, "Health Potion".to_string()));
+                build_data.starting_position = Some((x, y));
             }
-            '%' => {
-                map.tiles[idx] = TileType::Floor;
-                spawn_list.push((idx, "Rations".to_string()));
-            }
-            ')' => {
-                map.tiles[idx] = TileType::Floor;
-                spawn_list.push((idx, "Magic Missile Scroll".to_string()));
-            }
-            // Traps
-            '^' => {
-                map.tiles[idx] = TileType::Floor;
-                spawn_list.push((idx, "Bear Trap".to_string()));
-            }
-            // Equipment
-            '/' => {
-                map.tiles[idx] = TileType::Floor;
-                spawn_list.push((idx, "Dagger".to_string()));
-            }
-            '(' => {
-                map.tiles[idx] = TileType::Floor;
-                spawn_list.push((idx, "Shield".to_string()));
-            }
-            _ => map.tiles[idx] = TileType::Floor,
+            _ => build_data.map.tiles[idx] = TileType::Floor,
         }
     }
 
-    /// Apply a vault at a specific position
-    fn apply_vault(
-        vault: &PrefabVault,
+    /// Stamp a prefab's art (in the orientation it was given) with its top-left at a position
+    fn apply_template(
+        prefab: &Prefab,
+        template: &PrefabTemplate,
         start_x: i32,
         start_y: i32,
         build_data: &mut BuilderMap,
     ) {
-        let template = &vault.template;
-        let chars = PrefabBuilder::read_template(template.template, template.width, template.height);
-
-        for y in 0..template.height as i32 {
-            for x in 0..template.width as i32 {
-                let map_x = start_x + x;
-                let map_y = start_y + y;
+        for y in 0..template.height {
+            for x in 0..template.width {
+                let map_x = start_x + x as i32;
+                let map_y = start_y + y as i32;
 
                 if map_x >= 0
                     && map_x < MAP_WIDTH as i32
@@ -691,68 +392,43 @@ impl PrefabMetaBuilder {
                     && map_y < MAP_HEIGHT as i32
                 {
                     let map_idx = build_data.map.xy_idx(map_x, map_y);
-                    let char_idx = (y as usize) * template.width + (x as usize);
-                    Self::char_to_map(
-                        chars[char_idx],
-                        map_idx,
-                        &mut build_data.map,
-                        &mut build_data.spawn_list,
-                        &mut build_data.starting_position,
-                    );
+                    Self::char_to_map(prefab, template.get(x, y), map_idx, build_data);
                 }
             }
         }
     }
 
     /// Apply a section at its specified placement
-    fn apply_section(section: &PrefabSection, build_data: &mut BuilderMap) {
+    fn apply_section(section: &Prefab, build_data: &mut BuilderMap) {
         let template = &section.template;
-        let start_x = match section.placement.0 {
+        let Some((horizontal, vertical)) = section.placement else {
+            return;
+        };
+        let start_x = match horizontal {
             HorizontalPlacement::Left => 1,
             HorizontalPlacement::Center => (MAP_WIDTH as i32 / 2) - (template.width as i32 / 2),
             HorizontalPlacement::Right => MAP_WIDTH as i32 - template.width as i32 - 1,
         };
 
-        let start_y = match section.placement.1 {
+        let start_y = match vertical {
             VerticalPlacement::Top => 1,
             VerticalPlacement::Center => (MAP_HEIGHT as i32 / 2) - (template.height as i32 / 2),
             VerticalPlacement::Bottom => MAP_HEIGHT as i32 - template.height as i32 - 1,
         };
 
-        let chars = PrefabBuilder::read_template(template.template, template.width, template.height);
-
-        // Apply the section
-        for y in 0..template.height as i32 {
-            for x in 0..template.width as i32 {
-                let map_x = start_x + x;
-                let map_y = start_y + y;
-
-                if map_x >= 0
-                    && map_x < MAP_WIDTH as i32
-                    && map_y >= 0
-                    && map_y < MAP_HEIGHT as i32
-                {
-                    let map_idx = build_data.map.xy_idx(map_x, map_y);
-                    let char_idx = (y as usize) * template.width + (x as usize);
-                    Self::char_to_map(
-                        chars[char_idx],
-                        map_idx,
-                        &mut build_data.map,
-                        &mut build_data.spawn_list,
-                        &mut build_data.starting_position,
-                    );
-                }
-            }
-        }
+        Self::apply_template(section, template, start_x, start_y, build_data);
+        build_data.level.add_feature(LevelFeature::Vault);
     }
 
     /// Find suitable locations and apply random vaults
     fn apply_random_vaults(build_data: &mut BuilderMap, rng: &mut GameRng) {
         // Filter vaults by depth constraints
-        let eligible_vaults: Vec<&PrefabVault> = VAULTS
+        let eligible_vaults: Vec<&Prefab> = prefabs()
             .iter()
             .filter(|vault| {
-                build_data.depth >= vault.constraints.min_depth
+                vault.placement.is_none()
+                    && vault.room.is_none()
+                    && build_data.depth >= vault.constraints.min_depth
                     && build_data.depth <= vault.constraints.max_depth
             })
             .collect();
@@ -774,23 +450,90 @@ impl PrefabMetaBuilder {
                 continue;
             }
 
+            let template = vault.random_orientation(rng);
+            if template.width as i32 + 4 >= MAP_WIDTH as i32
+                || template.height as i32 + 4 >= MAP_HEIGHT as i32
+            {
+                continue;
+            }
+
             // Try to find a valid placement (up to 50 attempts)
-            let template = &vault.template;
             for _ in 0..50 {
                 let x = rng.0.gen_range(2..MAP_WIDTH as i32 - template.width as i32 - 2);
                 let y = rng.0.gen_range(2..MAP_HEIGHT as i32 - template.height as i32 - 2);
 
-                if Self::can_place_vault(&build_data.map, vault, x, y) {
-                    Self::apply_vault(vault, x, y, build_data);
+                if Self::can_place_vault(&build_data.map, vault, &template, x, y) {
+                    Self::apply_template(vault, &template, x, y, build_data);
+                    build_data.level.add_feature(LevelFeature::Vault);
                     break;
                 }
             }
         }
     }
 
+    /// Stamp room vaults into rooms big enough to hold them with a ring of floor to spare, so
+    /// corridors coming into the room still get in. The first room is where the player arrives
+    /// and the last one is kept ordinary so the stairs always have somewhere to go; both are
+    /// left alone. `RoomBasedStairs` never puts the stairs in a special room.
+    fn apply_special_rooms(build_data: &mut BuilderMap, rng: &mut GameRng) {
+        let Some(rooms) = build_data.rooms.clone() else {
+            return;
+        };
+        if rooms.len() < 3 {
+            return;
+        }
+        let candidates = &rooms[1..rooms.len() - 1];
+
+        let mut eligible: Vec<&Prefab> = prefabs()
+            .iter()
+            .filter(|prefab| {
+                prefab.room.is_some()
+                    && build_data.depth >= prefab.constraints.min_depth
+                    && build_data.depth <= prefab.constraints.max_depth
+            })
+            .collect();
+
+        // Up to two special rooms, never two of the same kind
+        for _ in 0..2 {
+            if eligible.is_empty() {
+                break;
+            }
+            let prefab = eligible.swap_remove(rng.0.gen_range(0..eligible.len()));
+            if rng.0.gen::<f32>() > prefab.constraints.spawn_chance {
+                continue;
+            }
+
+            let template = prefab.random_orientation(rng);
+            let fits = |room: &&Rect| {
+                build_data.room_type(room).is_none()
+                    && template.width as i32 + 2 <= room.x2 - room.x1
+                    && template.height as i32 + 2 <= room.y2 - room.y1
+            };
+            let rooms: Vec<&Rect> = candidates.iter().filter(fits).collect();
+            if rooms.is_empty() {
+                continue;
+            }
+            let room = rooms[rng.0.gen_range(0..rooms.len())];
+
+            // Centered in the room's floor, which runs from x1 + 1 to x2
+            let x = room.x1 + 1 + (room.x2 - room.x1 - template.width as i32) / 2;
+            let y = room.y1 + 1 + (room.y2 - room.y1 - template.height as i32) / 2;
+            Self::apply_template(prefab, &template, x, y, build_data);
+            if let Some(room_type) = prefab.room {
+                build_data.special_rooms.push((room.clone(), room_type));
+                build_data.level.add_feature(LevelFeature::Room(room_type));
+            }
+        }
+    }
+
     /// Check if a vault can be placed at a position (needs mostly floor tiles)
-    fn can_place_vault(map: &Map, vault: &PrefabVault, start_x: i32, start_y: i32) -> bool {
-        let template = &vault.template;
+    fn can_place_vault(
+        map: &Map,
+        vault: &Prefab,
+        template: &PrefabTemplate,
+        start_x: i32,
+        start_y: i32,
+    ) -> bool {
         let mut floor_count = 0;
         let total_tiles = template.width * template.height;
 
@@ -827,8 +570,85 @@ impl MetaMapBuilder for PrefabMetaBuilder {
             PrefabMode::Sectional { section } => {
                 Self::apply_section(section, build_data);
             }
+            PrefabMode::SpecialRooms => {
+                Self::apply_special_rooms(build_data, rng);
+            }
         }
 
         build_data.take_snapshot();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotating_and_mirroring_keep_the_art() {
+        let art = PrefabTemplate::from_rows(&["ab", "cd", "ef"]);
+        let turned = art.rotated();
+        assert_eq!((turned.width, turned.height), (3, 2));
+        assert_eq!(turned.tiles, "ecafdb".chars().collect::<Vec<_>>());
+        assert_eq!(turned.rotated().rotated().rotated(), art);
+        assert_eq!(art.mirrored().tiles, "badcfe".chars().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_special_rooms_skip_the_first_and_last_rooms() {
+        let rooms = vec![
+            Rect::new(2, 2, 10, 10),
+            Rect::new(20, 2, 10, 10),
+            Rect::new(40, 2, 10, 10),
+        ];
+        let mut stamped = 0;
+        for seed in 0..20 {
+            let mut build_data = BuilderMap::new(5);
+            build_data.rooms = Some(rooms.clone());
+            PrefabMetaBuilder::special_rooms().build_map(&mut GameRng::seeded(seed), &mut build_data);
+
+            assert!(build_data.special_rooms.len() <= 1);
+            if let Some((room, _)) = build_data.special_rooms.first() {
+                assert!(room == &rooms[1]);
+                assert!(build_data.room_type(&rooms[1]).is_some());
+                stamped += 1;
+            }
+        }
+        assert!(stamped > 0);
+    }
+
+    #[test]
+    fn test_stairs_never_land_in_a_special_room() {
+        use crate::map_builders::{
+            BuilderChain, DoglegCorridors, DoorPlacement, MapBuilder, RoomBasedStairs,
+            RoomBasedStartingPosition, RoomSort, RoomSorter, SimpleMapRoomsBuilder,
+        };
+
+        let mut stamped = 0;
+        for seed in 0..40 {
+            let mut chain = BuilderChain::new(5, "Special Rooms")
+                .start_with(Box::new(SimpleMapRoomsBuilder::new(5)))
+                .with(RoomSorter::new(RoomSort::Central))
+                .with(DoglegCorridors::new())
+                .with(PrefabMetaBuilder::special_rooms())
+                .with(DoorPlacement::new())
+                .with(RoomBasedStartingPosition::new())
+                .with(RoomBasedStairs::new());
+            chain.build_map(&mut GameRng::seeded(seed));
+
+            let build_data = &chain.build_data;
+            let stairs = build_data
+                .map
+                .tiles
+                .iter()
+                .position(|&tile| tile == TileType::DownStairs)
+                .unwrap();
+            let (x, y) = ((stairs % MAP_WIDTH) as i32, (stairs / MAP_WIDTH) as i32);
+            for (room, _) in &build_data.special_rooms {
+                let inside = x >= room.x1 && x <= room.x2 && y >= room.y1 && y <= room.y2;
+                assert!(!inside, "seed {}", seed);
+            }
+            stamped += build_data.special_rooms.len();
+        }
+        assert!(stamped > 0);
+    }
+}
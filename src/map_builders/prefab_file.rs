@@ -0,0 +1,388 @@
+use std::collections::HashMap;
+use std::io::Read;
+use std::sync::OnceLock;
+
+use bevy::prelude::*;
+use flate2::read::GzDecoder;
+
+use super::prefab::{
+    HorizontalPlacement, Prefab, PrefabTemplate, RoomType, VaultConstraints, VerticalPlacement,
+};
+
+// ============================================================================
+// Prefab Files - Vaults and sections in assets/prefabs
+// ============================================================================
+//
+// A prefab is a .txt file: a header of `key = value` lines, then `---` and the art.
+//
+//     ; Goblins guarding a stash
+//     depth = 2-            min-max, either end may be left open
+//     chance = 0.7          of appearing when picked
+//     floor = 80            percent of its area that must already be floor
+//     placement = right top makes it a section placed there instead of a vault
+//     room = library        makes it a room vault, stamped into a room of that type
+//     rotate = yes          vaults may be turned and flipped when placed
+//     mirror = yes
+//     'k' = Orc             what a character spawns, on top of the default legend
+//     map = den.xp          takes the art from a REXPaint file instead of a `---` block
+//     ---
+//     gggg
+//     gk!g
+//
+// The prefab is named after its file.
+
+/// The web build has no files to read, so it carries the shipped prefabs compiled in
+#[cfg(target_arch = "wasm32")]
+const EMBEDDED: &[(&str, &[u8])] = &[
+    (
+        "checkerboard_trap.txt",
+        include_bytes!("../../assets/prefabs/checkerboard_trap.txt"),
+    ),
+    (
+        "corner_fort.txt",
+        include_bytes!("../../assets/prefabs/corner_fort.txt"),
+    ),
+    (
+        "corner_fort.xp",
+        include_bytes!("../../assets/prefabs/corner_fort.xp"),
+    ),
+    (
+        "armory.txt",
+        include_bytes!("../../assets/prefabs/armory.txt"),
+    ),
+    (
+        "library.txt",
+        include_bytes!("../../assets/prefabs/library.txt"),
+    ),
+    (
+        "monster_den.txt",
+        include_bytes!("../../assets/prefabs/monster_den.txt"),
+    ),
+    (
+        "shrine.txt",
+        include_bytes!("../../assets/prefabs/shrine.txt"),
+    ),
+    (
+        "totally_not_a_trap.txt",
+        include_bytes!("../../assets/prefabs/totally_not_a_trap.txt"),
+    ),
+    (
+        "treasure_room.txt",
+        include_bytes!("../../assets/prefabs/treasure_room.txt"),
+    ),
+];
+
+/// Every prefab, read on first use
+pub fn prefabs() -> &'static [Prefab] {
+    static PREFABS: OnceLock<Vec<Prefab>> = OnceLock::new();
+    PREFABS.get_or_init(|| load_prefabs(&prefab_files()))
+}
+
+/// The files of assets/prefabs by name
+#[cfg(not(target_arch = "wasm32"))]
+fn prefab_files() -> HashMap<String, Vec<u8>> {
+    use bevy::asset::io::file::FileAssetReader;
+
+    let dir = FileAssetReader::get_base_path().join("assets/prefabs");
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            warn!("No prefabs: failed to read {}: {}", dir.display(), e);
+            return HashMap::new();
+        }
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let bytes = std::fs::read(entry.path()).ok()?;
+            Some((name, bytes))
+        })
+        .collect()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn prefab_files() -> HashMap<String, Vec<u8>> {
+    EMBEDDED
+        .iter()
+        .map(|(name, bytes)| (name.to_string(), bytes.to_vec()))
+        .collect()
+}
+
+/// Parses every .txt file into a prefab, in name order. Broken files are skipped with a
+/// warning rather than taking the game down.
+fn load_prefabs(files: &HashMap<String, Vec<u8>>) -> Vec<Prefab> {
+    let mut names: Vec<&String> = files.keys().filter(|name| name.ends_with(".txt")).collect();
+    names.sort();
+
+    names
+        .into_iter()
+        .filter_map(|file| {
+            let text = String::from_utf8_lossy(&files[file]);
+            let name = file.trim_end_matches(".txt");
+            match parse_prefab(name, &text, |art| files.get(art).cloned()) {
+                Ok(prefab) => Some(prefab),
+                Err(e) => {
+                    warn!("Skipping prefab {}: {}", file, e);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Reads a prefab file. `read` fetches a file the header refers to, like the .xp of its art.
+pub fn parse_prefab(
+    name: &str,
+    text: &str,
+    read: impl Fn(&str) -> Option<Vec<u8>>,
+) -> Result<Prefab, String> {
+    let mut prefab = Prefab {
+        name: name.to_string(),
+        template: PrefabTemplate::from_rows(&[]),
+        constraints: VaultConstraints::default(),
+        legend: HashMap::new(),
+        placement: None,
+        room: None,
+        rotate: false,
+        mirror: false,
+    };
+    let mut lines = text.lines().map(|line| line.trim_end_matches('\r'));
+    let mut art = None;
+
+    for line in lines.by_ref() {
+        let line = line.trim();
+        if line == "---" {
+            break;
+        }
+        if line.is_empty() || line.starts_with(';') {
+            continue;
+        }
+        let (key, value) = line
+            .split_once('=')
+            .map(|(key, value)| (key.trim(), value.trim()))
+            .ok_or_else(|| format!("expected `key = value`, got {:?}", line))?;
+
+        if let Some(ch) = legend_char(key) {
+            prefab.legend.insert(ch, value.to_string());
+            continue;
+        }
+        match key {
+            "depth" => {
+                let (min, max) = value.split_once('-').unwrap_or((value, value));
+                if !min.trim().is_empty() {
+                    prefab.constraints.min_depth = parse_number(key, min)?;
+                }
+                if !max.trim().is_empty() {
+                    prefab.constraints.max_depth = parse_number(key, max)?;
+                }
+            }
+            "chance" => prefab.constraints.spawn_chance = parse_number(key, value)?,
+            "floor" => prefab.constraints.min_floor_percent = parse_number(key, value)?,
+            "placement" => prefab.placement = Some(parse_placement(value)?),
+            "room" => {
+                prefab.room = Some(
+                    RoomType::from_name(value)
+                        .ok_or_else(|| format!("unknown room type {:?}", value))?,
+                )
+            }
+            "rotate" => prefab.rotate = parse_flag(key, value)?,
+            "mirror" => prefab.mirror = parse_flag(key, value)?,
+            "map" => {
+                let bytes = read(value).ok_or_else(|| format!("no map file {:?}", value))?;
+                art = Some(parse_xp(&bytes)?);
+            }
+            _ => return Err(format!("unknown header {:?}", key)),
+        }
+    }
+
+    prefab.template = match art {
+        Some(template) => template,
+        None => PrefabTemplate::from_rows(&lines.collect::<Vec<_>>()),
+    };
+    if prefab.template.width == 0 || prefab.template.height == 0 {
+        return Err("no art".to_string());
+    }
+    Ok(prefab)
+}
+
+/// The character of a legend key, which is quoted so any character can be given one
+fn legend_char(key: &str) -> Option<char> {
+    let mut chars = key.strip_prefix('\'')?.strip_suffix('\'')?.chars();
+    match (chars.next(), chars.next()) {
+        (Some(ch), None) => Some(ch),
+        _ => None,
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, String> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| format!("{} is not a number: {:?}", key, value))
+}
+
+fn parse_flag(key: &str, value: &str) -> Result<bool, String> {
+    match value {
+        "yes" | "true" => Ok(true),
+        "no" | "false" => Ok(false),
+        _ => Err(format!("{} should be yes or no, got {:?}", key, value)),
+    }
+}
+
+fn parse_placement(value: &str) -> Result<(HorizontalPlacement, VerticalPlacement), String> {
+    let mut words = value.split_whitespace();
+    let horizontal = match words.next() {
+        Some("left") => HorizontalPlacement::Left,
+        Some("center") => HorizontalPlacement::Center,
+        Some("right") => HorizontalPlacement::Right,
+        _ => {
+            return Err(format!(
+                "placement should start with left, center or right: {:?}",
+                value
+            ))
+        }
+    };
+    let vertical = match words.next() {
+        Some("top") => VerticalPlacement::Top,
+        Some("center") => VerticalPlacement::Center,
+        Some("bottom") => VerticalPlacement::Bottom,
+        _ => {
+            return Err(format!(
+                "placement should end with top, center or bottom: {:?}",
+                value
+            ))
+        }
+    };
+    Ok((horizontal, vertical))
+}
+
+// ============================================================================
+// REXPaint
+// ============================================================================
+
+/// Reads the art of a REXPaint .xp image: gzipped layers of CP437 cells stored column by
+/// column. Layers are drawn over each other; blank cells let the ones below show.
+pub fn parse_xp(bytes: &[u8]) -> Result<PrefabTemplate, String> {
+    let mut data = Vec::new();
+    GzDecoder::new(bytes)
+        .read_to_end(&mut data)
+        .map_err(|e| format!("not a REXPaint file: {}", e))?;
+    let mut cursor = XpCursor { data: &data, at: 0 };
+
+    // Newer files start with a negative version before the layer count
+    let first = cursor.int()?;
+    let layers = if first < 0 { cursor.int()? } else { first };
+
+    let mut template: Option<PrefabTemplate> = None;
+    for _ in 0..layers {
+        let width = cursor.int()?.max(0) as usize;
+        let height = cursor.int()?.max(0) as usize;
+        let art = template.get_or_insert_with(|| PrefabTemplate {
+            tiles: vec![' '; width * height],
+            width,
+            height,
+        });
+        for x in 0..width {
+            for y in 0..height {
+                let ch = cp437_char(cursor.int()?);
+                // The foreground and background colors, which the map doesn't use
+                cursor.take(6)?;
+                if ch != ' ' && x < art.width && y < art.height {
+                    art.tiles[y * art.width + x] = ch;
+                }
+            }
+        }
+    }
+    template.ok_or_else(|| "REXPaint file has no layers".to_string())
+}
+
+struct XpCursor<'a> {
+    data: &'a [u8],
+    at: usize,
+}
+
+impl XpCursor<'_> {
+    fn take(&mut self, len: usize) -> Result<&[u8], String> {
+        let bytes = self
+            .data
+            .get(self.at..self.at + len)
+            .ok_or_else(|| "REXPaint file is cut short".to_string())?;
+        self.at += len;
+        Ok(bytes)
+    }
+
+    fn int(&mut self) -> Result<i32, String> {
+        let word = self.take(4)?;
+        Ok(i32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+    }
+}
+
+/// The character a prefab reads for a CP437 code. Shades, blocks and box drawing are walls.
+fn cp437_char(code: i32) -> char {
+    match code {
+        33..=126 => code as u8 as char,
+        176..=223 => '#',
+        _ => ' ',
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+
+    use super::*;
+
+    #[test]
+    fn test_prefab_header_and_legend() {
+        let text = "; A stash\ndepth = 2-5\nchance = 0.5\nrotate = yes\n'k' = Orc\n---\n#k#\n#!";
+        let prefab = parse_prefab("stash", text, |_| None).unwrap();
+        assert_eq!(
+            (prefab.constraints.min_depth, prefab.constraints.max_depth),
+            (2, 5)
+        );
+        assert_eq!(prefab.constraints.spawn_chance, 0.5);
+        assert!(prefab.rotate && !prefab.mirror && prefab.placement.is_none());
+        assert_eq!(prefab.spawn_for('k'), Some("Orc"));
+        assert_eq!(prefab.spawn_for('!'), Some("Health Potion"));
+        // Short rows are padded to the widest
+        assert_eq!((prefab.template.width, prefab.template.height), (3, 2));
+        assert_eq!(prefab.template.get(2, 1), ' ');
+
+        assert!(parse_prefab("bad", "colour = red\n---\n#", |_| None).is_err());
+        assert!(parse_prefab("empty", "depth = 1\n---\n", |_| None).is_err());
+    }
+
+    #[test]
+    fn test_rexpaint_art_is_read_column_by_column() {
+        // A 2x1 image: a wall block then an orc
+        let mut raw = Vec::new();
+        for int in [-1, 1, 2, 1] {
+            raw.extend_from_slice(&i32::to_le_bytes(int));
+        }
+        for code in [219, 'o' as i32] {
+            raw.extend_from_slice(&i32::to_le_bytes(code));
+            raw.extend_from_slice(&[255, 255, 255, 0, 0, 0]);
+        }
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&raw).unwrap();
+        let xp = encoder.finish().unwrap();
+
+        let prefab = parse_prefab("den", "map = den.xp", |_| Some(xp.clone())).unwrap();
+        assert_eq!(prefab.template, PrefabTemplate::from_rows(&["#o"]));
+    }
+
+    #[test]
+    fn test_shipped_prefabs_load() {
+        let names: Vec<&str> = prefabs()
+            .iter()
+            .map(|prefab| prefab.name.as_str())
+            .collect();
+        assert!(names.contains(&"monster_den"), "{:?}", names);
+        let fort = super::super::prefab::section("corner_fort").unwrap();
+        assert_eq!((fort.template.width, fort.template.height), (9, 9));
+    }
+}
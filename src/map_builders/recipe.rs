@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use serde::Deserialize;
+
+use crate::distance::DistanceAlg;
+
+use super::common::Symmetry;
+use super::*;
+
+// ============================================================================
+// Map Recipes - Builder chains described in assets/recipes/levels.json
+// ============================================================================
+
+/// The recipe book, compiled in so the web build and the tests read the same file
+const LEVELS_JSON: &str = include_str!("../../assets/recipes/levels.json");
+
+/// A builder chain: the builder that lays out the map, then the steps that reshape, connect
+/// and populate it, in order
+#[derive(Debug, Deserialize)]
+pub struct Recipe {
+    pub name: String,
+    pub start: InitialStep,
+    #[serde(default)]
+    pub steps: Vec<MetaStep>,
+}
+
+/// Builders a recipe can start with
+#[derive(Debug, Deserialize)]
+pub enum InitialStep {
+    SimpleMap,
+    BspDungeon,
+    BspInterior,
+    CellularAutomata,
+    Maze,
+    SimpleMapRooms,
+    BspRooms,
+    Town,
+    Drunkard(DrunkardSettings),
+    Dla {
+        algorithm: DLAAlgorithm,
+        brush_size: i32,
+        symmetry: Symmetry,
+        floor_percent: f32,
+    },
+    Voronoi {
+        seeds: usize,
+        distance: DistanceAlg,
+    },
+}
+
+/// Steps a recipe can run over the map
+#[derive(Debug, Deserialize)]
+pub enum MetaStep {
+    RoomSorter(RoomSort),
+    RoomExploder,
+    RoomCornerRounder,
+    RoomDrawer(RoomShape),
+    DoglegCorridors,
+    BspCorridors,
+    StraightLineCorridors,
+    NearestCorridors,
+    CorridorSpawner,
+    DoorPlacement,
+    SecretDoors,
+    LockAndKey,
+    DeadEndTreasure,
+    ChokePointGuards,
+    RoomBasedStartingPosition,
+    RoomBasedStairs,
+    RoomBasedSpawner,
+    CullUnreachable,
+    DistantExit,
+    VoronoiSpawning,
+    AreaStartingPosition {
+        x: XStart,
+        y: YStart,
+    },
+    CellularAutomataEroder {
+        iterations: i32,
+    },
+    DrunkardsWalkEroder {
+        floor_percent: f32,
+        brush_size: i32,
+        symmetry: Symmetry,
+    },
+    Terrain {
+        kind: TerrainKind,
+        count: i32,
+        size: i32,
+    },
+    Wfc {
+        chunk_size: i32,
+    },
+    PrefabVaults,
+    PrefabSpecialRooms,
+    PrefabSection(String),
+    HeartChamber,
+}
+
+impl InitialStep {
+    fn create(&self, depth: i32) -> Box<dyn InitialMapBuilder> {
+        match *self {
+            InitialStep::SimpleMap => Box::new(SimpleMapBuilder::new(depth)),
+            InitialStep::BspDungeon => Box::new(BspDungeonBuilder::new(depth)),
+            InitialStep::BspInterior => Box::new(BspInteriorBuilder::new(depth)),
+            InitialStep::CellularAutomata => Box::new(CellularAutomataBuilder::new(depth)),
+            InitialStep::Maze => Box::new(MazeBuilder::new(depth)),
+            InitialStep::SimpleMapRooms => Box::new(SimpleMapRoomsBuilder::new(depth)),
+            InitialStep::BspRooms => Box::new(BspRoomsBuilder::new(depth)),
+            InitialStep::Town => TownBuilder::new(),
+            InitialStep::Drunkard(settings) => Box::new(DrunkardsWalkBuilder::new(depth, settings)),
+            InitialStep::Dla {
+                algorithm,
+                brush_size,
+                symmetry,
+                floor_percent,
+            } => Box::new(DLABuilder::new(
+                depth,
+                algorithm,
+                brush_size,
+                symmetry,
+                floor_percent,
+            )),
+            InitialStep::Voronoi { seeds, distance } => {
+                Box::new(VoronoiCellBuilder::new(depth, seeds, distance))
+            }
+        }
+    }
+}
+
+impl MetaStep {
+    fn create(&self, depth: i32) -> Box<dyn MetaMapBuilder> {
+        match *self {
+            MetaStep::RoomSorter(sort) => RoomSorter::new(sort),
+            MetaStep::RoomExploder => RoomExploder::new(),
+            MetaStep::RoomCornerRounder => RoomCornerRounder::new(),
+            MetaStep::RoomDrawer(RoomShape::Rectangle) => RoomDrawer::new(),
+            MetaStep::RoomDrawer(RoomShape::Circle) => RoomDrawer::circles(),
+            MetaStep::DoglegCorridors => DoglegCorridors::new(),
+            MetaStep::BspCorridors => BspCorridors::new(),
+            MetaStep::StraightLineCorridors => StraightLineCorridors::new(),
+            MetaStep::NearestCorridors => NearestCorridors::new(),
+            MetaStep::CorridorSpawner => CorridorSpawner::new(),
+            MetaStep::DoorPlacement => DoorPlacement::new(),
+            MetaStep::SecretDoors => SecretDoors::new(),
+            MetaStep::LockAndKey => LockAndKey::new(),
+            MetaStep::DeadEndTreasure => DeadEndTreasure::new(),
+            MetaStep::ChokePointGuards => ChokePointGuards::new(),
+            MetaStep::RoomBasedStartingPosition => RoomBasedStartingPosition::new(),
+            MetaStep::RoomBasedStairs => RoomBasedStairs::new(),
+            MetaStep::RoomBasedSpawner => RoomBasedSpawner::new(),
+            MetaStep::CullUnreachable => CullUnreachable::new(),
+            MetaStep::DistantExit => DistantExit::new(),
+            MetaStep::VoronoiSpawning => VoronoiSpawning::new(),
+            MetaStep::AreaStartingPosition { x, y } => AreaStartingPosition::new(x, y),
+            MetaStep::CellularAutomataEroder { iterations } => {
+                CellularAutomataEroder::with_iterations(iterations)
+            }
+            MetaStep::DrunkardsWalkEroder {
+                floor_percent,
+                brush_size,
+                symmetry,
+            } => DrunkardsWalkEroder::with_settings(floor_percent, brush_size, symmetry),
+            MetaStep::Terrain { kind, count, size } => TerrainFeatures::new(kind, count, size),
+            MetaStep::Wfc { chunk_size } => Box::new(WfcBuilder::with_options(
+                depth,
+                chunk_size,
+                WfcSourceType::CellularAutomata,
+            )),
+            MetaStep::PrefabVaults => PrefabMetaBuilder::vaults(),
+            MetaStep::PrefabSpecialRooms => PrefabMetaBuilder::special_rooms(),
+            MetaStep::PrefabSection(ref name) => PrefabMetaBuilder::sectional(
+                section(name).unwrap_or_else(|| panic!("No prefab section named {:?}", name)),
+            ),
+            MetaStep::HeartChamber => HeartChamber::new(),
+        }
+    }
+}
+
+impl Recipe {
+    /// The builder chain this recipe describes, for a level at depth
+    pub fn chain(&'static self, depth: i32) -> BuilderChain {
+        self.steps.iter().fold(
+            BuilderChain::new(depth, &self.name).start_with(self.start.create(depth)),
+            |chain, step| chain.with(step.create(depth)),
+        )
+    }
+}
+
+/// Every recipe by the name themes know it by, parsed on first use
+pub fn recipes() -> &'static HashMap<String, Recipe> {
+    static RECIPES: OnceLock<HashMap<String, Recipe>> = OnceLock::new();
+    RECIPES.get_or_init(|| {
+        serde_json::from_str(LEVELS_JSON)
+            .unwrap_or_else(|e| panic!("assets/recipes/levels.json is invalid: {}", e))
+    })
+}
+
+/// Builds the chain of a named recipe
+pub fn recipe_builder(name: &str, depth: i32) -> Box<dyn MapBuilder> {
+    let recipe = recipes()
+        .get(name)
+        .unwrap_or_else(|| panic!("No map recipe named {:?}", name));
+    Box::new(recipe.chain(depth))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::GameRng;
+
+    #[test]
+    fn test_recipe_steps_read_with_parameters() {
+        let recipe: Recipe = serde_json::from_str(
+            r#"{
+                "name": "Eroded",
+                "start": { "Voronoi": { "seeds": 32, "distance": "Manhattan" } },
+                "steps": [
+                    { "RoomSorter": "Central" },
+                    "DoglegCorridors",
+                    { "CellularAutomataEroder": { "iterations": 2 } }
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            recipe.start,
+            InitialStep::Voronoi { seeds: 32, .. }
+        ));
+        assert!(matches!(
+            recipe.steps[2],
+            MetaStep::CellularAutomataEroder { iterations: 2 }
+        ));
+        assert!(serde_json::from_str::<MetaStep>(r#""Teleporters""#).is_err());
+    }
+
+    #[test]
+    fn test_every_recipe_builds_a_map() {
+        for (name, recipe) in recipes() {
+            let mut builder = recipe_builder(name, 5);
+            builder.build_map(&mut GameRng::seeded(7));
+            assert_eq!(builder.get_name(), recipe.name);
+            assert!(
+                builder.get_map().tiles.contains(&TileType::Floor),
+                "{}",
+                name
+            );
+        }
+    }
+}
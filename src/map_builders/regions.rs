@@ -0,0 +1,422 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use bevy::prelude::*;
+
+use crate::map::{Map, TileType, MAP_HEIGHT, MAP_WIDTH};
+use crate::shapes::Rect;
+
+use super::BuilderMap;
+
+// ============================================================================
+// Region Graph - Rooms and open areas, and the ways between them
+// ============================================================================
+
+/// How far apart open areas are seeded on maps without rooms
+const AREA_SPACING: i32 = 12;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RegionKind {
+    Room,
+    /// A stretch of cave or open ground, on maps without rooms
+    Area,
+}
+
+#[derive(Clone, Debug)]
+pub struct Region {
+    pub kind: RegionKind,
+    pub tiles: Vec<usize>,
+}
+
+/// A way between two regions: a corridor (with any doors in it), or the seam where they touch
+#[derive(Clone, Debug)]
+pub struct Passage {
+    pub from: usize,
+    pub to: usize,
+    pub tiles: Vec<usize>,
+}
+
+/// Rooms (or open areas, on maps without rooms) as nodes, and the corridors, doorways and seams
+/// between them as edges. Meta builders get it from `BuilderMap::region_graph` to ask where the
+/// dead ends and choke points are, and the finished level's is kept as a resource for the debug
+/// overlays.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct RegionGraph {
+    pub regions: Vec<Region>,
+    pub passages: Vec<Passage>,
+    /// Region each tile belongs to; corridors and walls belong to none
+    pub region_of: Vec<Option<usize>>,
+    /// Region the player arrives in
+    pub entrance: Option<usize>,
+    /// Region holding the stairs down, if the level has any
+    pub exit: Option<usize>,
+}
+
+impl RegionGraph {
+    pub fn new(build_data: &BuilderMap) -> Self {
+        Self::from_map(
+            &build_data.map,
+            build_data.rooms.as_deref(),
+            build_data.start_idx(),
+        )
+    }
+
+    /// The graph of a map, split into the given rooms or, without any, into open areas
+    pub fn from_map(map: &Map, rooms: Option<&[Rect]>, start: usize) -> Self {
+        let mut graph = Self {
+            region_of: vec![None; map.tiles.len()],
+            ..default()
+        };
+        match rooms {
+            Some(rooms) if !rooms.is_empty() => {
+                graph.add_rooms(map, rooms);
+                graph.connect_corridors(map);
+            }
+            _ => graph.add_areas(map),
+        }
+        graph.connect_seams(map);
+
+        graph.entrance = graph.region_near(map, start);
+        graph.exit = map
+            .tiles
+            .iter()
+            .position(|&tile| tile == TileType::DownStairs)
+            .and_then(|idx| graph.region_near(map, idx));
+        graph
+    }
+
+    fn add_region(&mut self, kind: RegionKind, tiles: Vec<usize>) {
+        for &idx in &tiles {
+            self.region_of[idx] = Some(self.regions.len());
+        }
+        self.regions.push(Region { kind, tiles });
+    }
+
+    /// One region per room, of the tiles in it that can be walked on
+    fn add_rooms(&mut self, map: &Map, rooms: &[Rect]) {
+        for room in rooms {
+            let tiles: Vec<usize> = (room.y1 + 1..=room.y2)
+                .flat_map(|y| (room.x1 + 1..=room.x2).map(move |x| map.xy_idx(x, y)))
+                .filter(|&idx| map.tiles[idx].is_walkable() && self.region_of[idx].is_none())
+                .collect();
+            if !tiles.is_empty() {
+                self.add_region(RegionKind::Room, tiles);
+            }
+        }
+    }
+
+    /// Splits the walkable tiles into areas grown out from seeds spread evenly over the map, each
+    /// tile going to whichever seed reaches it first. Anything cut off from every seed gets an
+    /// area of its own.
+    fn add_areas(&mut self, map: &Map) {
+        let mut owner: Vec<Option<usize>> = vec![None; map.tiles.len()];
+        let mut open = VecDeque::new();
+        let mut seeds = 0;
+        for cy in (0..MAP_HEIGHT as i32).step_by(AREA_SPACING as usize) {
+            for cx in (0..MAP_WIDTH as i32).step_by(AREA_SPACING as usize) {
+                // The walkable tile nearest the middle of the cell
+                let (mx, my) = (cx + AREA_SPACING / 2, cy + AREA_SPACING / 2);
+                let seed = (cy..(cy + AREA_SPACING).min(MAP_HEIGHT as i32))
+                    .flat_map(|y| {
+                        (cx..(cx + AREA_SPACING).min(MAP_WIDTH as i32)).map(move |x| (x, y))
+                    })
+                    .filter(|&(x, y)| map.tiles[map.xy_idx(x, y)].is_walkable())
+                    .min_by_key(|&(x, y)| (x - mx).pow(2) + (y - my).pow(2));
+                if let Some((x, y)) = seed {
+                    let idx = map.xy_idx(x, y);
+                    owner[idx] = Some(seeds);
+                    open.push_back(idx);
+                    seeds += 1;
+                }
+            }
+        }
+        Self::grow(map, &mut owner, open);
+
+        for idx in 0..map.tiles.len() {
+            if map.tiles[idx].is_walkable() && owner[idx].is_none() {
+                owner[idx] = Some(seeds);
+                seeds += 1;
+                Self::grow(map, &mut owner, VecDeque::from([idx]));
+            }
+        }
+
+        let mut areas = vec![Vec::new(); seeds];
+        for (idx, area) in owner.iter().enumerate() {
+            if let Some(area) = area {
+                areas[*area].push(idx);
+            }
+        }
+        for tiles in areas {
+            self.add_region(RegionKind::Area, tiles);
+        }
+    }
+
+    /// Spreads ownership from the open tiles to every walkable tile nobody owns yet
+    fn grow(map: &Map, owner: &mut [Option<usize>], mut open: VecDeque<usize>) {
+        while let Some(idx) = open.pop_front() {
+            for (next, _) in map.get_available_exits_ignoring_entities(idx) {
+                if owner[next].is_none() {
+                    owner[next] = owner[idx];
+                    open.push_back(next);
+                }
+            }
+        }
+    }
+
+    /// Links the rooms each run of corridor touches. A corridor that meets three or more rooms
+    /// links every pair of them.
+    fn connect_corridors(&mut self, map: &Map) {
+        let mut seen = vec![false; map.tiles.len()];
+        for start in 0..map.tiles.len() {
+            if seen[start] || !map.tiles[start].is_walkable() || self.region_of[start].is_some() {
+                continue;
+            }
+            seen[start] = true;
+            let mut corridor = Vec::new();
+            let mut touches = Vec::new();
+            let mut open = vec![start];
+            while let Some(idx) = open.pop() {
+                corridor.push(idx);
+                for (next, _) in map.get_available_exits_ignoring_entities(idx) {
+                    match self.region_of[next] {
+                        Some(region) if !touches.contains(&region) => touches.push(region),
+                        Some(_) => {}
+                        None if !seen[next] => {
+                            seen[next] = true;
+                            open.push(next);
+                        }
+                        None => {}
+                    }
+                }
+            }
+            touches.sort_unstable();
+            for (i, &from) in touches.iter().enumerate() {
+                for &to in &touches[i + 1..] {
+                    self.passages.push(Passage {
+                        from,
+                        to,
+                        tiles: corridor.clone(),
+                    });
+                }
+            }
+        }
+    }
+
+    /// Links regions whose tiles sit right next to each other
+    fn connect_seams(&mut self, map: &Map) {
+        let mut seams: BTreeMap<(usize, usize), Vec<usize>> = BTreeMap::new();
+        for idx in 0..map.tiles.len() {
+            let Some(from) = self.region_of[idx] else {
+                continue;
+            };
+            for (next, _) in map.get_available_exits_ignoring_entities(idx) {
+                match self.region_of[next] {
+                    Some(to) if to > from => seams.entry((from, to)).or_default().push(idx),
+                    Some(to) if to < from => seams.entry((to, from)).or_default().push(idx),
+                    _ => {}
+                }
+            }
+        }
+        for ((from, to), mut tiles) in seams {
+            tiles.sort_unstable();
+            tiles.dedup();
+            self.passages.push(Passage { from, to, tiles });
+        }
+    }
+
+    /// The region a tile is in, or the nearest one to it by walking
+    fn region_near(&self, map: &Map, idx: usize) -> Option<usize> {
+        let mut seen = vec![false; map.tiles.len()];
+        let mut open = VecDeque::from([idx]);
+        seen[idx] = true;
+        while let Some(idx) = open.pop_front() {
+            if let Some(region) = self.region_of[idx] {
+                return Some(region);
+            }
+            for (next, _) in map.get_available_exits_ignoring_entities(idx) {
+                if !seen[next] {
+                    seen[next] = true;
+                    open.push_back(next);
+                }
+            }
+        }
+        None
+    }
+
+    /// Regions next to the given one, each with the passage leading there
+    pub fn neighbours(&self, region: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.passages
+            .iter()
+            .enumerate()
+            .filter_map(move |(i, passage)| {
+                if passage.from == region {
+                    Some((i, passage.to))
+                } else if passage.to == region {
+                    Some((i, passage.from))
+                } else {
+                    None
+                }
+            })
+    }
+
+    /// Hops to every region from the given one and the region each was reached from, only
+    /// through the passages that are open. Unreachable regions have none.
+    fn search(&self, from: usize, open_passage: impl Fn(usize) -> bool) -> Vec<Option<(usize, usize)>> {
+        let mut found = vec![None; self.regions.len()];
+        found[from] = Some((0, from));
+        let mut open = VecDeque::from([from]);
+        while let Some(region) = open.pop_front() {
+            let hops = found[region].map_or(0, |(hops, _)| hops);
+            for (passage, next) in self.neighbours(region) {
+                if open_passage(passage) && found[next].is_none() {
+                    found[next] = Some((hops + 1, region));
+                    open.push_back(next);
+                }
+            }
+        }
+        found
+    }
+
+    /// How many passages away every region is from the given one
+    pub fn distances_from(&self, region: usize) -> Vec<Option<usize>> {
+        self.search(region, |_| true)
+            .into_iter()
+            .map(|found| found.map(|(hops, _)| hops))
+            .collect()
+    }
+
+    /// Like `distances_from`, but never through a passage or region with any of the given tiles
+    /// in it, so whatever sits there might as well be wall
+    pub fn distances_without(&self, region: usize, shut: &[usize]) -> Vec<Option<usize>> {
+        let closed = |tiles: &[usize]| tiles.iter().any(|idx| shut.contains(idx));
+        let closed_regions: Vec<bool> = self.regions.iter().map(|r| closed(&r.tiles)).collect();
+        if closed_regions[region] {
+            return vec![None; self.regions.len()];
+        }
+        self.search(region, |i| {
+            let passage = &self.passages[i];
+            !closed(&passage.tiles) && !closed_regions[passage.from] && !closed_regions[passage.to]
+        })
+        .into_iter()
+        .map(|found| found.map(|(hops, _)| hops))
+        .collect()
+    }
+
+    /// Regions with a way into only one other region
+    pub fn dead_ends(&self) -> Vec<usize> {
+        (0..self.regions.len())
+            .filter(|&region| {
+                let mut others: Vec<usize> = self.neighbours(region).map(|(_, to)| to).collect();
+                others.sort_unstable();
+                others.dedup();
+                others.len() == 1
+            })
+            .collect()
+    }
+
+    /// Passages that are the only way between the parts of the map on either side of them
+    pub fn choke_points(&self) -> Vec<usize> {
+        (0..self.passages.len())
+            .filter(|&i| {
+                let passage = &self.passages[i];
+                self.search(passage.from, |other| other != i)[passage.to].is_none()
+            })
+            .collect()
+    }
+
+    /// Regions on the fewest-hops way from the entrance to the exit, both included; empty if
+    /// either is missing or they aren't joined
+    pub fn critical_path(&self) -> Vec<usize> {
+        let (Some(entrance), Some(exit)) = (self.entrance, self.exit) else {
+            return Vec::new();
+        };
+        let found = self.search(entrance, |_| true);
+        let mut path = vec![exit];
+        let mut region = exit;
+        while region != entrance {
+            let Some((_, previous)) = found[region] else {
+                return Vec::new();
+            };
+            region = previous;
+            path.push(region);
+        }
+        path.reverse();
+        path
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::map_builders::common::{
+        apply_horizontal_tunnel, apply_room_to_map, apply_vertical_tunnel,
+    };
+
+    /// Three rooms in a row joined by corridors, with a fourth hanging off the middle one, and
+    /// the stairs down in the last of the row
+    pub(crate) fn rooms_in_a_row() -> (Map, Vec<Rect>) {
+        let mut map = Map::new(MAP_WIDTH, MAP_HEIGHT, 1);
+        let rooms = vec![
+            Rect::new(2, 2, 5, 5),
+            Rect::new(12, 2, 5, 5),
+            Rect::new(22, 2, 5, 5),
+            Rect::new(12, 14, 5, 5),
+        ];
+        for room in &rooms {
+            apply_room_to_map(&mut map, room);
+        }
+        apply_horizontal_tunnel(&mut map, 8, 12, 4);
+        apply_horizontal_tunnel(&mut map, 18, 22, 4);
+        apply_vertical_tunnel(&mut map, 8, 14, 15);
+        let stairs = map.xy_idx(24, 4);
+        map.tiles[stairs] = TileType::DownStairs;
+        (map, rooms)
+    }
+
+    #[test]
+    fn test_rooms_find_their_dead_ends_choke_points_and_critical_path() {
+        let (map, rooms) = rooms_in_a_row();
+        let graph = RegionGraph::from_map(&map, Some(&rooms), map.xy_idx(4, 4));
+
+        assert_eq!(graph.regions.len(), 4);
+        assert_eq!(graph.passages.len(), 3);
+        assert_eq!(graph.entrance, Some(0));
+        assert_eq!(graph.exit, Some(2));
+        assert_eq!(graph.critical_path(), vec![0, 1, 2]);
+
+        let mut dead_ends = graph.dead_ends();
+        dead_ends.sort_unstable();
+        assert_eq!(dead_ends, vec![0, 2, 3]);
+        // A tree of rooms: every corridor is the only way through
+        assert_eq!(graph.choke_points().len(), 3);
+        assert_eq!(graph.distances_from(0)[3], Some(2));
+
+        // Shutting the corridor to the far room leaves it out of reach
+        let corridor = map.xy_idx(20, 4);
+        let distances = graph.distances_without(0, &[corridor]);
+        assert_eq!(distances[1], Some(1));
+        assert_eq!(distances[2], None);
+        assert_eq!(distances[3], Some(2));
+    }
+
+    #[test]
+    fn test_open_areas_cover_every_walkable_tile_and_a_loop_has_no_choke_points() {
+        let mut map = Map::new(MAP_WIDTH, MAP_HEIGHT, 1);
+        // A ring of floor, a few tiles thick
+        for y in 5..30 {
+            for x in 5..60 {
+                if !(9..26).contains(&y) || !(9..56).contains(&x) {
+                    let idx = map.xy_idx(x, y);
+                    map.tiles[idx] = TileType::Floor;
+                }
+            }
+        }
+        let graph = RegionGraph::from_map(&map, None, map.xy_idx(5, 5));
+
+        assert!(graph.regions.len() > 2);
+        for idx in 0..map.tiles.len() {
+            assert_eq!(graph.region_of[idx].is_some(), map.tiles[idx].is_walkable());
+        }
+        assert!(graph.choke_points().is_empty());
+        assert!(graph.dead_ends().is_empty());
+    }
+}
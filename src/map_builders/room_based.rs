@@ -1,5 +1,6 @@
 use crate::map::TileType;
 use crate::rng::GameRng;
+use crate::theme::Theme;
 
 use super::{BuilderMap, MetaMapBuilder};
 
@@ -26,9 +27,12 @@ impl MetaMapBuilder for RoomBasedStartingPosition {
 }
 
 // ============================================================================
-// Room-Based Stairs (place in last room)
+// Room-Based Stairs (place in the room farthest from the start)
 // ============================================================================
 
+/// Puts the stairs down in the middle of the room that makes the critical path longest: the one
+/// the most passages away from where the player starts, the later room on a tie. Without a start
+/// to measure from, the last room. Rooms a room vault was stamped into are never picked.
 pub struct RoomBasedStairs;
 
 impl RoomBasedStairs {
@@ -39,10 +43,35 @@ impl RoomBasedStairs {
 
 impl MetaMapBuilder for RoomBasedStairs {
     fn build_map(&mut self, _rng: &mut GameRng, build_data: &mut BuilderMap) {
-        if let Some(ref rooms) = build_data.rooms {
-            if let Some(last_room) = rooms.last() {
-                let (x, y) = last_room.center();
-                let idx = build_data.map.xy_idx(x, y);
+        if let Some(rooms) = build_data.rooms.clone() {
+            let centers: Vec<usize> = rooms
+                .iter()
+                .map(|room| {
+                    let (x, y) = room.center();
+                    build_data.map.xy_idx(x, y)
+                })
+                .collect();
+            // Room vaults keep what was stamped into them
+            let ordinary: Vec<bool> = rooms
+                .iter()
+                .map(|room| build_data.room_type(room).is_none())
+                .collect();
+            let has_start = build_data.starting_position.is_some();
+            let graph = build_data.region_graph();
+            let hops = match graph.entrance {
+                Some(entrance) if has_start => graph.distances_from(entrance),
+                _ => Vec::new(),
+            };
+            let farthest = centers
+                .iter()
+                .enumerate()
+                .filter(|&(i, _)| ordinary[i])
+                .max_by_key(|&(i, &idx)| {
+                    let hops = graph.region_of[idx].and_then(|region| hops.get(region).copied().flatten());
+                    (hops, i)
+                })
+                .map(|(_, &idx)| idx);
+            if let Some(idx) = farthest {
                 build_data.map.tiles[idx] = TileType::DownStairs;
             }
         }
@@ -68,8 +97,11 @@ impl MetaMapBuilder for RoomBasedSpawner {
     fn build_map(&mut self, rng: &mut GameRng, build_data: &mut BuilderMap) {
         // Clone rooms to avoid borrow checker issues
         if let Some(rooms) = build_data.rooms.clone() {
+            // Room vaults bring their own inhabitants
             for room in rooms.iter().skip(1) {
-                spawn_room_entities(build_data, &room, rng);
+                if build_data.room_type(room).is_none() {
+                    spawn_room_entities(build_data, room, rng);
+                }
             }
         }
     }
@@ -84,17 +116,15 @@ fn spawn_room_entities(build_data: &mut BuilderMap, room: &crate::shapes::Rect,
         let idx = build_data.map.xy_idx(x, y);
 
         if build_data.map.tiles[idx] == TileType::Floor {
-            let roll = rng.0.gen_range(0..100);
-            let name = if roll < 10 {
-                "Health Potion"
-            } else if roll < 20 {
-                "Magic Missile Scroll"
-            } else if roll < 60 {
-                "Goblin"
+            let theme = Theme::for_depth(build_data.spawn_depth);
+            let table = if rng.0.gen_range(0..100) < 20 {
+                theme.item_table(build_data.spawn_depth)
             } else {
-                "Orc"
+                theme.monster_table(build_data.spawn_depth)
             };
-            build_data.spawn_list.push((idx, name.to_string()));
+            if let Some(name) = table.roll(rng) {
+                build_data.spawn_list.push((idx, name));
+            }
         }
     }
 }
@@ -1,4 +1,5 @@
 use rand::Rng;
+use serde::Deserialize;
 
 use crate::map::{Map, TileType, MAP_HEIGHT, MAP_WIDTH};
 use crate::rng::GameRng;
@@ -112,7 +113,7 @@ impl MetaMapBuilder for RoomCornerRounder {
 // RoomDrawer - Redraws rooms with configurable shapes
 // ============================================================================
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
 pub enum RoomShape {
     Rectangle,
     Circle,
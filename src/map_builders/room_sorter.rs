@@ -1,3 +1,5 @@
+use serde::Deserialize;
+
 use crate::map::{MAP_HEIGHT, MAP_WIDTH};
 use crate::rng::GameRng;
 
@@ -7,7 +9,7 @@ use super::{BuilderMap, MetaMapBuilder};
 // RoomSort - Sorting strategies for room ordering
 // ============================================================================
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
 pub enum RoomSort {
     /// Sort by leftmost x coordinate (x1)
     Leftmost,
@@ -0,0 +1,213 @@
+use rand::Rng;
+
+use crate::map::{TileType, MAP_HEIGHT, MAP_WIDTH};
+use crate::pathfinding::dijkstra_map;
+use crate::rng::GameRng;
+use crate::theme::Theme;
+
+use super::{BuilderMap, LevelFeature, MetaMapBuilder};
+
+// ============================================================================
+// SecretDoors - Hidden doors, secret passages and a sealed vault
+// ============================================================================
+
+/// One in this many doors is hidden
+const SECRET_DOOR_CHANCE: i32 = 4;
+/// Most secret passages cut through a wall on one level
+const MAX_PASSAGES: usize = 2;
+/// How much further the long way round has to be for a wall to be worth a secret passage
+const MIN_SHORTCUT: f32 = 25.0;
+/// Attempts at finding solid rock for the hidden vault
+const VAULT_ATTEMPTS: i32 = 200;
+
+/// Hides some of the doors already placed, opens secret passages through walls between parts of
+/// the map that are far apart the long way round, and digs a vault into solid rock behind a
+/// secret door. Secret doors are wall on the map, with a hidden door spawned on top; finding one
+/// turns the wall back into a doorway. The way from the start to the stairs never depends on
+/// finding one.
+pub struct SecretDoors;
+
+impl SecretDoors {
+    pub fn new() -> Box<Self> {
+        Box::new(Self)
+    }
+
+    /// Whether the stairs down (if the level has any) can still be reached from the start
+    fn exit_reachable(build_data: &BuilderMap) -> bool {
+        let Some(exit) = build_data
+            .map
+            .tiles
+            .iter()
+            .position(|&tile| tile == TileType::DownStairs)
+        else {
+            return true;
+        };
+        dijkstra_map(&build_data.map, &[build_data.start_idx()])[exit] < f32::MAX
+    }
+
+    /// Turns some of the doors in the spawn list into secret doors
+    fn hide_doors(rng: &mut GameRng, build_data: &mut BuilderMap) {
+        for i in 0..build_data.spawn_list.len() {
+            let (idx, ref name) = build_data.spawn_list[i];
+            if name != "Door" || rng.0.gen_range(0..SECRET_DOOR_CHANCE) != 0 {
+                continue;
+            }
+            let tile = build_data.map.tiles[idx];
+            build_data.map.tiles[idx] = TileType::Wall;
+            if Self::exit_reachable(build_data) {
+                build_data.spawn_list[i].1 = "Secret Door".to_string();
+            } else {
+                build_data.map.tiles[idx] = tile;
+            }
+        }
+    }
+
+    /// Finds wall tiles between two bits of floor that are far apart walking around, and puts
+    /// secret doors in some of them
+    fn open_passages(rng: &mut GameRng, build_data: &mut BuilderMap) {
+        let distances = dijkstra_map(&build_data.map, &[build_data.start_idx()]);
+        let map = &build_data.map;
+        let mut candidates = Vec::new();
+        for y in 1..MAP_HEIGHT as i32 - 1 {
+            for x in 1..MAP_WIDTH as i32 - 1 {
+                let idx = map.xy_idx(x, y);
+                if map.tiles[idx] != TileType::Wall {
+                    continue;
+                }
+                for (dx, dy) in [(1, 0), (0, 1)] {
+                    let (a, b) = (map.xy_idx(x - dx, y - dy), map.xy_idx(x + dx, y + dy));
+                    let (side_a, side_b) = (map.xy_idx(x - dy, y - dx), map.xy_idx(x + dy, y + dx));
+                    let open = map.tiles[a] == TileType::Floor && map.tiles[b] == TileType::Floor;
+                    let walled =
+                        map.tiles[side_a] == TileType::Wall && map.tiles[side_b] == TileType::Wall;
+                    let reachable = distances[a] < f32::MAX && distances[b] < f32::MAX;
+                    if open
+                        && walled
+                        && reachable
+                        && (distances[a] - distances[b]).abs() >= MIN_SHORTCUT
+                    {
+                        candidates.push(idx);
+                    }
+                }
+            }
+        }
+
+        for _ in 0..MAX_PASSAGES.min(candidates.len()) {
+            let idx = candidates.swap_remove(rng.0.gen_range(0..candidates.len()));
+            build_data.spawn_list.push((idx, "Secret Door".to_string()));
+        }
+    }
+
+    /// Digs a small room into solid rock next to the reachable part of the map, with a secret
+    /// door as its only way in and a few things worth finding inside
+    fn dig_vault(rng: &mut GameRng, build_data: &mut BuilderMap) {
+        let distances = dijkstra_map(&build_data.map, &[build_data.start_idx()]);
+        let floor: Vec<usize> = (0..build_data.map.tiles.len())
+            .filter(|&idx| {
+                build_data.map.tiles[idx] == TileType::Floor && distances[idx] < f32::MAX
+            })
+            .collect();
+        if floor.is_empty() {
+            return;
+        }
+
+        for _ in 0..VAULT_ATTEMPTS {
+            let idx = floor[rng.0.gen_range(0..floor.len())];
+            let (fx, fy) = ((idx % MAP_WIDTH) as i32, (idx / MAP_WIDTH) as i32);
+            let (dx, dy) = [(1, 0), (-1, 0), (0, 1), (0, -1)][rng.0.gen_range(0..4)];
+            let (w, h) = (rng.0.gen_range(3..=5), rng.0.gen_range(3..=5));
+
+            // The door is the wall next to the floor; the room starts one tile further on,
+            // centered across the way in
+            let door = (fx + dx, fy + dy);
+            let (x1, y1) = if dx != 0 {
+                (if dx > 0 { fx + 2 } else { fx - 1 - w }, fy - h / 2)
+            } else {
+                (fx - w / 2, if dy > 0 { fy + 2 } else { fy - 1 - h })
+            };
+            let (x2, y2) = (x1 + w - 1, y1 + h - 1);
+
+            // The room and a ring of rock around it all have to be solid wall, inside the map
+            if x1 < 2 || y1 < 2 || x2 > MAP_WIDTH as i32 - 3 || y2 > MAP_HEIGHT as i32 - 3 {
+                continue;
+            }
+            let solid = (y1 - 1..=y2 + 1).all(|y| {
+                (x1 - 1..=x2 + 1)
+                    .all(|x| build_data.map.tiles[build_data.map.xy_idx(x, y)] == TileType::Wall)
+            });
+            if !solid
+                || build_data.map.tiles[build_data.map.xy_idx(door.0, door.1)] != TileType::Wall
+            {
+                continue;
+            }
+
+            let mut room = Vec::new();
+            for y in y1..=y2 {
+                for x in x1..=x2 {
+                    let idx = build_data.map.xy_idx(x, y);
+                    build_data.map.tiles[idx] = TileType::Floor;
+                    room.push(idx);
+                }
+            }
+            let door_idx = build_data.map.xy_idx(door.0, door.1);
+            build_data
+                .spawn_list
+                .push((door_idx, "Secret Door".to_string()));
+
+            let table = Theme::for_depth(build_data.spawn_depth).item_table(build_data.spawn_depth);
+            for _ in 0..rng.0.gen_range(2..=3) {
+                let spot = room[rng.0.gen_range(0..room.len())];
+                if let Some(item) = table.roll(rng) {
+                    build_data.spawn_list.push((spot, item));
+                }
+            }
+            build_data.level.add_feature(LevelFeature::HiddenVault);
+            return;
+        }
+    }
+}
+
+impl MetaMapBuilder for SecretDoors {
+    fn build_map(&mut self, rng: &mut GameRng, build_data: &mut BuilderMap) {
+        Self::hide_doors(rng, build_data);
+        Self::open_passages(rng, build_data);
+        Self::dig_vault(rng, build_data);
+        build_data.take_snapshot();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map_builders::{
+        BuilderChain, DoglegCorridors, DoorPlacement, MapBuilder, RoomBasedStairs,
+        RoomBasedStartingPosition, SimpleMapRoomsBuilder,
+    };
+
+    #[test]
+    fn test_secret_doors_never_cut_off_the_stairs() {
+        let mut vaults = 0;
+        for seed in 0..10 {
+            let mut chain = BuilderChain::new(1, "Secrets")
+                .start_with(Box::new(SimpleMapRoomsBuilder::new(1)))
+                .with(DoglegCorridors::new())
+                .with(DoorPlacement::new())
+                .with(RoomBasedStartingPosition::new())
+                .with(RoomBasedStairs::new())
+                .with(SecretDoors::new());
+            chain.build_map(&mut GameRng::seeded(seed));
+
+            let build_data = &chain.build_data;
+            assert!(SecretDoors::exit_reachable(build_data), "seed {}", seed);
+            for (idx, name) in &build_data.spawn_list {
+                if name == "Secret Door" {
+                    assert_eq!(build_data.map.tiles[*idx], TileType::Wall, "seed {}", seed);
+                }
+            }
+            if build_data.level.features.contains(&LevelFeature::HiddenVault) {
+                vaults += 1;
+            }
+        }
+        assert!(vaults > 0);
+    }
+}
@@ -0,0 +1,236 @@
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::map::{Map, TileType, MAP_HEIGHT, MAP_WIDTH};
+use crate::rng::GameRng;
+
+use super::{BuilderMap, MetaMapBuilder};
+
+// ============================================================================
+// TerrainFeatures - Paint pools, patches and rock formations onto floors
+// ============================================================================
+
+/// What a TerrainFeatures pass paints
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum TerrainKind {
+    /// Deep water pools ringed by shallows
+    Water,
+    /// Lava pools ringed by gravel
+    Lava,
+    /// Chasms ringed by gravel
+    Chasm,
+    /// Grass patches
+    Vegetation,
+    /// Gravel patches with the odd stalagmite
+    Rubble,
+}
+
+pub struct TerrainFeatures {
+    kind: TerrainKind,
+    /// Number of blobs to paint
+    count: i32,
+    /// Random-walk steps per blob
+    size: i32,
+}
+
+impl TerrainFeatures {
+    pub fn new(kind: TerrainKind, count: i32, size: i32) -> Box<Self> {
+        Box::new(Self { kind, count, size })
+    }
+
+    pub fn water() -> Box<Self> {
+        Self::new(TerrainKind::Water, 4, 60)
+    }
+
+    pub fn vegetation() -> Box<Self> {
+        Self::new(TerrainKind::Vegetation, 6, 80)
+    }
+
+    pub fn rubble() -> Box<Self> {
+        Self::new(TerrainKind::Rubble, 5, 40)
+    }
+
+    /// Core and rim tiles for this kind of feature
+    fn palette(&self) -> (TileType, TileType) {
+        match self.kind {
+            TerrainKind::Water => (TileType::DeepWater, TileType::ShallowWater),
+            TerrainKind::Lava => (TileType::Lava, TileType::Gravel),
+            TerrainKind::Chasm => (TileType::Chasm, TileType::Gravel),
+            TerrainKind::Vegetation => (TileType::Grass, TileType::Grass),
+            TerrainKind::Rubble => (TileType::Gravel, TileType::Gravel),
+        }
+    }
+
+    /// Random-walks out a blob of floor tiles starting from a random floor tile
+    fn grow_blob(&self, rng: &mut GameRng, map: &Map) -> Vec<usize> {
+        let floors: Vec<usize> = (0..map.tiles.len())
+            .filter(|&idx| map.tiles[idx] == TileType::Floor)
+            .collect();
+        if floors.is_empty() {
+            return Vec::new();
+        }
+
+        let start = floors[rng.0.gen_range(0..floors.len())];
+        let mut x = start as i32 % MAP_WIDTH as i32;
+        let mut y = start as i32 / MAP_WIDTH as i32;
+        let mut blob = vec![start];
+
+        for _ in 0..self.size {
+            let (dx, dy) = match rng.0.gen_range(0..4) {
+                0 => (-1, 0),
+                1 => (1, 0),
+                2 => (0, -1),
+                _ => (0, 1),
+            };
+            let (nx, ny) = (x + dx, y + dy);
+            if nx < 1 || nx >= MAP_WIDTH as i32 - 1 || ny < 1 || ny >= MAP_HEIGHT as i32 - 1 {
+                continue;
+            }
+            let idx = map.xy_idx(nx, ny);
+            if map.tiles[idx] != TileType::Floor {
+                continue;
+            }
+            (x, y) = (nx, ny);
+            if !blob.contains(&idx) {
+                blob.push(idx);
+            }
+        }
+
+        blob
+    }
+}
+
+impl MetaMapBuilder for TerrainFeatures {
+    fn build_map(&mut self, rng: &mut GameRng, build_data: &mut BuilderMap) {
+        let (core, rim) = self.palette();
+        let start_idx = build_data
+            .starting_position
+            .map(|(x, y)| build_data.map.xy_idx(x, y));
+
+        for _ in 0..self.count {
+            // The player's arrival tile is left as it is
+            let mut blob = self.grow_blob(rng, &build_data.map);
+            blob.retain(|&idx| Some(idx) != start_idx);
+
+            // Only tiles entirely surrounded by the blob become the core; everything touching
+            // the rest of the map stays walkable rim
+            let in_blob = |x: i32, y: i32| blob.contains(&build_data.map.xy_idx(x, y));
+            let painted: Vec<(usize, TileType)> = blob
+                .iter()
+                .map(|&idx| {
+                    let x = idx as i32 % MAP_WIDTH as i32;
+                    let y = idx as i32 / MAP_WIDTH as i32;
+                    let surrounded = (-1..=1)
+                        .flat_map(|dy| (-1..=1).map(move |dx| (dx, dy)))
+                        .all(|(dx, dy)| in_blob(x + dx, y + dy));
+                    (idx, if surrounded { core } else { rim })
+                })
+                .collect();
+
+            // A ring-shaped blob could still wall off its middle, so undo any pool that splits
+            // the walkable area
+            let regions_before = walkable_regions(&build_data.map);
+            let previous = build_data.map.tiles.clone();
+            for (idx, tile) in painted {
+                build_data.map.tiles[idx] = tile;
+            }
+            if walkable_regions(&build_data.map) > regions_before {
+                build_data.map.tiles = previous;
+            }
+        }
+
+        // Rubble fields get stalagmites, but only in the open where they can't block a corridor
+        if self.kind == TerrainKind::Rubble {
+            for y in 1..MAP_HEIGHT as i32 - 1 {
+                for x in 1..MAP_WIDTH as i32 - 1 {
+                    let idx = build_data.map.xy_idx(x, y);
+                    let open = (-1..=1)
+                        .flat_map(|dy| (-1..=1).map(move |dx| (dx, dy)))
+                        .all(|(dx, dy)| {
+                            let tile = build_data.map.tiles[build_data.map.xy_idx(x + dx, y + dy)];
+                            tile == TileType::Floor || tile == TileType::Gravel
+                        });
+                    if open
+                        && build_data.map.tiles[idx] == TileType::Gravel
+                        && rng.0.gen_range(0..6) == 0
+                    {
+                        build_data.map.tiles[idx] = TileType::Stalagmite;
+                    }
+                }
+            }
+        }
+
+        build_data.take_snapshot();
+    }
+}
+
+/// Number of separate walkable areas, moving in eight directions
+fn walkable_regions(map: &Map) -> usize {
+    let mut seen = vec![false; map.tiles.len()];
+    let mut regions = 0;
+
+    for start in 0..map.tiles.len() {
+        if seen[start] || !map.tiles[start].is_walkable() {
+            continue;
+        }
+        regions += 1;
+        seen[start] = true;
+        let mut open = vec![start];
+        while let Some(idx) = open.pop() {
+            let x = idx as i32 % map.width;
+            let y = idx as i32 / map.width;
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    let (nx, ny) = (x + dx, y + dy);
+                    if nx < 0 || nx >= map.width || ny < 0 || ny >= map.height {
+                        continue;
+                    }
+                    let next = map.xy_idx(nx, ny);
+                    if !seen[next] && map.tiles[next].is_walkable() {
+                        seen[next] = true;
+                        open.push(next);
+                    }
+                }
+            }
+        }
+    }
+
+    regions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map_builders::{DoglegCorridors, InitialMapBuilder, SimpleMapRoomsBuilder};
+
+    #[test]
+    fn test_pools_never_split_the_map() {
+        for seed in 0..20 {
+            let mut rng = GameRng::seeded(seed);
+            let mut build_data = BuilderMap::new(1);
+            SimpleMapRoomsBuilder::new(1).build_map(&mut rng, &mut build_data);
+            DoglegCorridors::new().build_map(&mut rng, &mut build_data);
+            let regions = walkable_regions(&build_data.map);
+
+            TerrainFeatures::water().build_map(&mut rng, &mut build_data);
+            TerrainFeatures::new(TerrainKind::Lava, 3, 50).build_map(&mut rng, &mut build_data);
+            TerrainFeatures::rubble().build_map(&mut rng, &mut build_data);
+
+            assert_eq!(walkable_regions(&build_data.map), regions, "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn test_walkable_regions_counts_separate_areas() {
+        let mut map = Map::new(7, 3, 1);
+        for x in [1, 2, 4, 5] {
+            let idx = map.xy_idx(x, 1);
+            map.tiles[idx] = TileType::Floor;
+        }
+        assert_eq!(walkable_regions(&map), 2);
+
+        let idx = map.xy_idx(3, 1);
+        map.tiles[idx] = TileType::ShallowWater;
+        assert_eq!(walkable_regions(&map), 1);
+    }
+}
@@ -0,0 +1,195 @@
+use rand::Rng;
+
+use crate::map::{TileType, MAP_HEIGHT, MAP_WIDTH};
+use crate::rng::GameRng;
+use crate::shapes::Rect;
+
+use super::{BuilderMap, InitialMapBuilder, RoomType};
+
+// ============================================================================
+// TownBuilder - The town on the surface, above the way into the Abyss
+// ============================================================================
+
+const MIN_WIDTH: i32 = 7;
+const MAX_WIDTH: i32 = 12;
+const MIN_HEIGHT: i32 = 5;
+const MAX_HEIGHT: i32 = 8;
+/// Townsfolk out and about on the road
+const STROLLERS: i32 = 4;
+
+/// The named buildings, handed out biggest building first, and who keeps each of them
+const NAMED_BUILDINGS: [(RoomType, &str); 4] = [
+    (RoomType::Tavern, "Barkeep"),
+    (RoomType::Temple, "Priest"),
+    (RoomType::Blacksmith, "Blacksmith"),
+    (RoomType::Alchemist, "Alchemist"),
+];
+
+/// Grass cut east to west by a road, with a row of buildings fronting each side of it. The
+/// player walks in from the west end of the road; the dungeon entrance waits at the east end.
+///
+/// Doorways are left as one-tile corridors for `DoorPlacement` to hang doors in.
+pub struct TownBuilder;
+
+impl TownBuilder {
+    pub fn new() -> Box<Self> {
+        Box::new(Self)
+    }
+
+    /// Lays grass inside a wall border, and the road across the middle
+    fn lay_ground(build_data: &mut BuilderMap, road_y: i32) {
+        for y in 0..MAP_HEIGHT as i32 {
+            for x in 0..MAP_WIDTH as i32 {
+                let idx = build_data.map.xy_idx(x, y);
+                let edge =
+                    x == 0 || y == 0 || x == MAP_WIDTH as i32 - 1 || y == MAP_HEIGHT as i32 - 1;
+                build_data.map.tiles[idx] = if edge {
+                    TileType::Wall
+                } else if (road_y - 1..=road_y + 1).contains(&y) {
+                    TileType::Road
+                } else {
+                    TileType::Grass
+                };
+            }
+        }
+    }
+
+    /// Puts up a row of buildings along one side of the road, each with a doorway facing it
+    /// and a path out to it. Returns the buildings and their doorways.
+    fn build_row(
+        rng: &mut GameRng,
+        build_data: &mut BuilderMap,
+        road_y: i32,
+        north: bool,
+    ) -> Vec<(Rect, usize)> {
+        let mut buildings = Vec::new();
+        let mut x = rng.0.gen_range(2..=4);
+        loop {
+            let w = rng.0.gen_range(MIN_WIDTH..=MAX_WIDTH);
+            let h = rng.0.gen_range(MIN_HEIGHT..=MAX_HEIGHT);
+            if x + w >= MAP_WIDTH as i32 - 2 {
+                break;
+            }
+            // A front yard of one to three tiles between the building and the road
+            let yard = rng.0.gen_range(1..=3);
+            let y = if north {
+                road_y - 2 - yard - h
+            } else {
+                road_y + 2 + yard
+            };
+            let building = Rect::new(x, y, w, h);
+
+            for ty in building.y1..=building.y2 {
+                for tx in building.x1..=building.x2 {
+                    let idx = build_data.map.xy_idx(tx, ty);
+                    let wall = tx == building.x1
+                        || tx == building.x2
+                        || ty == building.y1
+                        || ty == building.y2;
+                    build_data.map.tiles[idx] = if wall {
+                        TileType::Wall
+                    } else {
+                        TileType::WoodFloor
+                    };
+                }
+            }
+
+            let (door_x, _) = building.center();
+            let (door_y, path) = if north {
+                (building.y2, building.y2 + 1..road_y - 1)
+            } else {
+                (building.y1, road_y + 2..building.y1)
+            };
+            let door = build_data.map.xy_idx(door_x, door_y);
+            build_data.map.tiles[door] = TileType::Floor;
+            for py in path {
+                let idx = build_data.map.xy_idx(door_x, py);
+                build_data.map.tiles[idx] = TileType::Road;
+            }
+
+            buildings.push((building, door));
+            x += w + rng.0.gen_range(2..=4);
+        }
+        buildings
+    }
+}
+
+impl InitialMapBuilder for TownBuilder {
+    fn build_map(&mut self, rng: &mut GameRng, build_data: &mut BuilderMap) {
+        let road_y = MAP_HEIGHT as i32 / 2;
+        Self::lay_ground(build_data, road_y);
+        build_data.take_snapshot();
+
+        let mut buildings = Self::build_row(rng, build_data, road_y, true);
+        buildings.extend(Self::build_row(rng, build_data, road_y, false));
+        build_data.take_snapshot();
+
+        // Each doorway is a corridor of its own, so every building gets a door
+        build_data.corridors = Some(buildings.iter().map(|&(_, door)| vec![door]).collect());
+
+        // The biggest buildings are the ones with names and keepers; some houses have someone home
+        buildings.sort_by_key(|(building, _)| {
+            -(building.x2 - building.x1) * (building.y2 - building.y1)
+        });
+        for (i, (building, _)) in buildings.iter().enumerate() {
+            let (cx, cy) = building.center();
+            let idx = build_data.map.xy_idx(cx, cy);
+            if let Some(&(room_type, keeper)) = NAMED_BUILDINGS.get(i) {
+                build_data.special_rooms.push((building.clone(), room_type));
+                build_data.spawn_list.push((idx, keeper.to_string()));
+            } else if rng.0.gen_bool(0.5) {
+                build_data.spawn_list.push((idx, "Townsperson".to_string()));
+            }
+        }
+
+        let start = (2, road_y);
+        let entrance = (MAP_WIDTH as i32 - 4, road_y);
+        for _ in 0..STROLLERS {
+            let x = rng.0.gen_range(start.0 + 3..entrance.0 - 2);
+            let y = rng.0.gen_range(road_y - 1..=road_y + 1);
+            build_data
+                .spawn_list
+                .push((build_data.map.xy_idx(x, y), "Townsperson".to_string()));
+        }
+
+        let entrance_idx = build_data.map.xy_idx(entrance.0, entrance.1);
+        build_data.map.tiles[entrance_idx] = TileType::DownStairs;
+        build_data.starting_position = Some(start);
+        build_data.level.arrival = Some(
+            "You walk into town along the old road. At its far end, stairs lead down into the \
+             Abyss, and somewhere at the bottom the Heart waits. Bring it back up into the light."
+                .to_string(),
+        );
+        build_data.take_snapshot();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_town_has_named_buildings_with_keepers_and_an_entrance() {
+        let mut build_data = BuilderMap::new(0);
+        TownBuilder::new().build_map(&mut GameRng::seeded(3), &mut build_data);
+
+        for (room_type, keeper) in NAMED_BUILDINGS {
+            let (building, _) = build_data
+                .special_rooms
+                .iter()
+                .find(|&&(_, t)| t == room_type)
+                .unwrap();
+            let (cx, cy) = building.center();
+            assert!(build_data
+                .spawn_list
+                .contains(&(build_data.map.xy_idx(cx, cy), keeper.to_string())));
+        }
+        // Every building has its doorway in a wall
+        let corridors = build_data.corridors.as_ref().unwrap();
+        assert!(corridors.len() >= NAMED_BUILDINGS.len());
+        assert!(corridors
+            .iter()
+            .all(|doorway| build_data.map.tiles[doorway[0]] == TileType::Floor));
+        assert!(build_data.map.tiles.contains(&TileType::DownStairs));
+    }
+}
@@ -0,0 +1,92 @@
+use rand::Rng;
+
+use crate::map::{TileType, MAP_WIDTH};
+use crate::rng::GameRng;
+use crate::theme::Theme;
+
+use super::{BuilderMap, MetaMapBuilder};
+
+// ============================================================================
+// DeadEndTreasure - Something worth the walk at the end of every dead end
+// ============================================================================
+
+/// How much deeper than the level dead-end treasure is rolled
+const TREASURE_DEPTH_BONUS: i32 = 2;
+
+/// Leaves an item in every room or area with only one way in, apart from where the player starts,
+/// the room with the stairs down and room vaults, which bring their own. Rolled from the item
+/// table of a little deeper than the level, so the detour pays.
+pub struct DeadEndTreasure;
+
+impl DeadEndTreasure {
+    pub fn new() -> Box<Self> {
+        Box::new(Self)
+    }
+
+    /// Whether a tile is inside a room a room vault was stamped into
+    fn in_vault(build_data: &BuilderMap, idx: usize) -> bool {
+        let x = (idx % MAP_WIDTH) as i32;
+        let y = (idx / MAP_WIDTH) as i32;
+        build_data
+            .special_rooms
+            .iter()
+            .any(|(room, _)| x > room.x1 && x <= room.x2 && y > room.y1 && y <= room.y2)
+    }
+}
+
+impl MetaMapBuilder for DeadEndTreasure {
+    fn build_map(&mut self, rng: &mut GameRng, build_data: &mut BuilderMap) {
+        let graph = build_data.region_graph().clone();
+        let depth = build_data.spawn_depth;
+        let table = Theme::for_depth(depth).item_table(depth + TREASURE_DEPTH_BONUS);
+
+        for region in graph.dead_ends() {
+            if Some(region) == graph.entrance || Some(region) == graph.exit {
+                continue;
+            }
+            let tiles = &graph.regions[region].tiles;
+            if tiles.iter().any(|&idx| Self::in_vault(build_data, idx)) {
+                continue;
+            }
+            let spots: Vec<usize> = tiles
+                .iter()
+                .copied()
+                .filter(|&idx| {
+                    build_data.map.tiles[idx] == TileType::Floor
+                        && !build_data.spawn_list.iter().any(|&(taken, _)| taken == idx)
+                })
+                .collect();
+            if spots.is_empty() {
+                continue;
+            }
+            let idx = spots[rng.0.gen_range(0..spots.len())];
+            if let Some(name) = table.roll(rng) {
+                build_data.spawn_list.push((idx, name));
+            }
+        }
+        build_data.take_snapshot();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map_builders::regions::tests::rooms_in_a_row;
+
+    #[test]
+    fn test_treasure_waits_only_in_dead_ends_off_the_way_down() {
+        let (map, rooms) = rooms_in_a_row();
+        let mut build_data = BuilderMap::new(3);
+        build_data.map = map;
+        build_data.starting_position = Some((4, 4));
+        build_data.rooms = Some(rooms.clone());
+
+        // The side room is the only dead end that isn't the start or the way down
+        DeadEndTreasure.build_map(&mut GameRng::seeded(1), &mut build_data);
+        assert_eq!(build_data.spawn_list.len(), 1);
+        let (idx, _) = build_data.spawn_list[0];
+        let (x, y) = ((idx % MAP_WIDTH) as i32, (idx / MAP_WIDTH) as i32);
+        let side_room = &rooms[3];
+        assert!(x > side_room.x1 && x <= side_room.x2 && y > side_room.y1 && y <= side_room.y2);
+    }
+}
@@ -21,7 +21,7 @@ pub struct VoronoiCellBuilder {
 }
 
 impl VoronoiCellBuilder {
-    fn new(depth: i32, n_seeds: usize, distance_algorithm: DistanceAlg) -> Self {
+    pub fn new(depth: i32, n_seeds: usize, distance_algorithm: DistanceAlg) -> Self {
         Self {
             map: Map::new(MAP_WIDTH, MAP_HEIGHT, depth),
             starting_position: (MAP_WIDTH as i32 / 2, MAP_HEIGHT as i32 / 2),
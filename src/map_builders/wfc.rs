@@ -1,5 +1,6 @@
 use bevy::prelude::*;
 use rand::Rng;
+use serde::Deserialize;
 use std::collections::{HashMap, HashSet};
 
 use crate::map::{Map, TileType, MAP_HEIGHT, MAP_WIDTH};
@@ -438,7 +439,7 @@ impl WfcSolver {
 // WfcSourceType - Which generator to use for source patterns
 // ============================================================================
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Deserialize)]
 pub enum WfcSourceType {
     CellularAutomata,
     BspDungeon,
@@ -698,7 +699,12 @@ impl MetaMapBuilder for WfcBuilder {
 
             let mut solver = WfcSolver::new(&source, self.chunk_size);
 
-            if solver.solve(rng, &mut build_data.map, &mut build_data.history) {
+            let mut frames = Vec::new();
+            let solved = solver.solve(rng, &mut build_data.map, &mut frames);
+            for frame in frames {
+                build_data.take_snapshot_of(frame);
+            }
+            if solved {
                 solver.render_to_map(&mut build_data.map);
                 build_data.take_snapshot();
                 success = true;
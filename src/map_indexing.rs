@@ -1,30 +1,52 @@
 use bevy::prelude::*;
 
 use crate::components::{BlocksTile, BlocksVisibility};
-use crate::map::{Map, Position};
+use crate::map::{Footprint, Map, Position};
 
 pub fn map_indexing_system(
     mut map: ResMut<Map>,
-    query: Query<(Entity, &Position, Option<&BlocksTile>, Option<&BlocksVisibility>)>,
+    query: Query<(
+        Entity,
+        &Position,
+        Option<&Footprint>,
+        Option<&BlocksTile>,
+        Option<&BlocksVisibility>,
+    )>,
 ) {
-    map.populate_blocked();
-    map.clear_content_index();
-    map.view_blocked.clear();
+    // Rebuilt every frame, but the map only counts as changed when the index comes out different
+    let before = (
+        map.blocked_tiles.clone(),
+        map.view_blocked.clone(),
+        map.tile_content.clone(),
+    );
+    let index = map.bypass_change_detection();
+    index.populate_blocked();
+    index.clear_content_index();
+    index.view_blocked.clear();
 
-    for (entity, position, blocks_tile, blocks_visibility) in &query {
-        let idx = map.xy_idx(position.x, position.y);
+    for (entity, position, footprint, blocks_tile, blocks_visibility) in &query {
+        // Large monsters are indexed on every tile they cover
+        for (x, y) in footprint.copied().unwrap_or_default().cells(*position) {
+            let idx = index.xy_idx(x, y);
 
-        // If they block movement, update the blocking list
-        if blocks_tile.is_some() {
-            map.blocked_tiles[idx] = true;
-        }
+            // If they block movement, update the blocking list
+            if blocks_tile.is_some() {
+                index.blocked_tiles[idx] = true;
+            }
+
+            // If they block visibility, update the view_blocked set
+            if blocks_visibility.is_some() {
+                index.view_blocked.insert(idx);
+            }
 
-        // If they block visibility, update the view_blocked set
-        if blocks_visibility.is_some() {
-            map.view_blocked.insert(idx);
+            // Push the entity to the appropriate index slot
+            index.tile_content[idx].push(entity);
         }
+    }
 
-        // Push the entity to the appropriate index slot
-        map.tile_content[idx].push(entity);
+    if (&map.blocked_tiles, &map.view_blocked, &map.tile_content)
+        != (&before.0, &before.1, &before.2)
+    {
+        map.set_changed();
     }
 }
@@ -0,0 +1,486 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::camera::Camera;
+use crate::console::{Cell, Console, ConsoleSet, Layer};
+use crate::map::{Map, TileType};
+use crate::map_builders::Snapshot;
+use crate::morgue::map_dump;
+use crate::pathfinding::dijkstra_map;
+use crate::resources::UiFont;
+use crate::tilemap::{self, MapPreview};
+use crate::{MapGenBuilderName, MapGenHistory, MapGenIndex, MapGenTimer, RunState};
+
+// ============================================================================
+// Map Generation Debugger - Stepping through the snapshots a builder took
+// ============================================================================
+
+/// Seconds between snapshots while playing, fastest first
+const SPEEDS: [f32; 6] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.0];
+/// Half a second a snapshot, the pace the visualizer always had
+const DEFAULT_SPEED: usize = 3;
+
+/// What is drawn over the snapshot
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Overlays {
+    pub rooms: bool,
+    pub corridors: bool,
+    pub start: bool,
+    /// Walking distance from the start, in tenths of the farthest; unreachable floor in red
+    pub distances: bool,
+}
+
+/// How the snapshots are being played back
+#[derive(Resource, Debug)]
+pub struct MapGenPlayback {
+    pub paused: bool,
+    /// Index into `SPEEDS`
+    pub speed: usize,
+    pub overlays: Overlays,
+    /// Where the last export was written
+    pub exported: Option<String>,
+}
+
+impl Default for MapGenPlayback {
+    fn default() -> Self {
+        Self {
+            paused: false,
+            speed: DEFAULT_SPEED,
+            overlays: Overlays::default(),
+            exported: None,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct MapGenUI;
+
+#[derive(Component)]
+struct MapGenText;
+
+pub struct MapGenPlugin;
+
+impl Plugin for MapGenPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MapGenPlayback>()
+            .add_systems(OnEnter(RunState::MapGeneration), spawn_panel)
+            .add_systems(
+                Update,
+                (
+                    playback_input,
+                    advance_playback,
+                    show_snapshot,
+                    update_panel,
+                )
+                    .chain()
+                    .run_if(in_state(RunState::MapGeneration)),
+            )
+            .add_systems(
+                PostUpdate,
+                draw_overlays
+                    .after(tilemap::draw_map_layer)
+                    .in_set(ConsoleSet::Draw)
+                    .run_if(in_state(RunState::MapGeneration)),
+            )
+            .add_systems(OnExit(RunState::MapGeneration), despawn_panel);
+    }
+}
+
+/// Whether a snapshot has anything to look at yet
+fn has_floors(snapshot: &Snapshot) -> bool {
+    snapshot.map.tiles.contains(&TileType::Floor)
+}
+
+/// The nearest snapshot after (or before) the given one that has something to look at
+fn step(snapshots: &[Snapshot], from: usize, forward: bool) -> Option<usize> {
+    if forward {
+        (from + 1..snapshots.len()).find(|&i| has_floors(&snapshots[i]))
+    } else {
+        (0..from).rev().find(|&i| has_floors(&snapshots[i]))
+    }
+}
+
+fn playback_input(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    history: Res<MapGenHistory>,
+    builder_name: Res<MapGenBuilderName>,
+    mut playback: ResMut<MapGenPlayback>,
+    mut index: ResMut<MapGenIndex>,
+    mut timer: ResMut<MapGenTimer>,
+    mut preview: ResMut<MapPreview>,
+) {
+    let snapshots = &history.0;
+    if snapshots.is_empty() {
+        return;
+    }
+
+    for key in keyboard.get_just_pressed() {
+        match key {
+            KeyCode::KeyP => playback.paused = !playback.paused,
+            // Stepping by hand pauses, so the step stays on screen
+            KeyCode::ArrowRight | KeyCode::KeyL | KeyCode::ArrowLeft | KeyCode::KeyH => {
+                let forward = matches!(key, KeyCode::ArrowRight | KeyCode::KeyL);
+                playback.paused = true;
+                if let Some(next) = step(snapshots, index.0, forward) {
+                    index.0 = next;
+                }
+            }
+            KeyCode::Home => index.0 = step(snapshots, 0, true).unwrap_or(0),
+            KeyCode::End => index.0 = snapshots.len() - 1,
+            KeyCode::Equal | KeyCode::NumpadAdd | KeyCode::Minus | KeyCode::NumpadSubtract => {
+                playback.speed = if matches!(key, KeyCode::Equal | KeyCode::NumpadAdd) {
+                    playback.speed.saturating_sub(1)
+                } else {
+                    (playback.speed + 1).min(SPEEDS.len() - 1)
+                };
+                timer
+                    .0
+                    .set_duration(Duration::from_secs_f32(SPEEDS[playback.speed]));
+            }
+            KeyCode::Digit1 | KeyCode::Digit2 | KeyCode::Digit3 | KeyCode::Digit4 => {
+                let overlays = &mut playback.overlays;
+                let overlay = match key {
+                    KeyCode::Digit1 => &mut overlays.rooms,
+                    KeyCode::Digit2 => &mut overlays.corridors,
+                    KeyCode::Digit3 => &mut overlays.start,
+                    _ => &mut overlays.distances,
+                };
+                *overlay = !*overlay;
+                // Redraws the map layer, so overlays that were turned off go away
+                preview.set_changed();
+            }
+            KeyCode::KeyX => {
+                playback.exported = write(&export_text(&builder_name.0, snapshots, index.0));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Moves on a snapshot whenever the timer runs out, stopping at the last
+fn advance_playback(
+    time: Res<Time>,
+    playback: Res<MapGenPlayback>,
+    history: Res<MapGenHistory>,
+    mut timer: ResMut<MapGenTimer>,
+    mut index: ResMut<MapGenIndex>,
+) {
+    if playback.paused {
+        return;
+    }
+    timer.0.tick(time.delta());
+    if timer.0.just_finished() {
+        if let Some(next) = step(&history.0, index.0, true) {
+            index.0 = next;
+        }
+    }
+}
+
+fn show_snapshot(
+    history: Res<MapGenHistory>,
+    index: Res<MapGenIndex>,
+    mut preview: ResMut<MapPreview>,
+) {
+    if !history.is_changed() && !index.is_changed() {
+        return;
+    }
+    preview.0 = history.0.get(index.0).map(|snapshot| snapshot.map.clone());
+}
+
+// ============================================================================
+// Overlays
+// ============================================================================
+
+/// Draws the overlays that are on into the console's map layer, each time the map layer is
+/// redrawn underneath them. Room and corridor tiles are recolored; distances and the start
+/// replace the glyph.
+fn draw_overlays(
+    mut console: ResMut<Console>,
+    camera: Res<Camera>,
+    map: Res<Map>,
+    preview: Res<MapPreview>,
+    history: Res<MapGenHistory>,
+    index: Res<MapGenIndex>,
+    playback: Res<MapGenPlayback>,
+) {
+    if !preview.is_changed() && !camera.is_changed() && !map.is_changed() {
+        return;
+    }
+    let Some(snapshot) = history.0.get(index.0) else {
+        return;
+    };
+    let overlays = playback.overlays;
+    let width = snapshot.map.width;
+    let mut put = |idx: usize, glyph: Option<char>, fg: Color| {
+        let (x, y) = (idx as i32 % width, idx as i32 / width);
+        if !camera.in_bounds(x, y) {
+            return;
+        }
+        let (sx, sy) = camera.world_to_screen(x, y);
+        let glyph = glyph
+            .or_else(|| console.get(Layer::Map, sx, sy).map(|cell| cell.glyph))
+            .unwrap_or(' ');
+        console.set(Layer::Map, sx, sy, Cell::new(glyph, fg));
+    };
+
+    if overlays.rooms {
+        for (i, room) in snapshot.rooms.iter().enumerate() {
+            let color = Color::hsl((i as f32 * 137.5) % 360.0, 0.8, 0.6);
+            for y in room.y1 + 1..=room.y2 {
+                for x in room.x1 + 1..=room.x2 {
+                    put(snapshot.map.xy_idx(x, y), None, color);
+                }
+            }
+        }
+    }
+    if overlays.corridors {
+        for corridor in &snapshot.corridors {
+            for &idx in corridor {
+                put(idx, None, Color::srgb(1.0, 0.6, 0.0));
+            }
+        }
+    }
+
+    let start = snapshot
+        .starting_position
+        .map(|(x, y)| snapshot.map.xy_idx(x, y));
+    if let (true, Some(start)) = (overlays.distances, start) {
+        let distances = dijkstra_map(&snapshot.map, &[start]);
+        let farthest = distances
+            .iter()
+            .copied()
+            .filter(|&d| d < f32::MAX)
+            .fold(1.0, f32::max);
+        for (idx, &distance) in distances.iter().enumerate() {
+            if !snapshot.map.tiles[idx].is_walkable() {
+                continue;
+            }
+            if distance == f32::MAX {
+                put(idx, Some('!'), Color::srgb(1.0, 0.0, 0.0));
+                continue;
+            }
+            // Blue near the start, through green, to red at the far end
+            let t = distance / farthest;
+            let band = ((t * 9.0).round() as u32).min(9);
+            let glyph = char::from_digit(band, 10);
+            put(idx, glyph, Color::hsl(240.0 * (1.0 - t), 0.9, 0.6));
+        }
+    }
+    if let (true, Some(start)) = (overlays.start, start) {
+        put(start, Some('@'), Color::srgb(1.0, 0.0, 1.0));
+    }
+}
+
+// ============================================================================
+// Panel
+// ============================================================================
+
+fn spawn_panel(mut commands: Commands, font: Res<UiFont>) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.0),
+                left: Val::Px(10.0),
+                padding: UiRect::all(Val::Px(10.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.8)),
+            MapGenUI,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                MapGenText,
+                Text::default(),
+                TextFont {
+                    font: font.0.clone(),
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(1.0, 1.0, 0.0)),
+            ));
+        });
+}
+
+fn update_panel(
+    history: Res<MapGenHistory>,
+    index: Res<MapGenIndex>,
+    playback: Res<MapGenPlayback>,
+    builder_name: Res<MapGenBuilderName>,
+    mut text: Query<(Ref<MapGenText>, &mut Text)>,
+) {
+    let changed = history.is_changed()
+        || index.is_changed()
+        || playback.is_changed()
+        || builder_name.is_changed();
+    for (marker, mut text) in &mut text {
+        if changed || marker.is_added() {
+            text.0 = panel_text(&builder_name.0, &history.0, index.0, &playback);
+        }
+    }
+}
+
+fn panel_text(
+    builder: &str,
+    snapshots: &[Snapshot],
+    index: usize,
+    playback: &MapGenPlayback,
+) -> String {
+    let step = match snapshots.get(index) {
+        Some(snapshot) => format!(
+            "Snapshot {}/{}: {}",
+            index + 1,
+            snapshots.len(),
+            snapshot.label
+        ),
+        None => "No snapshots".to_string(),
+    };
+    let state = if playback.paused {
+        "Paused".to_string()
+    } else {
+        format!("Playing, {}s a snapshot", SPEEDS[playback.speed])
+    };
+    let overlays = playback.overlays;
+    let on = |on: bool| if on { "*" } else { " " };
+    let mut text = format!(
+        "Builder: {}\n{}\n{}\n\n1:Rooms{} 2:Corridors{} 3:Start{} 4:Distances{}\n\
+         P: Pause  Left/Right: Step  Home/End: First/Last\n\
+         -/+: Slower/Faster  X: Export\nSpace: Regenerate  Esc: Back",
+        builder,
+        step,
+        state,
+        on(overlays.rooms),
+        on(overlays.corridors),
+        on(overlays.start),
+        on(overlays.distances),
+    );
+    if let Some(path) = &playback.exported {
+        text.push_str(&format!("\n\nExported to {}", path));
+    }
+    text
+}
+
+fn despawn_panel(mut commands: Commands, ui_query: Query<Entity, With<MapGenUI>>) {
+    for entity in &ui_query {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+// ============================================================================
+// Export
+// ============================================================================
+
+/// A snapshot as text: which step took it and what it knew, then the map with the start marked
+pub fn export_text(builder: &str, snapshots: &[Snapshot], index: usize) -> String {
+    let snapshot = &snapshots[index];
+    let mut text = format!(
+        "{}\nSnapshot {}/{}: {}\nRooms: {}  Corridors: {}\n",
+        builder,
+        index + 1,
+        snapshots.len(),
+        snapshot.label,
+        snapshot.rooms.len(),
+        snapshot.corridors.len()
+    );
+    if let Some((x, y)) = snapshot.starting_position {
+        text.push_str(&format!("Start: ({}, {})\n", x, y));
+    }
+    text.push('\n');
+
+    let start: Vec<(i32, i32, char)> = snapshot
+        .starting_position
+        .map(|(x, y)| (x, y, '@'))
+        .into_iter()
+        .collect();
+    text.push_str(&map_dump(&snapshot.map, &start));
+    text.push('\n');
+    text
+}
+
+/// Writes an export next to savegame.json, named by the time it was taken
+#[cfg(not(target_arch = "wasm32"))]
+fn write(text: &str) -> Option<String> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    let path = format!("mapgen-{}.txt", timestamp);
+
+    match fs::write(&path, text) {
+        Ok(()) => {
+            info!("Snapshot exported to {}", path);
+            Some(path)
+        }
+        Err(e) => {
+            error!("Failed to export snapshot: {}", e);
+            None
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write(_text: &str) -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map_builders::{BuilderChain, DoglegCorridors, MapBuilder, SimpleMapRoomsBuilder};
+    use crate::rng::GameRng;
+
+    fn chain_snapshots() -> Vec<Snapshot> {
+        let mut chain = BuilderChain::new(1, "Debugger")
+            .start_with(Box::new(SimpleMapRoomsBuilder::new(1)))
+            .with(DoglegCorridors::new());
+        chain.build_map(&mut GameRng::seeded(1));
+        chain.get_snapshots()
+    }
+
+    #[test]
+    fn test_snapshots_are_labelled_with_the_step_that_took_them() {
+        let snapshots = chain_snapshots();
+        assert!(snapshots
+            .iter()
+            .any(|snapshot| snapshot.label == "1. SimpleMapRoomsBuilder"));
+        let last = snapshots.last().unwrap();
+        assert_eq!(last.label, "2. DoglegCorridors");
+        assert!(!last.corridors.is_empty());
+    }
+
+    #[test]
+    fn test_stepping_skips_snapshots_with_nothing_to_see() {
+        let full = chain_snapshots().pop().unwrap();
+        let empty = Snapshot {
+            map: Map::new(4, 4, 1),
+            ..full.clone()
+        };
+        let snapshots = vec![empty.clone(), full.clone(), empty, full];
+
+        assert_eq!(step(&snapshots, 0, true), Some(1));
+        assert_eq!(step(&snapshots, 1, true), Some(3));
+        assert_eq!(step(&snapshots, 3, false), Some(1));
+        assert_eq!(step(&snapshots, 1, false), None);
+        assert_eq!(step(&snapshots, 3, true), None);
+    }
+
+    #[test]
+    fn test_export_names_the_step_and_marks_the_start() {
+        let mut snapshots = chain_snapshots();
+        let last = snapshots.len() - 1;
+        snapshots[last].starting_position = Some((2, 1));
+
+        let text = export_text("Debugger", &snapshots, last);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "Debugger");
+        assert!(lines[1].ends_with(": 2. DoglegCorridors"));
+        assert_eq!(lines[3], "Start: (2, 1)");
+        assert_eq!(lines[5 + 1].chars().nth(2), Some('@'));
+    }
+}
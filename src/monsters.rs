@@ -2,11 +2,11 @@ use bevy::prelude::*;
 
 use crate::{
     combat::{CombatStats, WantsToMelee},
-    components::{Confusion, Name},
-    distance::DistanceAlg,
-    gamelog::GameLog,
-    map::{Map, Position, TileType, MAP_HEIGHT, MAP_WIDTH},
-    particle::ParticleBuilder,
+    components::{Confusion, Door, Item, Name, OpensDoors},
+    doors,
+    effects::{EffectEvent, Targets},
+    gamelog::{GameLog, LogCategory},
+    map::{Footprint, Map, Position, MAP_HEIGHT, MAP_WIDTH},
     pathfinding,
     player::Player,
     viewshed::Viewshed,
@@ -26,17 +26,22 @@ impl Plugin for MonstersPlugin {
     }
 }
 
-fn update_blocked_tiles(mut map: ResMut<Map>, monster_query: Query<&Position, With<Monster>>) {
+fn update_blocked_tiles(
+    mut map: ResMut<Map>,
+    monster_query: Query<(&Position, Option<&Footprint>), With<Monster>>,
+) {
     // Clear blocked tiles and re-populate from walls
     let size = MAP_WIDTH * MAP_HEIGHT;
     for idx in 0..size {
-        map.blocked_tiles[idx] = map.tiles[idx] == TileType::Wall;
+        map.blocked_tiles[idx] = !map.tiles[idx].is_walkable();
     }
 
     // Block tiles with monsters
-    for pos in &monster_query {
-        let idx = map.xy_idx(pos.x, pos.y);
-        map.blocked_tiles[idx] = true;
+    for (pos, footprint) in &monster_query {
+        for (x, y) in footprint.copied().unwrap_or_default().cells(*pos) {
+            let idx = map.xy_idx(x, y);
+            map.blocked_tiles[idx] = true;
+        }
     }
 }
 
@@ -44,17 +49,33 @@ pub fn monster_ai(
     mut commands: Commands,
     mut map: ResMut<Map>,
     mut gamelog: ResMut<GameLog>,
-    mut particle_builder: ResMut<ParticleBuilder>,
+    mut effects: EventWriter<EffectEvent>,
     mut monster_query: Query<
-        (Entity, &mut Position, &mut Viewshed, &Name, &CombatStats, Option<&mut Confusion>),
+        (
+            Entity,
+            &mut Position,
+            &mut Viewshed,
+            &Name,
+            &CombatStats,
+            Option<&mut Confusion>,
+            Option<&Footprint>,
+            Has<OpensDoors>,
+        ),
         (With<Monster>, Without<Player>),
     >,
     player_query: Single<(Entity, &Position), With<Player>>,
+    mut doors: Query<(Entity, &mut Door, &mut Text2d)>,
+    items: Query<(), With<Item>>,
 ) {
     let (player_entity, player_pos) = player_query.into_inner();
     let player_idx = map.xy_idx(player_pos.x, player_pos.y);
+    // Where large monsters fit; only worked out if there are any
+    let mut clearance = None;
 
-    for (entity, mut pos, mut viewshed, name, stats, confusion) in &mut monster_query {
+    for (entity, mut pos, mut viewshed, name, stats, confusion, footprint, opens_doors) in
+        &mut monster_query
+    {
+        let footprint = footprint.copied().unwrap_or_default();
         if stats.hp <= 0 {
             continue;
         }
@@ -64,27 +85,25 @@ pub fn monster_ai(
             confused.turns -= 1;
             if confused.turns < 1 {
                 commands.entity(entity).remove::<Confusion>();
-                gamelog.entries.push(format!("{} is no longer confused.", name.name));
+                gamelog.log(LogCategory::Status, format!("{} is no longer confused.", name.name));
             } else {
-                gamelog.entries.push(format!("{} is confused and stumbles around.", name.name));
+                gamelog.log(
+                    LogCategory::Status,
+                    format!("{} is confused and stumbles around.", name.name),
+                );
 
                 // Spawn confusion particle
-                particle_builder.request(
-                    pos.x,
-                    pos.y,
+                effects.send(EffectEvent::particle(
                     "?",
                     Color::srgb(1.0, 0.0, 1.0), // Magenta
-                    200.0,
-                );
+                    Targets::Tile { x: pos.x, y: pos.y },
+                ));
             }
             continue; // Skip normal AI while confused
         }
 
-        let distance = DistanceAlg::Chebyshev.distance2d(
-            Vec2::new(pos.x as f32, pos.y as f32),
-            Vec2::new(player_pos.x as f32, player_pos.y as f32),
-        );
-        if distance < 1.5 {
+        // Large monsters strike from any tile they cover
+        if footprint.distance_to(*pos, player_pos.x, player_pos.y) <= 1 {
             commands.entity(entity).insert(WantsToMelee {
                 target: player_entity,
             });
@@ -92,25 +111,89 @@ pub fn monster_ai(
         }
 
         // Check if player is visible
-        if viewshed
-            .visible_tiles
-            .contains(&(player_pos.x, player_pos.y))
-        {
+        if viewshed.visible_tiles.contains(player_pos.x, player_pos.y) {
             let monster_idx = map.xy_idx(pos.x, pos.y);
 
-            // Find path to player (ignoring other entities so monsters keep chasing)
-            if let Some(path) = pathfinding::a_star_ignoring_entities(&map, monster_idx, player_idx) {
+            // Find path to player (ignoring other entities so monsters keep chasing), around
+            // the doors this monster can't get through
+            let shut = |idx: usize| {
+                map.tile_content[idx].iter().any(|&e| {
+                    doors
+                        .get(e)
+                        .is_ok_and(|(_, door, _)| !doors::passable(door, opens_doors))
+                })
+            };
+            let path = if footprint.0 > 1 {
+                let clearance = clearance.get_or_insert_with(|| map.clearance());
+                pathfinding::a_star_for_footprint(&map, clearance, footprint, monster_idx, player_idx, shut)
+            } else {
+                pathfinding::a_star_avoiding(&map, monster_idx, player_idx, shut)
+            };
+            if let Some(path) = path {
                 // Move one step toward player (path[0] is current position)
                 if path.len() > 1 {
-                    let next_idx = path[1];
-                    // Only move if destination is not blocked
-                    if !map.blocked_tiles[next_idx] {
+                    let next = Position {
+                        x: (path[1] % map.width as usize) as i32,
+                        y: (path[1] / map.width as usize) as i32,
+                    };
+                    let old: Vec<usize> = footprint.cells(*pos).map(|(x, y)| map.xy_idx(x, y)).collect();
+                    let new: Vec<usize> = footprint.cells(next).map(|(x, y)| map.xy_idx(x, y)).collect();
+
+                    // Opening a door under any part of where it is going takes the turn
+                    let closed_door = new
+                        .iter()
+                        .flat_map(|&idx| map.tile_content[idx].iter())
+                        .find(|&&e| doors.get(e).is_ok_and(|(_, door, _)| !door.open))
+                        .copied();
+                    if let Some(door_entity) = closed_door {
+                        // Only doors it could pass anyway, never locks or gates
+                        if let Some((_, mut door, mut glyph)) = doors
+                            .get_mut(door_entity)
+                            .ok()
+                            .filter(|(_, door, _)| doors::passable(door, opens_doors))
+                        {
+                            doors::set_open(&mut commands, door_entity, &mut door, &mut glyph, true);
+                            if new.iter().any(|&idx| map.visible_tiles[idx]) {
+                                gamelog.log(LogCategory::System, format!("{} opens a door.", name.name));
+                            }
+                        }
+                        continue;
+                    }
+                    // Only move if destination is not blocked by anything but the monster itself
+                    if new.iter().all(|idx| old.contains(idx) || !map.blocked_tiles[*idx]) {
                         // Unblock old position, block new position
-                        map.blocked_tiles[monster_idx] = false;
-                        map.blocked_tiles[next_idx] = true;
-                        pos.x = (next_idx % map.width as usize) as i32;
-                        pos.y = (next_idx / map.width as usize) as i32;
+                        for &idx in &old {
+                            map.blocked_tiles[idx] = false;
+                        }
+                        for &idx in &new {
+                            map.blocked_tiles[idx] = true;
+                        }
+                        *pos = next;
                         viewshed.dirty = true;
+
+                        // Monsters with hands pull the door shut behind them if the doorway is clear
+                        if opens_doors && footprint.0 <= 1 && !map.blocked_tiles[old[0]] {
+                            let open_door = map.tile_content[old[0]]
+                                .iter()
+                                .find(|&&e| {
+                                    doors.get(e).is_ok_and(|(_, door, _)| {
+                                        door.open && door.gate.is_none()
+                                    })
+                                })
+                                .copied();
+                            let clear = !map.tile_content[old[0]].iter().any(|&e| items.contains(e));
+                            if let (Some(door_entity), true) = (open_door, clear) {
+                                if let Ok((_, mut door, mut glyph)) = doors.get_mut(door_entity) {
+                                    doors::set_open(&mut commands, door_entity, &mut door, &mut glyph, false);
+                                    if map.visible_tiles[old[0]] {
+                                        gamelog.log(
+                                            LogCategory::System,
+                                            format!("{} shuts the door behind it.", name.name),
+                                        );
+                                    }
+                                }
+                            }
+                        }
                     }
                 }
             }
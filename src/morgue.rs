@@ -0,0 +1,189 @@
+use bevy::prelude::*;
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::combat::CombatStats;
+use crate::components::{Equipped, HungerClock, InBackpack, Item, Name};
+use crate::gamelog::GameLog;
+use crate::map::{tile_glyph, Map, Position};
+use crate::monsters::Monster;
+use crate::player::Player;
+use crate::run_stats::{self, RunStats};
+
+/// How many of the most recent log entries make it into the morgue
+const MORGUE_LOG_ENTRIES: usize = 50;
+
+/// Writes a plain-text post-mortem of the run. Runs after the run has been scored.
+pub fn write_morgue_file(
+    map: Res<Map>,
+    game_log: Res<GameLog>,
+    run_stats: Res<RunStats>,
+    player_query: Query<(Entity, &Name, &CombatStats, &HungerClock, &Position), With<Player>>,
+    equipped_query: Query<(&Name, &Equipped)>,
+    backpack_query: Query<(&Name, &InBackpack)>,
+    glyph_query: Query<(&Position, &Text2d), Or<(With<Monster>, With<Item>)>>,
+) {
+    let Ok((player, name, stats, hunger, player_pos)) = player_query.get_single() else {
+        return;
+    };
+
+    let mut text = String::new();
+
+    // Character
+    text.push_str(&format!("{} on depth {}\n", name.name, map.depth));
+    text.push_str(&format!(
+        "HP: {}/{}  Power: {}  Defense: {}  Hunger: {:?}\n",
+        stats.hp, stats.max_hp, stats.power, stats.defense, hunger.state
+    ));
+    text.push_str(&format!("Seed: {}\n", run_stats.seed));
+    let outcome = if run_stats.victory {
+        "Escaped the Abyss with its Heart"
+    } else {
+        run_stats
+            .cause_of_death
+            .as_deref()
+            .unwrap_or("Died of unknown causes")
+    };
+    text.push_str(&format!("{}\n\n", outcome));
+
+    text.push_str(&run_stats::summary(&run_stats));
+    text.push_str("\n\n");
+
+    // Equipment and inventory
+    text.push_str("Equipment:\n");
+    let mut equipped = 0;
+    for (item_name, item) in equipped_query.iter().filter(|(_, e)| e.owner == player) {
+        text.push_str(&format!("  {:?}: {}\n", item.slot, item_name.name));
+        equipped += 1;
+    }
+    if equipped == 0 {
+        text.push_str("  (nothing)\n");
+    }
+
+    text.push_str("\nInventory:\n");
+    let mut carried = 0;
+    for (item_name, _) in backpack_query.iter().filter(|(_, b)| b.owner == player) {
+        text.push_str(&format!("  {}\n", item_name.name));
+        carried += 1;
+    }
+    if carried == 0 {
+        text.push_str("  (nothing)\n");
+    }
+
+    // Kills
+    text.push_str("\nKills:\n");
+    if run_stats.kills.is_empty() {
+        text.push_str("  (none)\n");
+    }
+    for (monster, count) in &run_stats.kills {
+        text.push_str(&format!("  {:>3} {}\n", count, monster));
+    }
+
+    // Last messages
+    text.push_str("\nLast messages:\n");
+    for entry in game_log.recent(MORGUE_LOG_ENTRIES) {
+        text.push_str(&format!("  {}\n", entry.text()));
+    }
+
+    // Map, with monsters and floor items drawn over the tiles and the player on top
+    let mut overlay: Vec<(i32, i32, char)> = glyph_query
+        .iter()
+        .filter_map(|(pos, glyph)| glyph.0.chars().next().map(|c| (pos.x, pos.y, c)))
+        .collect();
+    overlay.push((player_pos.x, player_pos.y, '@'));
+
+    text.push_str("\nMap:\n");
+    text.push_str(&map_dump(&map, &overlay));
+
+    // The whole run, including messages that were spilled out of memory
+    text.push_str("\n\nMessage log:\n");
+    for line in game_log.export_lines() {
+        text.push_str(&line);
+        text.push('\n');
+    }
+
+    write(&text);
+    game_log.delete_archive();
+}
+
+/// Render the map as text using the same wall glyphs as the game.
+/// Later overlay entries are drawn over earlier ones.
+pub fn map_dump(map: &Map, overlay: &[(i32, i32, char)]) -> String {
+    let mut grid: Vec<char> = Vec::with_capacity(map.tiles.len());
+    for y in 0..map.height {
+        for x in 0..map.width {
+            let c = tile_glyph(map, x, y).map_or(' ', |(glyph, _)| glyph);
+            grid.push(c);
+        }
+    }
+
+    for &(x, y, c) in overlay {
+        if x >= 0 && x < map.width && y >= 0 && y < map.height {
+            grid[map.xy_idx(x, y)] = c;
+        }
+    }
+
+    grid.chunks(map.width as usize)
+        .map(|row| row.iter().collect::<String>().trim_end().to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// ============================================================================
+// Morgue File
+// ============================================================================
+
+/// Writes the morgue next to savegame.json, named by the time of death
+#[cfg(not(target_arch = "wasm32"))]
+fn write(text: &str) {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let path = format!("morgue-{}.txt", timestamp);
+
+    if let Err(e) = fs::write(&path, text) {
+        error!("Failed to write morgue file: {}", e);
+    } else {
+        info!("Morgue written to {}", path);
+    }
+}
+
+// ============================================================================
+// WASM Stub (no filesystem access)
+// ============================================================================
+
+#[cfg(target_arch = "wasm32")]
+fn write(_text: &str) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::TileType;
+
+    #[test]
+    fn test_map_dump_draws_walls_and_overlay() {
+        // 4x3 room with one floor row in the middle
+        // ─ ─ ─ ─
+        // . . . .
+        // ─ ─ ─ ─
+        let mut map = Map::new(4, 3, 1);
+        for x in 0..4 {
+            let idx = map.xy_idx(x, 1);
+            map.tiles[idx] = TileType::Floor;
+        }
+        let idx = map.xy_idx(3, 1);
+        map.tiles[idx] = TileType::DownStairs;
+
+        let dump = map_dump(&map, &[(1, 1, 'g'), (1, 1, '@')]);
+        let rows: Vec<&str> = dump.lines().collect();
+
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0], "────");
+        assert_eq!(rows[1], ".@.>");
+        assert_eq!(rows[2], "────");
+    }
+}
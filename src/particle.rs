@@ -1,5 +1,6 @@
 use bevy::prelude::*;
 
+use crate::lighting::LightSource;
 use crate::map::{Position, FONT_SIZE, GRID_PX};
 use crate::resources::UiFont;
 
@@ -17,9 +18,19 @@ pub struct ParticleRequest {
     pub lifetime_ms: f32,
 }
 
+/// A light that lives as long as a particle, with nothing drawn
+#[derive(Clone)]
+pub struct FlashRequest {
+    pub x: i32,
+    pub y: i32,
+    pub light: LightSource,
+    pub lifetime_ms: f32,
+}
+
 #[derive(Resource, Default)]
 pub struct ParticleBuilder {
     requests: Vec<ParticleRequest>,
+    flashes: Vec<FlashRequest>,
 }
 
 impl ParticleBuilder {
@@ -32,6 +43,15 @@ impl ParticleBuilder {
             lifetime_ms,
         });
     }
+
+    pub fn flash(&mut self, x: i32, y: i32, light: LightSource, lifetime_ms: f32) {
+        self.flashes.push(FlashRequest {
+            x,
+            y,
+            light,
+            lifetime_ms,
+        });
+    }
 }
 
 pub fn particle_spawn_system(
@@ -70,6 +90,19 @@ pub fn particle_spawn_system(
             Transform::from_xyz(screen_x, screen_y, 10.0), // Above other entities
         ));
     }
+
+    for flash in builder.flashes.drain(..) {
+        commands.spawn((
+            ParticleLifetime {
+                lifetime_ms: flash.lifetime_ms,
+            },
+            Position {
+                x: flash.x,
+                y: flash.y,
+            },
+            flash.light,
+        ));
+    }
 }
 
 pub fn particle_cull_system(
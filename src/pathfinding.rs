@@ -1,7 +1,7 @@
 use std::cmp::Ordering;
 use std::collections::{BinaryHeap, HashMap};
 
-use crate::map::{Map, TileType, MAP_HEIGHT, MAP_WIDTH};
+use crate::map::{Footprint, Map, Position, MAP_HEIGHT, MAP_WIDTH};
 
 #[derive(Clone, Eq, PartialEq)]
 struct Node {
@@ -24,56 +24,81 @@ impl PartialOrd for Node {
 
 /// A* pathfinding algorithm. Returns path from start to end (inclusive), or None if no path exists.
 pub fn a_star(map: &Map, start: usize, end: usize) -> Option<Vec<usize>> {
-    let mut open_set = BinaryHeap::new();
-    let mut came_from: HashMap<usize, usize> = HashMap::new();
-    let mut g_score: HashMap<usize, f32> = HashMap::new();
-
-    g_score.insert(start, 0.0);
-    open_set.push(Node {
-        idx: start,
-        f_score: map.get_pathing_distance(start, end) as i32,
-    });
-
-    while let Some(current) = open_set.pop() {
-        if current.idx == end {
-            // Reconstruct path
-            let mut path = vec![current.idx];
-            let mut current_idx = current.idx;
-            while let Some(&prev) = came_from.get(&current_idx) {
-                path.push(prev);
-                current_idx = prev;
-            }
-            path.reverse();
-            return Some(path);
-        }
-
-        let current_g = *g_score.get(&current.idx).unwrap_or(&f32::INFINITY);
-
-        for (neighbor_idx, cost) in map.get_available_exits(current.idx) {
-            let tentative_g = current_g + cost;
-            let neighbor_g = *g_score.get(&neighbor_idx).unwrap_or(&f32::INFINITY);
-
-            if tentative_g < neighbor_g {
-                came_from.insert(neighbor_idx, current.idx);
-                g_score.insert(neighbor_idx, tentative_g);
-
-                let h = map.get_pathing_distance(neighbor_idx, end);
-                let f = tentative_g + h;
+    search(
+        start,
+        |idx| map.get_pathing_distance(idx, end),
+        |idx| idx == end,
+        |idx| map.get_available_exits(idx),
+    )
+}
 
-                open_set.push(Node {
-                    idx: neighbor_idx,
-                    f_score: f as i32,
-                });
-            }
-        }
-    }
+/// A* pathfinding that ignores entities (only considers walls as obstacles), plus whatever tiles
+/// `avoid` picks out, such as doors a monster can't open.
+/// Use this for AI pathing so monsters can path through each other's positions.
+pub fn a_star_avoiding(
+    map: &Map,
+    start: usize,
+    end: usize,
+    avoid: impl Fn(usize) -> bool,
+) -> Option<Vec<usize>> {
+    search(
+        start,
+        |idx| map.get_pathing_distance(idx, end),
+        |idx| idx == end,
+        |idx| {
+            let mut exits = map.get_available_exits_ignoring_entities(idx);
+            exits.retain(|&(exit, _)| !avoid(exit));
+            exits
+        },
+    )
+}
 
-    None // No path found
+/// A* for a monster with a Footprint, ignoring entities. The path runs through top-left corners
+/// where the clearance map says the monster fits and none of the tiles it would cover are to be
+/// avoided, and ends once it is next to `target`.
+pub fn a_star_for_footprint(
+    map: &Map,
+    clearance: &[i32],
+    footprint: Footprint,
+    start: usize,
+    target: usize,
+    avoid: impl Fn(usize) -> bool,
+) -> Option<Vec<usize>> {
+    let w = map.width as usize;
+    let (tx, ty) = ((target % w) as i32, (target / w) as i32);
+    let reach = move |idx: usize| {
+        let corner = Position {
+            x: (idx % w) as i32,
+            y: (idx / w) as i32,
+        };
+        footprint.distance_to(corner, tx, ty)
+    };
+
+    search(
+        start,
+        |idx| (reach(idx) - 1).max(0) as f32,
+        |idx| reach(idx) <= 1,
+        |idx| {
+            let mut exits = map.get_available_exits_for_size(idx, clearance, footprint.0);
+            exits.retain(|&(exit, _)| {
+                let corner = Position {
+                    x: (exit % w) as i32,
+                    y: (exit / w) as i32,
+                };
+                !footprint.cells(corner).any(|(x, y)| avoid(map.xy_idx(x, y)))
+            });
+            exits
+        },
+    )
 }
 
-/// A* pathfinding that ignores entities (only considers walls as obstacles).
-/// Use this for AI pathing so monsters can path through each other's positions.
-pub fn a_star_ignoring_entities(map: &Map, start: usize, end: usize) -> Option<Vec<usize>> {
+/// A* from `start` over the given exits until a goal is reached. Returns the path (inclusive).
+fn search(
+    start: usize,
+    heuristic: impl Fn(usize) -> f32,
+    is_goal: impl Fn(usize) -> bool,
+    exits: impl Fn(usize) -> Vec<(usize, f32)>,
+) -> Option<Vec<usize>> {
     let mut open_set = BinaryHeap::new();
     let mut came_from: HashMap<usize, usize> = HashMap::new();
     let mut g_score: HashMap<usize, f32> = HashMap::new();
@@ -81,11 +106,11 @@ pub fn a_star_ignoring_entities(map: &Map, start: usize, end: usize) -> Option<V
     g_score.insert(start, 0.0);
     open_set.push(Node {
         idx: start,
-        f_score: map.get_pathing_distance(start, end) as i32,
+        f_score: heuristic(start) as i32,
     });
 
     while let Some(current) = open_set.pop() {
-        if current.idx == end {
+        if is_goal(current.idx) {
             // Reconstruct path
             let mut path = vec![current.idx];
             let mut current_idx = current.idx;
@@ -99,7 +124,7 @@ pub fn a_star_ignoring_entities(map: &Map, start: usize, end: usize) -> Option<V
 
         let current_g = *g_score.get(&current.idx).unwrap_or(&f32::INFINITY);
 
-        for (neighbor_idx, cost) in map.get_available_exits_ignoring_entities(current.idx) {
+        for (neighbor_idx, cost) in exits(current.idx) {
             let tentative_g = current_g + cost;
             let neighbor_g = *g_score.get(&neighbor_idx).unwrap_or(&f32::INFINITY);
 
@@ -107,7 +132,7 @@ pub fn a_star_ignoring_entities(map: &Map, start: usize, end: usize) -> Option<V
                 came_from.insert(neighbor_idx, current.idx);
                 g_score.insert(neighbor_idx, tentative_g);
 
-                let h = map.get_pathing_distance(neighbor_idx, end);
+                let h = heuristic(neighbor_idx);
                 let f = tentative_g + h;
 
                 open_set.push(Node {
@@ -148,7 +173,7 @@ pub fn dijkstra_map(map: &Map, starts: &[usize]) -> Vec<f32> {
                     continue;
                 }
                 let neighbor_idx = map.xy_idx(nx, ny);
-                if map.tiles[neighbor_idx] != TileType::Wall {
+                if map.tiles[neighbor_idx].is_walkable() {
                     let new_dist = current_dist + 1.0;
                     if new_dist < distances[neighbor_idx] {
                         distances[neighbor_idx] = new_dist;
@@ -161,3 +186,30 @@ pub fn dijkstra_map(map: &Map, starts: &[usize]) -> Vec<f32> {
 
     distances
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::TileType;
+
+    #[test]
+    fn test_large_monsters_path_around_what_they_must_avoid() {
+        // A hall two tiles high, just wide enough for a 2x2 monster
+        let mut map = Map::new(MAP_WIDTH, MAP_HEIGHT, 1);
+        for y in 1..=2 {
+            for x in 1..=20 {
+                let idx = map.xy_idx(x, y);
+                map.tiles[idx] = TileType::Floor;
+            }
+        }
+        let clearance = map.clearance();
+        let (start, target) = (map.xy_idx(1, 1), map.xy_idx(20, 1));
+        let door = map.xy_idx(10, 2);
+
+        let path = a_star_for_footprint(&map, &clearance, Footprint(2), start, target, |_| false);
+        assert!(path.is_some());
+        // A shut door under either row of it blocks the way
+        let path = a_star_for_footprint(&map, &clearance, Footprint(2), start, target, |idx| idx == door);
+        assert!(path.is_none());
+    }
+}
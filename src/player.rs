@@ -5,11 +5,17 @@ use bevy::{
 
 use crate::{
     combat::{CombatStats, WantsToMelee},
-    components::{BlocksTile, BlocksVisibility, Door, HungerClock, HungerState, Item, WantsToPickupItem},
+    components::{
+        Door, HeartOfTheAbyss, HungerClock, HungerState, InBackpack, Item, Key, Lever, Name, Npc,
+        WantsToPickupItem, WantsToSearch,
+    },
     debug::DebugMode,
-    gamelog::GameLog,
+    doors::{self, DoorCommand},
+    gamelog::{GameLog, LogCategory},
+    journey::ReturnJourney,
     map::{xy_idx, Map, Position, TileType},
     monsters::Monster,
+    spawner,
     viewshed::Viewshed,
     RunState,
 };
@@ -35,6 +41,9 @@ fn try_move_player(
     delta_y: i32,
     combat_stats: &Query<&CombatStats, Without<Player>>,
     doors: &mut Query<(Entity, &mut Door, &mut Text2d), Without<Player>>,
+    npcs: &Query<(&Name, &Npc)>,
+    keys: &Query<(&Key, &Name, &InBackpack)>,
+    levers: &mut Query<(&mut Lever, &mut Text2d), Without<Door>>,
 ) {
     let destination_idx = xy_idx(pos.x + delta_x, pos.y + delta_y);
 
@@ -48,18 +57,62 @@ fn try_move_player(
         }
     }
 
-    // Check for doors
-    for potential_door in map.tile_content[destination_idx].iter() {
+    // Townsfolk have a word instead
+    for potential_npc in map.tile_content[destination_idx].iter() {
+        if let Ok((name, npc)) = npcs.get(*potential_npc) {
+            gamelog.log(LogCategory::System, format!("{}: \"{}\"", name.name, npc.greeting));
+            return;
+        }
+    }
+
+    // Levers are set into walls, so they are pulled by walking into them
+    for potential_lever in map.tile_content[destination_idx].iter() {
+        if let Ok((mut lever, mut lever_glyph)) = levers.get_mut(*potential_lever) {
+            lever.pulled = !lever.pulled;
+            lever_glyph.0 = spawner::lever_glyph(lever.pulled).to_string();
+            for (door_entity, mut door, mut door_glyph) in doors.iter_mut() {
+                if door.gate == Some(lever.gate) {
+                    doors::set_open(commands, door_entity, &mut door, &mut door_glyph, lever.pulled);
+                }
+            }
+            gamelog.log(
+                LogCategory::System,
+                if lever.pulled {
+                    "You pull the lever. Somewhere, chains rattle as a portcullis rises."
+                } else {
+                    "You push the lever back. Somewhere, a portcullis crashes down."
+                },
+            );
+            return;
+        }
+    }
+
+    // Check for doors; a secret door nobody has found yet is still wall
+    let walkable = map.tiles[destination_idx].is_walkable();
+    for potential_door in map.tile_content[destination_idx].iter().filter(|_| walkable) {
         if let Ok((door_entity, mut door, mut door_glyph)) = doors.get_mut(*potential_door) {
-            if !door.open {
-                // Open the door
-                door.open = true;
-                door_glyph.0 = "/".to_string();
-                commands.entity(door_entity).remove::<BlocksTile>();
-                commands.entity(door_entity).remove::<BlocksVisibility>();
-                gamelog.entries.push("You open the door.".to_string());
-                return; // Opening a door takes a turn
+            if door.open {
+                continue;
+            }
+            if door.gate.is_some() {
+                gamelog.log(
+                    LogCategory::System,
+                    "The portcullis won't budge. There must be a lever somewhere.",
+                );
+                return;
+            }
+            if door.locked {
+                let Some(key_name) = door.lock.and_then(|lock| held_key(keys, player_entity, lock)) else {
+                    gamelog.log(LogCategory::System, "The door is locked.");
+                    return;
+                };
+                gamelog.log(LogCategory::System, format!("You unlock the door with the {}.", key_name));
+                door.locked = false;
+            } else {
+                gamelog.log(LogCategory::System, "You open the door.");
             }
+            doors::set_open(commands, door_entity, &mut door, &mut door_glyph, true);
+            return; // Opening a door takes a turn
         }
     }
 
@@ -69,6 +122,29 @@ fn try_move_player(
     }
 }
 
+/// The step a movement key takes
+pub fn direction_of(key: &KeyCode) -> Option<(i32, i32)> {
+    let direction = match key {
+        KeyCode::ArrowLeft | KeyCode::KeyH | KeyCode::Numpad4 => (-1, 0),
+        KeyCode::ArrowRight | KeyCode::KeyL | KeyCode::Numpad6 => (1, 0),
+        KeyCode::ArrowUp | KeyCode::KeyK | KeyCode::Numpad8 => (0, -1),
+        KeyCode::ArrowDown | KeyCode::KeyJ | KeyCode::Numpad2 => (0, 1),
+        KeyCode::KeyY | KeyCode::Numpad7 => (-1, -1),
+        KeyCode::KeyU | KeyCode::Numpad9 => (1, -1),
+        KeyCode::KeyM | KeyCode::Numpad3 => (1, 1),
+        KeyCode::KeyN | KeyCode::Numpad1 => (-1, 1),
+        _ => return None,
+    };
+    Some(direction)
+}
+
+/// Name of the key to a lock, if the player is carrying it
+pub fn held_key(keys: &Query<(&Key, &Name, &InBackpack)>, player_entity: Entity, lock: u32) -> Option<String> {
+    keys.iter()
+        .find(|(key, _, backpack)| key.lock == lock && backpack.owner == player_entity)
+        .map(|(_, name, _)| name.name.clone())
+}
+
 fn get_item(
     commands: &mut Commands,
     gamelog: &mut GameLog,
@@ -85,7 +161,7 @@ fn get_item(
             return true;
         }
     }
-    gamelog.entries.push("There is nothing here to pick up.".to_string());
+    gamelog.log(LogCategory::System, "There is nothing here to pick up.");
     false
 }
 
@@ -101,6 +177,11 @@ fn handle_player_input(
     items: Query<(Entity, &Position), (With<Item>, Without<Player>)>,
     monsters: Query<&Position, (With<Monster>, Without<Player>)>,
     mut doors: Query<(Entity, &mut Door, &mut Text2d), Without<Player>>,
+    npcs: Query<(&Name, &Npc)>,
+    keys: Query<(&Key, &Name, &InBackpack)>,
+    mut levers: Query<(&mut Lever, &mut Text2d), Without<Door>>,
+    journey: Res<ReturnJourney>,
+    heart: Query<&InBackpack, With<HeartOfTheAbyss>>,
 ) {
     // Don't process player input if debug console is open
     if debug_mode.show_console {
@@ -133,11 +214,14 @@ fn handle_player_input(
                     0,
                     &other_combat_stats,
                     &mut doors,
+                    &npcs,
+                    &keys,
+                    &mut levers,
                 );
                 player_acted = true;
             }
             KeyCode::ArrowRight | KeyCode::KeyL | KeyCode::Numpad6 => {
-                try_move_player(&mut commands, &mut gamelog, &map, player_entity, pos, 1, 0, &other_combat_stats, &mut doors);
+                try_move_player(&mut commands, &mut gamelog, &map, player_entity, pos, 1, 0, &other_combat_stats, &mut doors, &npcs, &keys, &mut levers);
                 player_acted = true;
             }
             KeyCode::ArrowUp | KeyCode::KeyK | KeyCode::Numpad8 => {
@@ -151,11 +235,14 @@ fn handle_player_input(
                     -1,
                     &other_combat_stats,
                     &mut doors,
+                    &npcs,
+                    &keys,
+                    &mut levers,
                 );
                 player_acted = true;
             }
             KeyCode::ArrowDown | KeyCode::KeyJ | KeyCode::Numpad2 => {
-                try_move_player(&mut commands, &mut gamelog, &map, player_entity, pos, 0, 1, &other_combat_stats, &mut doors);
+                try_move_player(&mut commands, &mut gamelog, &map, player_entity, pos, 0, 1, &other_combat_stats, &mut doors, &npcs, &keys, &mut levers);
                 player_acted = true;
             }
 
@@ -171,6 +258,9 @@ fn handle_player_input(
                     -1,
                     &other_combat_stats,
                     &mut doors,
+                    &npcs,
+                    &keys,
+                    &mut levers,
                 );
                 player_acted = true;
             }
@@ -185,11 +275,14 @@ fn handle_player_input(
                     -1,
                     &other_combat_stats,
                     &mut doors,
+                    &npcs,
+                    &keys,
+                    &mut levers,
                 );
                 player_acted = true;
             }
             KeyCode::KeyM | KeyCode::Numpad3 => {
-                try_move_player(&mut commands, &mut gamelog, &map, player_entity, pos, 1, 1, &other_combat_stats, &mut doors);
+                try_move_player(&mut commands, &mut gamelog, &map, player_entity, pos, 1, 1, &other_combat_stats, &mut doors, &npcs, &keys, &mut levers);
                 player_acted = true;
             }
             KeyCode::KeyN | KeyCode::Numpad1 => {
@@ -203,6 +296,9 @@ fn handle_player_input(
                     1,
                     &other_combat_stats,
                     &mut doors,
+                    &npcs,
+                    &keys,
+                    &mut levers,
                 );
                 player_acted = true;
             }
@@ -212,7 +308,7 @@ fn handle_player_input(
                 // Check if any monsters are visible
                 let mut can_heal = true;
                 for monster_pos in monsters.iter() {
-                    if viewshed.visible_tiles.contains(&(monster_pos.x, monster_pos.y)) {
+                    if viewshed.visible_tiles.contains(monster_pos.x, monster_pos.y) {
                         can_heal = false;
                         break;
                     }
@@ -224,7 +320,7 @@ fn handle_player_input(
                 // Heal 1 HP if no monsters visible and not starving
                 if can_heal && player_stats.hp < player_stats.max_hp {
                     player_stats.hp = (player_stats.hp + 1).min(player_stats.max_hp);
-                    gamelog.entries.push("You rest and recover 1 HP.".to_string());
+                    gamelog.log(LogCategory::Status, "You rest and recover 1 HP.");
                 }
                 player_acted = true;
             }
@@ -236,6 +332,27 @@ fn handle_player_input(
                 }
             }
 
+            // Search for hidden doors and traps
+            KeyCode::KeyS => {
+                commands.entity(player_entity).insert(WantsToSearch);
+                player_acted = true;
+            }
+
+            // Door commands, which ask for a direction first
+            KeyCode::KeyC | KeyCode::KeyB | KeyCode::KeyT => {
+                let command = match ev.key_code {
+                    KeyCode::KeyC => DoorCommand::Close,
+                    KeyCode::KeyB => DoorCommand::Bash,
+                    _ => DoorCommand::Lock,
+                };
+                gamelog.log(
+                    LogCategory::System,
+                    format!("{} which way? (Escape to cancel)", command.verb()),
+                );
+                commands.insert_resource(command);
+                next_state.set(RunState::ChooseDirection);
+            }
+
             // Inventory
             KeyCode::KeyI => {
                 next_state.set(RunState::ShowInventory);
@@ -251,13 +368,36 @@ fn handle_player_input(
                 next_state.set(RunState::ShowRemoveItem);
             }
 
+            // Message log
+            KeyCode::KeyP => {
+                next_state.set(RunState::ShowLog);
+            }
+
             // Go down stairs
             KeyCode::Period => {
                 let idx = xy_idx(pos.x, pos.y);
                 if map.tiles[idx] == TileType::DownStairs {
                     next_state.set(RunState::NextLevel);
                 } else {
-                    gamelog.entries.push("There are no stairs here.".to_string());
+                    gamelog.log(LogCategory::System, "There are no stairs here.");
+                }
+            }
+
+            // Go up stairs
+            KeyCode::Comma => {
+                let idx = xy_idx(pos.x, pos.y);
+                let carrying_heart = heart.iter().any(|backpack| backpack.owner == player_entity);
+                if map.tiles[idx] != TileType::UpStairs {
+                    gamelog.log(LogCategory::System, "There are no stairs here.");
+                } else if !journey.ascending {
+                    gamelog.log(
+                        LogCategory::System,
+                        "You cannot turn back until you claim the Heart of the Abyss.",
+                    );
+                } else if map.depth == 1 && !carrying_heart {
+                    gamelog.log(LogCategory::System, "You cannot leave the Abyss without the Heart.");
+                } else {
+                    next_state.set(RunState::PreviousLevel);
                 }
             }
             _ => {}
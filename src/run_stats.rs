@@ -0,0 +1,124 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::components::WantsToUseItem;
+use crate::gamelog::GameLog;
+use crate::highscores::{self, HighScoreEntry};
+use crate::map::Map;
+use crate::player::Player;
+use crate::RunState;
+
+/// Everything we track about the current run, used for scoring and the morgue.
+#[derive(Resource, Default, Clone, Serialize, Deserialize)]
+pub struct RunStats {
+    pub turns: u32,
+    pub deepest_depth: i32,
+    /// Kill counts keyed by monster type ("Orc", "Goblin", ...)
+    pub kills: BTreeMap<String, u32>,
+    pub damage_dealt: i32,
+    pub damage_taken: i32,
+    pub items_used: u32,
+    pub cause_of_death: Option<String>,
+    /// Cause phrase for whatever last hurt the player ("Killed by Orc")
+    pub last_damage_source: Option<String>,
+    pub victory: bool,
+    /// Seed the run's GameRng was created from
+    pub seed: u64,
+    /// Final score and high-score rank, filled in when the run ends
+    #[serde(skip)]
+    pub final_score: Option<i32>,
+    #[serde(skip)]
+    pub rank: Option<usize>,
+}
+
+impl RunStats {
+    pub fn record_kill(&mut self, name: &str) {
+        *self.kills.entry(monster_type(name).to_string()).or_insert(0) += 1;
+    }
+
+    pub fn total_kills(&self) -> u32 {
+        self.kills.values().sum()
+    }
+
+    /// "12 (Goblin 8, Orc 4)"
+    pub fn kills_description(&self) -> String {
+        if self.kills.is_empty() {
+            return "0".to_string();
+        }
+        let by_type: Vec<String> = self
+            .kills
+            .iter()
+            .map(|(name, count)| format!("{} {}", name, count))
+            .collect();
+        format!("{} ({})", self.total_kills(), by_type.join(", "))
+    }
+
+    /// Score per the design doc: depth reached and monsters killed, minus a small
+    /// penalty for turns taken. Escaping with the Heart doubles it.
+    pub fn score(&self) -> i32 {
+        let base = self.deepest_depth * 100 + self.total_kills() as i32 * 10 - self.turns as i32 / 10;
+        let base = base.max(0);
+        if self.victory {
+            base * 2
+        } else {
+            base
+        }
+    }
+}
+
+/// Strip the per-spawn suffix from a monster name ("Orc #3" -> "Orc")
+pub fn monster_type(name: &str) -> &str {
+    name.split(" #").next().unwrap_or(name)
+}
+
+/// Counts player turns and tracks the deepest level reached.
+pub fn count_turns(mut stats: ResMut<RunStats>, mut log: ResMut<GameLog>, map: Res<Map>) {
+    stats.turns += 1;
+    log.turn = stats.turns;
+    stats.deepest_depth = stats.deepest_depth.max(map.depth);
+}
+
+/// Counts items the player is about to use; runs before the item use system consumes the intent.
+pub fn count_items_used(mut stats: ResMut<RunStats>, query: Query<&WantsToUseItem, With<Player>>) {
+    stats.items_used += query.iter().count() as u32;
+}
+
+/// Scores the finished run and records it in the high-score table.
+pub fn finalize_run(mut stats: ResMut<RunStats>, state: Res<State<RunState>>, map: Res<Map>) {
+    stats.deepest_depth = stats.deepest_depth.max(map.depth);
+    stats.victory = *state.get() == RunState::Victory;
+    if stats.victory {
+        stats.cause_of_death = None;
+    }
+
+    let score = stats.score();
+    stats.final_score = Some(score);
+    stats.rank = highscores::record(HighScoreEntry {
+        score,
+        deepest_depth: stats.deepest_depth,
+        turns: stats.turns,
+        kills: stats.total_kills(),
+        victory: stats.victory,
+        cause_of_death: stats.cause_of_death.clone(),
+    });
+}
+
+/// Multi-line summary of the run for end-of-game screens.
+pub fn summary(stats: &RunStats) -> String {
+    let mut text = format!(
+        "Score: {}\nDeepest depth: {}\nTurns taken: {}\nMonsters killed: {}\nDamage dealt: {}\nDamage taken: {}\nItems used: {}",
+        stats.final_score.unwrap_or_else(|| stats.score()),
+        stats.deepest_depth,
+        stats.turns,
+        stats.kills_description(),
+        stats.damage_dealt,
+        stats.damage_taken,
+        stats.items_used,
+    );
+    if let Some(rank) = stats.rank {
+        text.push_str(&format!("\n\nNew high score! Rank #{}", rank + 1));
+    }
+    text
+}
@@ -5,19 +5,25 @@ use serde::{Deserialize, Serialize};
 #[cfg(not(target_arch = "wasm32"))]
 use std::fs;
 #[cfg(not(target_arch = "wasm32"))]
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use crate::combat::CombatStats;
 use crate::components::{
-    AreaOfEffect, BlocksTile, CausesConfusion, Confusion, Consumable, EntryTrigger, Hidden,
-    HungerClock, HungerState, InBackpack, InflictsDamage, Item, MagicMapper, Name, ProvidesFood,
-    ProvidesHealing, Ranged, RenderOrder, RenderableBundle, SingleActivation, Targeting,
+    AreaOfEffect, BlocksTile, BlocksVisibility, CausesConfusion, Confusion, Consumable, Door,
+    EntryTrigger,
+    HeartOfTheAbyss, Hidden, HungerClock, HungerState, InBackpack, InflictsDamage, Item, Key,
+    Lever, MagicMapper, Name, Npc, OpensDoors, ProvidesFood, ProvidesHealing, Ranged, RenderOrder,
+    RenderableBundle, SecretDoor, SingleActivation, Targeting,
 };
-use crate::gamelog::GameLog;
-use crate::map::{Map, Position, Revealed, RevealedState, Tile, TileType, MAP_WIDTH};
+use crate::gamelog::{GameLog, LogEntry};
+use crate::journey::ReturnJourney;
+use crate::lighting::{self, LightSource};
+use crate::map::{Footprint, Map, Position, TileType};
 use crate::monsters::Monster;
 use crate::player::Player;
 use crate::resources::UiFont;
+use crate::run_stats::RunStats;
+use crate::spawner;
 use crate::viewshed::Viewshed;
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -35,7 +41,17 @@ pub struct SaveData {
     pub items: Vec<SerializedItem>,
     #[serde(default)]
     pub traps: Vec<SerializedTrap>,
-    pub game_log: Vec<String>,
+    #[serde(default)]
+    pub npcs: Vec<SerializedNpc>,
+    #[serde(default)]
+    pub doors: Vec<SerializedDoor>,
+    #[serde(default)]
+    pub levers: Vec<SerializedLever>,
+    pub game_log: Vec<LogEntry>,
+    #[serde(default)]
+    pub ascending: bool,
+    #[serde(default)]
+    pub run_stats: RunStats,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -47,6 +63,16 @@ pub struct SerializedMap {
     pub depth: i32,
     #[serde(default)]
     pub bloodstains: Vec<usize>,
+    #[serde(default)]
+    pub remembered: Vec<SerializedMemory>,
+}
+
+/// What was last seen on a tile out of view
+#[derive(Serialize, Deserialize)]
+pub struct SerializedMemory {
+    pub idx: usize,
+    pub glyph: char,
+    pub color: SerializedColor,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -81,6 +107,11 @@ pub struct SerializedMonster {
     pub power: i32,
     pub viewshed_range: i32,
     pub confusion_turns: Option<i32>,
+    /// Size of a large monster's footprint
+    #[serde(default)]
+    pub footprint: Option<i32>,
+    #[serde(default)]
+    pub opens_doors: bool,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -110,6 +141,11 @@ pub struct ItemProperties {
     pub causes_confusion: Option<i32>,
     #[serde(default)]
     pub magic_mapper: bool,
+    #[serde(default)]
+    pub heart_of_the_abyss: bool,
+    /// ID of the lock a key opens
+    #[serde(default)]
+    pub key: Option<u32>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -131,6 +167,39 @@ pub struct SerializedTrap {
     pub single_activation: bool,
 }
 
+/// Townsfolk are spawned again by name, which gives them back their look and greeting
+#[derive(Serialize, Deserialize)]
+pub struct SerializedNpc {
+    pub x: i32,
+    pub y: i32,
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SerializedDoor {
+    pub x: i32,
+    pub y: i32,
+    pub open: bool,
+    /// Not found yet; the map has wall here until it is
+    pub secret: bool,
+    /// ID of the key that fits its lock
+    #[serde(default)]
+    pub lock: Option<u32>,
+    #[serde(default)]
+    pub locked: bool,
+    /// ID of the levers that work a portcullis
+    #[serde(default)]
+    pub gate: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SerializedLever {
+    pub x: i32,
+    pub y: i32,
+    pub gate: u32,
+    pub pulled: bool,
+}
+
 // ============================================================================
 // Save System
 // ============================================================================
@@ -139,6 +208,8 @@ pub struct SerializedTrap {
 pub fn save_game(
     map: Res<Map>,
     game_log: Res<GameLog>,
+    journey: Res<ReturnJourney>,
+    run_stats: Res<RunStats>,
     player_query: Query<
         (Entity, &Position, &Name, &CombatStats, &Viewshed, &HungerClock),
         With<Player>,
@@ -151,6 +222,8 @@ pub fn save_game(
             &Viewshed,
             &Text2d,
             Option<&Confusion>,
+            Option<&Footprint>,
+            Has<OpensDoors>,
         ),
         With<Monster>,
     >,
@@ -170,7 +243,7 @@ pub fn save_game(
             Option<&AreaOfEffect>,
             Option<&Targeting>,
             Option<&CausesConfusion>,
-            Option<&MagicMapper>,
+            (Option<&MagicMapper>, Option<&HeartOfTheAbyss>, Option<&Key>),
         ),
         With<Item>,
     >,
@@ -186,6 +259,9 @@ pub fn save_game(
         ),
         With<EntryTrigger>,
     >,
+    npc_query: Query<(&Position, &Name), With<Npc>>,
+    door_query: Query<(&Position, &Door, Has<SecretDoor>)>,
+    lever_query: Query<(&Position, &Lever)>,
 ) {
     let Ok((player_entity, player_pos, player_name, player_stats, player_viewshed, player_hunger)) =
         player_query.get_single()
@@ -212,7 +288,7 @@ pub fn save_game(
     let monsters: Vec<SerializedMonster> = monster_query
         .iter()
         .map(
-            |(pos, name, stats, viewshed, text, confusion)| SerializedMonster {
+            |(pos, name, stats, viewshed, text, confusion, footprint, opens_doors)| SerializedMonster {
                 x: pos.x,
                 y: pos.y,
                 name: name.name.clone(),
@@ -223,6 +299,8 @@ pub fn save_game(
                 power: stats.power,
                 viewshed_range: viewshed.range,
                 confusion_turns: confusion.map(|c| c.turns),
+                footprint: footprint.map(|f| f.0),
+                opens_doors,
             },
         )
         .collect();
@@ -246,7 +324,7 @@ pub fn save_game(
                 aoe,
                 targeting,
                 causes_confusion,
-                magic_mapper,
+                (magic_mapper, heart, key),
             )| {
                 let location = if let Some(backpack) = in_backpack {
                     if backpack.owner == player_entity {
@@ -288,6 +366,8 @@ pub fn save_game(
                         }),
                         causes_confusion: causes_confusion.map(|c| c.turns),
                         magic_mapper: magic_mapper.is_some(),
+                        heart_of_the_abyss: heart.is_some(),
+                        key: key.map(|k| k.lock),
                     },
                 }
             },
@@ -316,6 +396,41 @@ pub fn save_game(
         })
         .collect();
 
+    // Serialize townsfolk
+    let npcs: Vec<SerializedNpc> = npc_query
+        .iter()
+        .map(|(pos, name)| SerializedNpc {
+            x: pos.x,
+            y: pos.y,
+            name: name.name.clone(),
+        })
+        .collect();
+
+    // Serialize doors
+    let doors: Vec<SerializedDoor> = door_query
+        .iter()
+        .map(|(pos, door, secret)| SerializedDoor {
+            x: pos.x,
+            y: pos.y,
+            open: door.open,
+            secret,
+            lock: door.lock,
+            locked: door.locked,
+            gate: door.gate,
+        })
+        .collect();
+
+    // Serialize levers
+    let levers: Vec<SerializedLever> = lever_query
+        .iter()
+        .map(|(pos, lever)| SerializedLever {
+            x: pos.x,
+            y: pos.y,
+            gate: lever.gate,
+            pulled: lever.pulled,
+        })
+        .collect();
+
     // Serialize map
     let serialized_map = SerializedMap {
         tiles: map.tiles.clone(),
@@ -324,6 +439,22 @@ pub fn save_game(
         height: map.height,
         depth: map.depth,
         bloodstains: map.bloodstains.iter().copied().collect(),
+        remembered: map
+            .remembered
+            .iter()
+            .map(|(&idx, &(glyph, color))| {
+                let srgba = color.to_srgba();
+                SerializedMemory {
+                    idx,
+                    glyph,
+                    color: SerializedColor {
+                        r: srgba.red,
+                        g: srgba.green,
+                        b: srgba.blue,
+                    },
+                }
+            })
+            .collect(),
     };
 
     let save_data = SaveData {
@@ -332,7 +463,12 @@ pub fn save_game(
         monsters,
         items,
         traps,
-        game_log: game_log.entries.clone(),
+        npcs,
+        doors,
+        levers,
+        game_log: game_log.entries.iter().cloned().collect(),
+        ascending: journey.ascending,
+        run_stats: run_stats.clone(),
     };
 
     match serde_json::to_string_pretty(&save_data) {
@@ -363,12 +499,20 @@ pub fn delete_save_file() {
     let _ = fs::remove_file(SAVE_FILE);
 }
 
+/// Where a file that belongs with the current run is kept, in the same place as the save
+#[cfg(not(target_arch = "wasm32"))]
+pub fn beside_save(file: &str) -> PathBuf {
+    Path::new(SAVE_FILE).with_file_name(file)
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 pub fn load_game(
     commands: &mut Commands,
-    entities_to_despawn: &Query<Entity, Or<(With<Player>, With<Monster>, With<Item>, With<Tile>, With<EntryTrigger>)>>,
+    entities_to_despawn: &Query<Entity, Or<(With<Player>, With<Monster>, With<Item>, With<EntryTrigger>, With<Npc>, With<Door>, With<Lever>, With<LightSource>)>>,
     map: &mut Map,
     game_log: &mut GameLog,
+    journey: &mut ReturnJourney,
+    run_stats: &mut RunStats,
     font: &UiFont,
 ) -> bool {
     let Ok(json) = fs::read_to_string(SAVE_FILE) else {
@@ -393,13 +537,26 @@ pub fn load_game(
     map.height = save_data.map.height;
     map.depth = save_data.map.depth;
     // Recalculate blocked tiles
-    map.blocked_tiles = map.tiles.iter().map(|t| *t == TileType::Wall).collect();
+    map.blocked_tiles = map.tiles.iter().map(|t| !t.is_walkable()).collect();
     map.visible_tiles = vec![false; map.tiles.len()];
     map.tile_content = vec![Vec::new(); map.tiles.len()];
     map.bloodstains = save_data.map.bloodstains.into_iter().collect();
+    map.remembered = save_data
+        .map
+        .remembered
+        .into_iter()
+        .map(|m| (m.idx, (m.glyph, Color::srgb(m.color.r, m.color.g, m.color.b))))
+        .collect();
+    // Rooms aren't saved, so the level is split into open areas instead
+    let start = map.xy_idx(save_data.player.x, save_data.player.y);
+    commands.insert_resource(crate::map_builders::RegionGraph::from_map(map, None, start));
 
     // Restore game log
-    game_log.entries = save_data.game_log;
+    game_log.entries = save_data.game_log.into();
+    journey.ascending = save_data.ascending;
+    *run_stats = save_data.run_stats;
+    game_log.turn = run_stats.turns;
+    game_log.set_run(run_stats.seed);
 
     let text_font = TextFont {
         font: font.0.clone(),
@@ -407,61 +564,6 @@ pub fn load_game(
         ..default()
     };
 
-    // Spawn map tiles
-    let mut y = 0;
-    let mut x = 0;
-    for (idx, tile) in map.tiles.iter().enumerate() {
-        let revealed_state = if map.revealed_tiles[idx] {
-            RevealedState::Explored
-        } else {
-            RevealedState::Hidden
-        };
-
-        match tile {
-            TileType::Floor => {
-                commands.spawn((
-                    Tile,
-                    Position { x, y },
-                    Text2d::new("."),
-                    text_font.clone(),
-                    TextColor(Color::srgb(0.5, 0.5, 0.5)),
-                    Revealed(revealed_state),
-                ));
-            }
-            TileType::Wall => {
-                // Only spawn walls adjacent to floors (boundary walls)
-                if map.is_adjacent_to_floor(x, y) {
-                    let glyph = map.wall_glyph_at(x, y);
-                    commands.spawn((
-                        Tile,
-                        Position { x, y },
-                        glyph,
-                        Text2d::new(glyph.to_char().to_string()),
-                        text_font.clone(),
-                        TextColor(Color::srgb(0.0, 1.0, 0.0)),
-                        Revealed(revealed_state),
-                    ));
-                }
-            }
-            TileType::DownStairs => {
-                commands.spawn((
-                    Tile,
-                    Position { x, y },
-                    Text2d::new(">"),
-                    text_font.clone(),
-                    TextColor(Color::srgb(0.0, 1.0, 1.0)),
-                    Revealed(revealed_state),
-                ));
-            }
-        }
-
-        x += 1;
-        if x > MAP_WIDTH as i32 - 1 {
-            x = 0;
-            y += 1;
-        }
-    }
-
     // Spawn player
     let player_entity = commands
         .spawn((
@@ -481,9 +583,9 @@ pub fn load_game(
             },
             Viewshed {
                 range: save_data.player.viewshed_range,
-                visible_tiles: Vec::new(),
-                dirty: true,
+                ..default()
             },
+            LightSource::lantern(),
             HungerClock {
                 state: save_data.player.hunger_state,
                 duration: save_data.player.hunger_duration,
@@ -516,8 +618,7 @@ pub fn load_game(
             },
             Viewshed {
                 range: monster.viewshed_range,
-                visible_tiles: Vec::new(),
-                dirty: true,
+                ..default()
             },
             RenderableBundle::new(
                 &monster.glyph,
@@ -531,6 +632,12 @@ pub fn load_game(
         if let Some(turns) = monster.confusion_turns {
             entity_commands.insert(Confusion { turns });
         }
+        if let Some(size) = monster.footprint {
+            entity_commands.insert(Footprint(size));
+        }
+        if monster.opens_doors {
+            entity_commands.insert(OpensDoors);
+        }
     }
 
     // Spawn items
@@ -593,6 +700,12 @@ pub fn load_game(
         if item.properties.magic_mapper {
             entity_commands.insert(MagicMapper);
         }
+        if item.properties.heart_of_the_abyss {
+            entity_commands.insert(HeartOfTheAbyss);
+        }
+        if let Some(lock) = item.properties.key {
+            entity_commands.insert(Key { lock });
+        }
     }
 
     // Spawn traps
@@ -620,6 +733,63 @@ pub fn load_game(
         }
     }
 
+    // Spawn townsfolk
+    for npc in save_data.npcs {
+        spawner::spawn_npc(commands, &text_font, npc.x, npc.y, &npc.name);
+    }
+
+    // Spawn doors
+    for door in save_data.doors {
+        let (name, color) = if door.gate.is_some() {
+            ("Portcullis", palettes::css::LIGHT_SLATE_GRAY)
+        } else if door.lock.is_some() {
+            ("Locked Door", palettes::css::GOLDENROD)
+        } else if door.secret {
+            ("Secret Door", palettes::css::CHOCOLATE)
+        } else {
+            ("Door", palettes::css::CHOCOLATE)
+        };
+        let state = Door {
+            open: door.open,
+            lock: door.lock,
+            locked: door.locked,
+            gate: door.gate,
+        };
+        let glyph = spawner::door_glyph(&state);
+        let mut entity_commands = commands.spawn((
+            state,
+            Name {
+                name: name.to_string(),
+            },
+            Position { x: door.x, y: door.y },
+            RenderableBundle::new(
+                glyph,
+                color.into(),
+                palettes::basic::BLACK.into(),
+                RenderOrder::ITEM,
+                &text_font,
+            ),
+        ));
+
+        if !door.open {
+            entity_commands.insert(BlocksTile);
+            if door.gate.is_none() {
+                entity_commands.insert(BlocksVisibility);
+            }
+        }
+        if door.secret {
+            entity_commands.insert((SecretDoor, Hidden));
+        }
+    }
+
+    // Spawn levers
+    for lever in save_data.levers {
+        spawner::spawn_lever(commands, &text_font, lever.x, lever.y, lever.gate, lever.pulled);
+    }
+
+    // Torches and fungi aren't saved; they are placed the same way for the same map
+    lighting::spawn_level_lights(commands, map, &text_font);
+
     // Delete save file (permadeath)
     delete_save_file();
 
@@ -635,6 +805,8 @@ pub fn load_game(
 pub fn save_game(
     _map: Res<Map>,
     _game_log: Res<GameLog>,
+    _journey: Res<ReturnJourney>,
+    _run_stats: Res<RunStats>,
     _player_query: Query<
         (Entity, &Position, &Name, &CombatStats, &Viewshed, &HungerClock),
         With<Player>,
@@ -647,6 +819,8 @@ pub fn save_game(
             &Viewshed,
             &Text2d,
             Option<&Confusion>,
+            Option<&Footprint>,
+            Has<OpensDoors>,
         ),
         With<Monster>,
     >,
@@ -666,7 +840,7 @@ pub fn save_game(
             Option<&AreaOfEffect>,
             Option<&Targeting>,
             Option<&CausesConfusion>,
-            Option<&MagicMapper>,
+            (Option<&MagicMapper>, Option<&HeartOfTheAbyss>, Option<&Key>),
         ),
         With<Item>,
     >,
@@ -682,6 +856,9 @@ pub fn save_game(
         ),
         With<EntryTrigger>,
     >,
+    _npc_query: Query<(&Position, &Name), With<Npc>>,
+    _door_query: Query<(&Position, &Door, Has<SecretDoor>)>,
+    _lever_query: Query<(&Position, &Lever)>,
 ) {
     // No-op on WASM
 }
@@ -699,9 +876,11 @@ pub fn delete_save_file() {
 #[cfg(target_arch = "wasm32")]
 pub fn load_game(
     _commands: &mut Commands,
-    _entities_to_despawn: &Query<Entity, Or<(With<Player>, With<Monster>, With<Item>, With<Tile>, With<EntryTrigger>)>>,
+    _entities_to_despawn: &Query<Entity, Or<(With<Player>, With<Monster>, With<Item>, With<EntryTrigger>, With<Npc>, With<Door>, With<Lever>, With<LightSource>)>>,
     _map: &mut Map,
     _game_log: &mut GameLog,
+    _journey: &mut ReturnJourney,
+    _run_stats: &mut RunStats,
     _font: &UiFont,
 ) -> bool {
     false
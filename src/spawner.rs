@@ -5,15 +5,18 @@ use crate::{
     combat::CombatStats,
     components::{
         AreaOfEffect, BlocksTile, BlocksVisibility, CausesConfusion, Consumable, DefenseBonus,
-        Door, EntryTrigger, EquipmentSlot, Equippable, Hidden, HungerClock, HungerState,
-        InflictsDamage, Item, MagicMapper, MeleePowerBonus, Name, ProvidesFood, ProvidesHealing,
-        Ranged, RenderOrder, RenderableBundle, SingleActivation, Targeting,
+        Door, EntryTrigger, EquipmentSlot, Equippable, HeartOfTheAbyss, Hidden, HungerClock,
+        HungerState, InflictsDamage, Item, Key, Lever, MagicMapper, MeleePowerBonus, Name, Npc,
+        OpensDoors, ProvidesFood, ProvidesHealing, Ranged, RenderOrder, RenderableBundle,
+        SecretDoor, SingleActivation, Targeting,
     },
-    map::{Position, MAP_WIDTH},
+    lighting::LightSource,
+    map::{Footprint, Position, MAP_WIDTH},
     monsters::Monster,
     player::Player,
-    rng::{GameRng, RandomTable},
+    rng::GameRng,
     shapes::Rect,
+    theme::Theme,
     viewshed::Viewshed,
 };
 
@@ -37,6 +40,7 @@ pub fn spawn_player(commands: &mut Commands, font: &TextFont, x: i32, y: i32) {
             range: 8,
             ..default()
         },
+        LightSource::lantern(),
         HungerClock {
             state: HungerState::WellFed,
             duration: 200,
@@ -51,6 +55,7 @@ pub fn spawn_player(commands: &mut Commands, font: &TextFont, x: i32, y: i32) {
     ));
 }
 
+/// Spawns what lives in a room
 pub fn spawn_room(
     commands: &mut Commands,
     rng: &mut GameRng,
@@ -59,6 +64,28 @@ pub fn spawn_room(
     monster_id: &mut usize,
     map_depth: i32,
 ) {
+    for (idx, name) in roll_room(rng, room, map_depth) {
+        spawn_rolled(commands, font, idx, &name, monster_id);
+    }
+}
+
+/// Spawn entities in a region defined by tile indices (for non-rectangular areas like caves)
+pub fn spawn_region(
+    commands: &mut Commands,
+    rng: &mut GameRng,
+    font: &TextFont,
+    tiles: &[usize],
+    monster_id: &mut usize,
+    map_depth: i32,
+) {
+    for (idx, name) in roll_region(rng, tiles, map_depth) {
+        spawn_rolled(commands, font, idx, &name, monster_id);
+    }
+}
+
+/// Rolls the monsters and items for a room from the depth's spawn tables, as map indexes and
+/// names
+pub fn roll_room(rng: &mut GameRng, room: &Rect, map_depth: i32) -> Vec<(usize, String)> {
     // Calculate spawn counts based on depth
     let max_monsters_roll = (MAX_MONSTERS + 3) + (map_depth - 1) - 3;
     let num_monsters = if max_monsters_roll > 0 {
@@ -74,101 +101,95 @@ pub fn spawn_room(
         0
     };
 
-    // Build weighted spawn tables based on depth
-    let monster_table = RandomTable::new()
-        .add("Goblin", 10)
-        .add("Orc", 1 + map_depth);
-
-    let item_table = RandomTable::new()
-        .add("Health Potion", 7)
-        .add("Rations", 10)
-        .add("Magic Missile Scroll", 2)
-        .add("Fireball Scroll", map_depth - 1)
-        .add("Confusion Scroll", map_depth - 1)
-        .add("Magic Mapping Scroll", 2)
-        .add("Dagger", 3)
-        .add("Shield", 3)
-        .add("Longsword", map_depth - 1)
-        .add("Tower Shield", map_depth - 1)
-        .add("Bear Trap", 2);
+    // Build weighted spawn tables from the depth's theme
+    let theme = Theme::for_depth(map_depth);
+    let monster_table = theme.monster_table(map_depth);
+    let item_table = theme.item_table(map_depth);
 
+    let idx = |x: i32, y: i32| y as usize * MAP_WIDTH + x as usize;
+    let mut rolled = Vec::new();
     let mut spawn_points: Vec<(i32, i32)> = Vec::new();
 
     // Generate monster spawn points
     for _ in 0..num_monsters {
-        let mut added = false;
-        while !added {
+        let mut attempts = 0;
+        while attempts < 20 {
             let x = rng.0.gen_range(room.x1 + 1..=room.x2);
             let y = rng.0.gen_range(room.y1 + 1..=room.y2);
             if !spawn_points.contains(&(x, y)) {
                 spawn_points.push((x, y));
-                added = true;
+                break;
             }
+            attempts += 1;
         }
     }
 
-    // Spawn monsters using weighted table
-    for (x, y) in spawn_points.iter() {
+    // Roll monsters using weighted table
+    let mut occupied: Vec<(i32, i32)> = Vec::new();
+    for &(x, y) in spawn_points.iter() {
         if let Some(monster_name) = monster_table.roll(rng) {
-            match monster_name.as_str() {
-                "Orc" => spawn_orc(commands, font, *x, *y, *monster_id),
-                _ => spawn_goblin(commands, font, *x, *y, *monster_id),
+            let (ax, ay) = if monster_name == "Dragon" {
+                // Pulled back from the far walls so all of it fits in the room
+                (
+                    x.min(room.x2 - 1).max(room.x1 + 1),
+                    y.min(room.y2 - 1).max(room.y1 + 1),
+                )
+            } else {
+                (x, y)
+            };
+            // Never on top of another monster, placed already or still to come
+            let cells: Vec<(i32, i32)> = footprint_of(&monster_name)
+                .cells(Position { x: ax, y: ay })
+                .collect();
+            let overlaps = cells.iter().any(|cell| {
+                occupied.contains(cell) || (*cell != (x, y) && spawn_points.contains(cell))
+            });
+            if overlaps {
+                continue;
             }
-            *monster_id += 1;
+            occupied.extend(cells);
+            rolled.push((idx(ax, ay), monster_name));
         }
     }
 
     // Generate item spawn points
     let mut item_spawn_points: Vec<(i32, i32)> = Vec::new();
     for _ in 0..num_items {
-        let mut added = false;
-        while !added {
+        let mut attempts = 0;
+        while attempts < 20 {
             let x = rng.0.gen_range(room.x1 + 1..=room.x2);
             let y = rng.0.gen_range(room.y1 + 1..=room.y2);
-            if !spawn_points.contains(&(x, y)) && !item_spawn_points.contains(&(x, y)) {
+            if !spawn_points.contains(&(x, y))
+                && !occupied.contains(&(x, y))
+                && !item_spawn_points.contains(&(x, y))
+            {
                 item_spawn_points.push((x, y));
-                added = true;
+                break;
             }
+            attempts += 1;
         }
     }
 
-    // Spawn items using weighted table
+    // Roll items using weighted table
     for (x, y) in item_spawn_points.iter() {
         if let Some(item_name) = item_table.roll(rng) {
-            match item_name.as_str() {
-                "Health Potion" => spawn_health_potion(commands, font, *x, *y),
-                "Rations" => spawn_rations(commands, font, *x, *y),
-                "Magic Missile Scroll" => spawn_magic_missile_scroll(commands, font, *x, *y),
-                "Fireball Scroll" => spawn_fireball_scroll(commands, font, *x, *y),
-                "Confusion Scroll" => spawn_confusion_scroll(commands, font, *x, *y),
-                "Magic Mapping Scroll" => spawn_magic_mapping_scroll(commands, font, *x, *y),
-                "Dagger" => spawn_dagger(commands, font, *x, *y),
-                "Shield" => spawn_shield(commands, font, *x, *y),
-                "Longsword" => spawn_longsword(commands, font, *x, *y),
-                "Tower Shield" => spawn_tower_shield(commands, font, *x, *y),
-                "Bear Trap" => spawn_bear_trap(commands, font, *x, *y),
-                _ => {}
-            }
+            rolled.push((idx(*x, *y), item_name));
         }
     }
+    rolled
 }
 
-/// Spawn entities in a region defined by tile indices (for non-rectangular areas like caves)
-pub fn spawn_region(
-    commands: &mut Commands,
-    rng: &mut GameRng,
-    font: &TextFont,
-    tiles: &[usize],
-    monster_id: &mut usize,
-    map_depth: i32,
-) {
+/// Rolls the monsters and items for a region of tiles from the depth's spawn tables, as map
+/// indexes and names
+pub fn roll_region(rng: &mut GameRng, tiles: &[usize], map_depth: i32) -> Vec<(usize, String)> {
     if tiles.is_empty() {
-        return;
+        return Vec::new();
     }
 
     // Calculate spawn counts based on depth and region size
     let area_factor = (tiles.len() as f32 / 50.0).min(1.0); // Scale by region size
-    let max_monsters_roll = (((MAX_MONSTERS + 3) + (map_depth - 1) - 3) as f32 * area_factor) as i32;
+    let max_monsters_roll =
+        (((MAX_MONSTERS + 3) + (map_depth - 1) - 3) as f32 * area_factor) as i32;
     let num_monsters = if max_monsters_roll > 0 {
         rng.0.gen_range(0..=max_monsters_roll)
     } else {
@@ -182,24 +203,12 @@ pub fn spawn_region(
         0
     };
 
-    // Build weighted spawn tables based on depth
-    let monster_table = RandomTable::new()
-        .add("Goblin", 10)
-        .add("Orc", 1 + map_depth);
-
-    let item_table = RandomTable::new()
-        .add("Health Potion", 7)
-        .add("Rations", 10)
-        .add("Magic Missile Scroll", 2)
-        .add("Fireball Scroll", map_depth - 1)
-        .add("Confusion Scroll", map_depth - 1)
-        .add("Magic Mapping Scroll", 2)
-        .add("Dagger", 3)
-        .add("Shield", 3)
-        .add("Longsword", map_depth - 1)
-        .add("Tower Shield", map_depth - 1)
-        .add("Bear Trap", 2);
+    // Build weighted spawn tables from the depth's theme
+    let theme = Theme::for_depth(map_depth);
+    let monster_table = theme.monster_table(map_depth);
+    let item_table = theme.item_table(map_depth);
 
+    let mut rolled = Vec::new();
     let mut spawn_points: Vec<usize> = Vec::new();
 
     // Generate monster spawn points
@@ -215,16 +224,18 @@ pub fn spawn_region(
         }
     }
 
-    // Spawn monsters using weighted table
+    // Roll monsters using weighted table
     for idx in spawn_points.iter() {
         let x = (*idx % MAP_WIDTH) as i32;
         let y = (*idx / MAP_WIDTH) as i32;
         if let Some(monster_name) = monster_table.roll(rng) {
-            match monster_name.as_str() {
-                "Orc" => spawn_orc(commands, font, x, y, *monster_id),
-                _ => spawn_goblin(commands, font, x, y, *monster_id),
+            // Only where the whole of it is inside the region
+            let fits = footprint_of(&monster_name)
+                .cells(Position { x, y })
+                .all(|(cx, cy)| tiles.contains(&(cy as usize * MAP_WIDTH + cx as usize)));
+            if fits {
+                rolled.push((*idx, monster_name));
             }
-            *monster_id += 1;
         }
     }
 
@@ -242,29 +253,46 @@ pub fn spawn_region(
         }
     }
 
-    // Spawn items using weighted table
+    // Roll items using weighted table
     for idx in item_spawn_points.iter() {
-        let x = (*idx % MAP_WIDTH) as i32;
-        let y = (*idx / MAP_WIDTH) as i32;
         if let Some(item_name) = item_table.roll(rng) {
-            match item_name.as_str() {
-                "Health Potion" => spawn_health_potion(commands, font, x, y),
-                "Rations" => spawn_rations(commands, font, x, y),
-                "Magic Missile Scroll" => spawn_magic_missile_scroll(commands, font, x, y),
-                "Fireball Scroll" => spawn_fireball_scroll(commands, font, x, y),
-                "Confusion Scroll" => spawn_confusion_scroll(commands, font, x, y),
-                "Magic Mapping Scroll" => spawn_magic_mapping_scroll(commands, font, x, y),
-                "Dagger" => spawn_dagger(commands, font, x, y),
-                "Shield" => spawn_shield(commands, font, x, y),
-                "Longsword" => spawn_longsword(commands, font, x, y),
-                "Tower Shield" => spawn_tower_shield(commands, font, x, y),
-                "Bear Trap" => spawn_bear_trap(commands, font, x, y),
-                _ => {}
-            }
+            rolled.push((*idx, item_name));
         }
     }
+    rolled
 }
 
+/// Spawns a monster or item rolled from a spawn table
+fn spawn_rolled(
+    commands: &mut Commands,
+    font: &TextFont,
+    idx: usize,
+    name: &str,
+    monster_id: &mut usize,
+) {
+    let x = (idx % MAP_WIDTH) as i32;
+    let y = (idx / MAP_WIDTH) as i32;
+    match name {
+        "Orc" => spawn_orc(commands, font, x, y, *monster_id),
+        "Dragon" => spawn_dragon(commands, font, x, y, *monster_id),
+        "Goblin" => spawn_goblin(commands, font, x, y, *monster_id),
+        "Health Potion" => spawn_health_potion(commands, font, x, y),
+        "Rations" => spawn_rations(commands, font, x, y),
+        "Magic Missile Scroll" => spawn_magic_missile_scroll(commands, font, x, y),
+        "Fireball Scroll" => spawn_fireball_scroll(commands, font, x, y),
+        "Confusion Scroll" => spawn_confusion_scroll(commands, font, x, y),
+        "Magic Mapping Scroll" => spawn_magic_mapping_scroll(commands, font, x, y),
+        "Dagger" => spawn_dagger(commands, font, x, y),
+        "Shield" => spawn_shield(commands, font, x, y),
+        "Longsword" => spawn_longsword(commands, font, x, y),
+        "Tower Shield" => spawn_tower_shield(commands, font, x, y),
+        "Bear Trap" => spawn_bear_trap(commands, font, x, y),
+        _ => return,
+    }
+    if matches!(name, "Orc" | "Dragon" | "Goblin") {
+        *monster_id += 1;
+    }
+}
 fn spawn_orc(commands: &mut Commands, font: &TextFont, x: i32, y: i32, id: usize) {
     spawn_monster(commands, font, x, y, "o", &format!("Orc #{}", id));
 }
@@ -273,6 +301,43 @@ fn spawn_goblin(commands: &mut Commands, font: &TextFont, x: i32, y: i32, id: us
     spawn_monster(commands, font, x, y, "g", &format!("Goblin #{}", id));
 }
 
+/// How many tiles across a monster from the spawn tables is
+pub fn footprint_of(name: &str) -> Footprint {
+    match name {
+        "Dragon" => Footprint(2),
+        _ => Footprint::default(),
+    }
+}
+
+pub fn spawn_dragon(commands: &mut Commands, font: &TextFont, x: i32, y: i32, id: usize) {
+    commands.spawn((
+        Monster,
+        Name {
+            name: format!("Dragon #{}", id),
+        },
+        Position { x, y },
+        footprint_of("Dragon"),
+        BlocksTile,
+        CombatStats {
+            max_hp: 60,
+            hp: 60,
+            defense: 3,
+            power: 9,
+        },
+        Viewshed {
+            range: 10,
+            ..default()
+        },
+        RenderableBundle::new(
+            "DD\nDD",
+            palettes::basic::RED.into(),
+            palettes::basic::BLACK.into(),
+            RenderOrder::MONSTER,
+            font,
+        ),
+    ));
+}
+
 fn spawn_monster(
     commands: &mut Commands,
     font: &TextFont,
@@ -288,6 +353,7 @@ fn spawn_monster(
         },
         Position { x, y },
         BlocksTile,
+        OpensDoors,
         CombatStats {
             max_hp: 16,
             hp: 16,
@@ -513,6 +579,24 @@ pub fn spawn_magic_mapping_scroll(commands: &mut Commands, font: &TextFont, x: i
     ));
 }
 
+pub fn spawn_heart_of_the_abyss(commands: &mut Commands, font: &TextFont, x: i32, y: i32) {
+    commands.spawn((
+        Item,
+        HeartOfTheAbyss,
+        Name {
+            name: "Heart of the Abyss".to_string(),
+        },
+        Position { x, y },
+        RenderableBundle::new(
+            "♥",
+            palettes::css::CRIMSON.into(),
+            palettes::basic::BLACK.into(),
+            RenderOrder::ITEM,
+            font,
+        ),
+    ));
+}
+
 pub fn spawn_bear_trap(commands: &mut Commands, font: &TextFont, x: i32, y: i32) {
     commands.spawn((
         Name {
@@ -533,9 +617,72 @@ pub fn spawn_bear_trap(commands: &mut Commands, font: &TextFont, x: i32, y: i32)
     ));
 }
 
+/// One of the townsfolk, by name: the keepers of the named buildings, or anyone else in town
+pub fn spawn_npc(commands: &mut Commands, font: &TextFont, x: i32, y: i32, name: &str) {
+    let (color, greeting): (Color, _) = match name {
+        "Barkeep" => (
+            palettes::css::CHOCOLATE.into(),
+            "Drink up. Nobody pays their tab from down there.",
+        ),
+        "Priest" => (
+            palettes::basic::WHITE.into(),
+            "May the light follow you into the dark.",
+        ),
+        "Blacksmith" => (
+            palettes::css::LIGHT_SLATE_GRAY.into(),
+            "Bring me steel from the mines and I'll make it sing.",
+        ),
+        "Alchemist" => (
+            palettes::css::MEDIUM_PURPLE.into(),
+            "Mushrooms from the Fungal Depths fetch a fine price.",
+        ),
+        _ => (
+            palettes::css::WHEAT.into(),
+            "They say the Heart of the Abyss beats at the very bottom.",
+        ),
+    };
+    commands.spawn((
+        Npc { greeting },
+        Name {
+            name: name.to_string(),
+        },
+        Position { x, y },
+        BlocksTile,
+        RenderableBundle::new(
+            "@",
+            color,
+            palettes::basic::BLACK.into(),
+            RenderOrder::MONSTER,
+            font,
+        ),
+    ));
+}
+
+/// A hidden door; the map has wall here until it is found
+pub fn spawn_secret_door(commands: &mut Commands, font: &TextFont, x: i32, y: i32) {
+    commands.spawn((
+        Door::default(),
+        SecretDoor,
+        Hidden,
+        Name {
+            name: "Secret Door".to_string(),
+        },
+        Position { x, y },
+        BlocksTile,
+        BlocksVisibility,
+        RenderableBundle::new(
+            "+",
+            palettes::css::CHOCOLATE.into(),
+            palettes::basic::BLACK.into(),
+            RenderOrder::ITEM,
+            font,
+        ),
+    ));
+}
+
 pub fn spawn_door(commands: &mut Commands, font: &TextFont, x: i32, y: i32) {
     commands.spawn((
-        Door { open: false },
+        Door::default(),
         Name {
             name: "Door".to_string(),
         },
@@ -551,3 +698,151 @@ pub fn spawn_door(commands: &mut Commands, font: &TextFont, x: i32, y: i32) {
         ),
     ));
 }
+
+/// Glyph of a door, open or shut. Portcullises are bars that rise out of the way.
+pub fn door_glyph(door: &Door) -> &'static str {
+    match (door.gate.is_some(), door.open) {
+        (true, true) => "'",
+        (true, false) => "#",
+        (false, true) => "/",
+        (false, false) => "+",
+    }
+}
+
+pub fn lever_glyph(pulled: bool) -> &'static str {
+    if pulled {
+        "/"
+    } else {
+        "\\"
+    }
+}
+
+/// Spawn list name for one half of a lock and its key, or of a portcullis and its lever, e.g.
+/// "Key/2" for the key to "Locked Door/2"
+pub fn paired_name(kind: &str, id: u32) -> String {
+    format!("{}/{}", kind, id)
+}
+
+/// Keys are told apart by what they are made of
+pub fn key_name(lock: u32) -> String {
+    const METALS: [&str; 4] = ["Brass", "Iron", "Silver", "Bone"];
+    format!("{} Key", METALS[lock as usize % METALS.len()])
+}
+
+pub fn spawn_locked_door(commands: &mut Commands, font: &TextFont, x: i32, y: i32, lock: u32) {
+    commands.spawn((
+        Door {
+            lock: Some(lock),
+            locked: true,
+            ..default()
+        },
+        Name {
+            name: "Locked Door".to_string(),
+        },
+        Position { x, y },
+        BlocksTile,
+        BlocksVisibility,
+        RenderableBundle::new(
+            "+",
+            palettes::css::GOLDENROD.into(),
+            palettes::basic::BLACK.into(),
+            RenderOrder::ITEM,
+            font,
+        ),
+    ));
+}
+
+/// A gate of iron bars: it blocks the way but not the view, and only a lever raises it
+pub fn spawn_portcullis(commands: &mut Commands, font: &TextFont, x: i32, y: i32, gate: u32) {
+    commands.spawn((
+        Door {
+            gate: Some(gate),
+            ..default()
+        },
+        Name {
+            name: "Portcullis".to_string(),
+        },
+        Position { x, y },
+        BlocksTile,
+        RenderableBundle::new(
+            "#",
+            palettes::css::LIGHT_SLATE_GRAY.into(),
+            palettes::basic::BLACK.into(),
+            RenderOrder::ITEM,
+            font,
+        ),
+    ));
+}
+
+pub fn spawn_key(commands: &mut Commands, font: &TextFont, x: i32, y: i32, lock: u32) {
+    commands.spawn((
+        Item,
+        Key { lock },
+        Name {
+            name: key_name(lock),
+        },
+        Position { x, y },
+        RenderableBundle::new(
+            "-",
+            palettes::css::GOLD.into(),
+            palettes::basic::BLACK.into(),
+            RenderOrder::ITEM,
+            font,
+        ),
+    ));
+}
+
+/// A lever set into a wall
+pub fn spawn_lever(
+    commands: &mut Commands,
+    font: &TextFont,
+    x: i32,
+    y: i32,
+    gate: u32,
+    pulled: bool,
+) {
+    commands.spawn((
+        Lever { gate, pulled },
+        Name {
+            name: "Lever".to_string(),
+        },
+        Position { x, y },
+        RenderableBundle::new(
+            lever_glyph(pulled),
+            palettes::css::GOLD.into(),
+            palettes::basic::BLACK.into(),
+            RenderOrder::ITEM,
+            font,
+        ),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rolled_monsters_never_share_a_tile() {
+        let room = Rect::new(10, 10, 4, 4);
+        let mut dragons = 0;
+        for seed in 0..200 {
+            let mut rng = GameRng::seeded(seed);
+            let mut cells = Vec::new();
+            for (idx, name) in roll_room(&mut rng, &room, 11) {
+                let anchor = Position {
+                    x: (idx % MAP_WIDTH) as i32,
+                    y: (idx / MAP_WIDTH) as i32,
+                };
+                if !matches!(name.as_str(), "Goblin" | "Orc" | "Dragon") {
+                    continue;
+                }
+                dragons += usize::from(name == "Dragon");
+                for cell in footprint_of(&name).cells(anchor) {
+                    assert!(!cells.contains(&cell), "seed {}", seed);
+                    cells.push(cell);
+                }
+            }
+        }
+        assert!(dragons > 0);
+    }
+}
@@ -0,0 +1,227 @@
+use bevy::prelude::*;
+
+use crate::journey::{HEART_DEPTH, SURFACE_DEPTH};
+use crate::map::TileType;
+use crate::map_builders::{recipe_builder, MapBuilder};
+use crate::rng::RandomTable;
+
+// ============================================================================
+// Level Themes - The bands of the Abyss from the design doc
+// ============================================================================
+
+/// The look, layout and inhabitants of a band of depths
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Theme {
+    Town,
+    RuinedCellars,
+    LimestoneCaverns,
+    ForgottenMines,
+    FungalDepths,
+    SunkenTemple,
+    DarkRealm,
+    HeartChamber,
+}
+
+/// Colors and glyphs a theme draws its tiles with
+pub struct Palette {
+    pub wall: Color,
+    pub floor: (char, Color),
+    pub vegetation: (char, Color),
+}
+
+impl Theme {
+    /// The theme of a depth. Anything past the Heart Chamber (the harder spawn depths of the
+    /// return journey) belongs to the Dark Realm.
+    pub fn for_depth(depth: i32) -> Self {
+        match depth {
+            ..=SURFACE_DEPTH => Theme::Town,
+            1 => Theme::RuinedCellars,
+            2..=3 => Theme::LimestoneCaverns,
+            4..=5 => Theme::ForgottenMines,
+            6..=7 => Theme::FungalDepths,
+            8..=9 => Theme::SunkenTemple,
+            HEART_DEPTH => Theme::HeartChamber,
+            _ => Theme::DarkRealm,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Theme::Town => "Town",
+            Theme::RuinedCellars => "Ruined Cellars",
+            Theme::LimestoneCaverns => "Limestone Caverns",
+            Theme::ForgottenMines => "Forgotten Mines",
+            Theme::FungalDepths => "Fungal Depths",
+            Theme::SunkenTemple => "Sunken Temple",
+            Theme::DarkRealm => "The Dark Realm",
+            Theme::HeartChamber => "Heart Chamber",
+        }
+    }
+
+    pub fn palette(&self) -> Palette {
+        let grass = ('"', Color::srgb(0.2, 0.8, 0.2));
+        match self {
+            Theme::Town => Palette {
+                wall: Color::srgb(0.65, 0.45, 0.3),
+                floor: ('.', Color::srgb(0.6, 0.55, 0.4)),
+                vegetation: grass,
+            },
+            Theme::RuinedCellars => Palette {
+                wall: Color::srgb(0.6, 0.55, 0.45),
+                floor: ('.', Color::srgb(0.5, 0.5, 0.5)),
+                vegetation: grass,
+            },
+            Theme::LimestoneCaverns => Palette {
+                wall: Color::srgb(0.85, 0.85, 0.7),
+                floor: ('.', Color::srgb(0.55, 0.55, 0.45)),
+                vegetation: grass,
+            },
+            Theme::ForgottenMines => Palette {
+                wall: Color::srgb(0.6, 0.4, 0.2),
+                floor: ('.', Color::srgb(0.5, 0.4, 0.3)),
+                vegetation: grass,
+            },
+            Theme::FungalDepths => Palette {
+                wall: Color::srgb(0.55, 0.25, 0.65),
+                floor: ('.', Color::srgb(0.35, 0.5, 0.35)),
+                vegetation: ('♣', Color::srgb(0.7, 0.35, 0.9)),
+            },
+            Theme::SunkenTemple => Palette {
+                wall: Color::srgb(0.7, 0.75, 0.95),
+                floor: ('·', Color::srgb(0.6, 0.6, 0.75)),
+                vegetation: ('"', Color::srgb(0.3, 0.6, 0.5)),
+            },
+            Theme::DarkRealm => Palette {
+                wall: Color::srgb(0.6, 0.05, 0.1),
+                floor: ('.', Color::srgb(0.4, 0.25, 0.25)),
+                vegetation: ('"', Color::srgb(0.4, 0.4, 0.2)),
+            },
+            Theme::HeartChamber => Palette {
+                wall: Color::srgb(0.9, 0.1, 0.35),
+                floor: ('.', Color::srgb(0.55, 0.35, 0.4)),
+                vegetation: grass,
+            },
+        }
+    }
+
+    /// How a tile looks in this theme, or None when it is drawn the same everywhere
+    pub fn tile_style(&self, tile: TileType) -> Option<(char, Color)> {
+        let palette = self.palette();
+        match tile {
+            TileType::Floor => Some(palette.floor),
+            TileType::Grass => Some(palette.vegetation),
+            _ => None,
+        }
+    }
+
+    /// Name of the map recipe (in assets/recipes/levels.json) that lays out a level of this
+    /// theme
+    pub fn recipe(&self) -> &'static str {
+        match self {
+            // Houses along the road, the way down at the end of it
+            Theme::Town => "town",
+            // Collapsed rooms and corridors, the odd heap of rubble
+            Theme::RuinedCellars => "ruined_cellars",
+            // Open caves with underground pools
+            Theme::LimestoneCaverns => "limestone_caverns",
+            // Dwarven halls cut straight through the rock, with shafts dropping away
+            Theme::ForgottenMines => "forgotten_mines",
+            // Winding tunnels overgrown with mushrooms
+            Theme::FungalDepths => "fungal_depths",
+            // Flooded halls
+            Theme::SunkenTemple => "sunken_temple",
+            // A twisted plane cut by lava and bottomless pits
+            Theme::DarkRealm => "dark_realm",
+            // The Heart of the Abyss guarded in the last room, and no stairs further down
+            Theme::HeartChamber => "heart_chamber",
+        }
+    }
+
+    /// The builder chain that lays out a level of this theme
+    pub fn builder(&self, depth: i32) -> Box<dyn MapBuilder> {
+        recipe_builder(self.recipe(), depth)
+    }
+
+    /// Monsters to roll for at a spawn depth
+    pub fn monster_table(&self, depth: i32) -> RandomTable {
+        let (goblin, orc) = match self {
+            // Nothing hunts on the surface
+            Theme::Town => (0, 0),
+            Theme::RuinedCellars => (10, 1),
+            Theme::LimestoneCaverns => (10, depth),
+            Theme::ForgottenMines => (6, 4 + depth),
+            Theme::FungalDepths => (8, 2 + depth),
+            Theme::SunkenTemple => (4, 4 + depth),
+            Theme::DarkRealm | Theme::HeartChamber => (2, 6 + depth),
+        };
+        // Dragons only lair in the deepest levels
+        let dragon = match self {
+            Theme::DarkRealm | Theme::HeartChamber => depth / 3,
+            _ => 0,
+        };
+        RandomTable::new()
+            .add("Goblin", goblin)
+            .add("Orc", orc)
+            .add("Dragon", dragon)
+    }
+
+    /// Items to roll for at a spawn depth
+    pub fn item_table(&self, depth: i32) -> RandomTable {
+        // The mines hide better gear and more traps, the fungal depths confusion, the temple
+        // healing
+        let (gear, traps, confusion, healing) = match self {
+            Theme::ForgottenMines => (3, 4, 0, 0),
+            Theme::FungalDepths => (0, 0, 4, 0),
+            Theme::SunkenTemple => (0, 0, 0, 5),
+            _ => (0, 0, 0, 0),
+        };
+        RandomTable::new()
+            .add("Health Potion", 7 + healing)
+            .add("Rations", 10)
+            .add("Magic Missile Scroll", 2)
+            .add("Fireball Scroll", depth - 1)
+            .add("Confusion Scroll", depth - 1 + confusion)
+            .add("Magic Mapping Scroll", 2)
+            .add("Dagger", 3)
+            .add("Shield", 3)
+            .add("Longsword", depth - 1 + gear)
+            .add("Tower Shield", depth - 1 + gear)
+            .add("Bear Trap", 2 + traps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::GameRng;
+
+    #[test]
+    fn test_every_depth_has_a_theme_band() {
+        assert_eq!(Theme::for_depth(SURFACE_DEPTH), Theme::Town);
+        assert_eq!(Theme::for_depth(1), Theme::RuinedCellars);
+        assert_eq!(Theme::for_depth(3), Theme::LimestoneCaverns);
+        assert_eq!(Theme::for_depth(6), Theme::FungalDepths);
+        assert_eq!(Theme::for_depth(11), Theme::DarkRealm);
+        assert_eq!(Theme::for_depth(HEART_DEPTH), Theme::HeartChamber);
+        // Return journey spawn depths run past the bottom
+        assert_eq!(Theme::for_depth(HEART_DEPTH + 5), Theme::DarkRealm);
+    }
+
+    #[test]
+    fn test_theme_builders_make_playable_levels() {
+        // The first depth of every band
+        for depth in [SURFACE_DEPTH, 1, 2, 4, 6, 8, 10, HEART_DEPTH] {
+            let theme = Theme::for_depth(depth);
+            let mut rng = GameRng::seeded(depth as u64);
+            let mut builder = theme.builder(depth);
+            builder.build_map(&mut rng);
+
+            let map = builder.get_map();
+            let (x, y) = builder.get_starting_position();
+            assert!(map.tiles[map.xy_idx(x, y)].is_walkable(), "{}", theme.name());
+            if theme != Theme::HeartChamber {
+                assert!(map.tiles.contains(&TileType::DownStairs), "{}", theme.name());
+            }
+        }
+    }
+}
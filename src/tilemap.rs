@@ -0,0 +1,324 @@
+use bevy::prelude::*;
+
+use crate::camera::{Camera, SCREEN_HEIGHT, SCREEN_WIDTH};
+use crate::console::{Cell, Console, ConsoleSet, Layer};
+use crate::lighting::LightMap;
+use crate::map::{tile_glyph, Map, RevealedState, Tile, FONT_SIZE, GRID_PX};
+use crate::resources::UiFont;
+use crate::tileset::{sprite_index, GraphicsMode, Tileset};
+
+// ============================================================================
+// Tile Map Rendering - The map layer of the console, and the Bevy text backend
+// ============================================================================
+
+/// Alpha of tiles that have been seen before but aren't in view
+const EXPLORED_ALPHA: f32 = 0.1;
+/// Alpha of things remembered on those tiles, brighter than the tiles so they stand out
+const REMEMBERED_ALPHA: f32 = 0.35;
+
+/// A Text2d cell of the viewport grid, with a sprite for the tiles mode. Cells never move; they
+/// present the console's map layer, while entities, particles and UI are Bevy entities of their
+/// own in the window.
+#[derive(Component)]
+pub struct TileCell {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// A map to draw instead of the current level, fully revealed and unlit (the mapgen visualizer
+/// shows its snapshots through this)
+#[derive(Resource, Default)]
+pub struct MapPreview(pub Option<Map>);
+
+pub struct TileMapPlugin;
+
+impl Plugin for TileMapPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MapPreview>()
+            .add_systems(Startup, spawn_tile_cells)
+            .add_systems(PostUpdate, draw_map_layer.in_set(ConsoleSet::Draw))
+            .add_systems(
+                PostUpdate,
+                (position_tile_cells, present_tile_cells).in_set(ConsoleSet::Present),
+            );
+    }
+}
+
+fn spawn_tile_cells(mut commands: Commands, font: Res<UiFont>, tileset: Res<Tileset>) {
+    let text_font = TextFont {
+        font: font.0.clone(),
+        font_size: FONT_SIZE,
+        ..default()
+    };
+
+    for y in 0..SCREEN_HEIGHT {
+        for x in 0..SCREEN_WIDTH {
+            commands.spawn((
+                Tile,
+                TileCell { x, y },
+                Text2d::default(),
+                text_font.clone(),
+                TextColor(Color::NONE),
+                tileset.sprite(0, Color::NONE),
+            ));
+        }
+    }
+}
+
+/// Lays the cells out over the window, again whenever it is resized
+fn position_tile_cells(
+    window: Single<Ref<Window>>,
+    mut cells: Query<(Ref<TileCell>, &mut Transform)>,
+) {
+    let half_width = window.width() / 2.;
+    let half_height = window.height() / 2.;
+
+    for (cell, mut transform) in &mut cells {
+        if !window.is_changed() && !cell.is_added() {
+            continue;
+        }
+        // Y runs in the opposite direction to the pixel coords
+        transform.set_if_neq(Transform::from_xyz(
+            (cell.x as f32) * GRID_PX.x + (GRID_PX.x / 2.) - half_width,
+            (cell.y as f32) * -GRID_PX.y - (GRID_PX.y / 2.) + half_height,
+            0.0,
+        ));
+    }
+}
+
+/// Draws the map (or the preview) under the camera into the viewport rows of the console's map
+/// layer
+pub fn draw_map_layer(
+    mut console: ResMut<Console>,
+    map: Res<Map>,
+    preview: Res<MapPreview>,
+    camera: Res<Camera>,
+    light_map: Res<LightMap>,
+) {
+    if !map.is_changed() && !preview.is_changed() && !camera.is_changed() && !light_map.is_changed() {
+        return;
+    }
+
+    for sy in 0..SCREEN_HEIGHT {
+        for sx in 0..SCREEN_WIDTH {
+            let (x, y) = camera.screen_to_world(sx, sy);
+            let look = match &preview.0 {
+                Some(snapshot) => cell_look(snapshot, x, y, |_| 1.0, |base| base),
+                None => remembered_look(&map, x, y).or_else(|| {
+                    cell_look(
+                        &map,
+                        x,
+                        y,
+                        |idx| fog_alpha(map.revealed_state(idx)),
+                        |base| light_map.tint(base, x, y),
+                    )
+                }),
+            };
+            match look {
+                Some((glyph, color)) => console.set(Layer::Map, sx, sy, Cell::new(glyph, color)),
+                None => console.unset(Layer::Map, sx, sy),
+            }
+        }
+    }
+}
+
+/// Copies the map layer onto the cells, only touching the ones whose look changed. In tiles
+/// mode a cell shows the sprite of its glyph, or the glyph itself if the sheet has none.
+fn present_tile_cells(
+    console: Res<Console>,
+    mode: Res<GraphicsMode>,
+    mut cells: Query<(&TileCell, &mut Text2d, &mut TextColor, &mut Sprite)>,
+) {
+    if !console.is_changed() && !mode.is_changed() {
+        return;
+    }
+
+    for (cell, mut text, mut color, mut sprite) in &mut cells {
+        let look = console
+            .get(Layer::Map, cell.x, cell.y)
+            .unwrap_or(Cell::new(' ', Color::NONE));
+        let index = match *mode {
+            GraphicsMode::Tiles => sprite_index(look.glyph),
+            GraphicsMode::Ascii => None,
+        };
+
+        let mut buf = [0; 4];
+        let glyph = match index {
+            Some(_) => "",
+            None => look.glyph.encode_utf8(&mut buf),
+        };
+        if text.0 != *glyph {
+            text.0 = glyph.to_string();
+        }
+        if color.0 != look.fg {
+            color.0 = look.fg;
+        }
+
+        let (index, tint) = index.map_or((0, Color::NONE), |index| (index, look.fg));
+        if sprite.color != tint {
+            sprite.color = tint;
+        }
+        // Looked at before borrowing it mutably, which would mark the sprite changed
+        if sprite.texture_atlas.as_ref().is_some_and(|atlas| atlas.index != index) {
+            if let Some(atlas) = sprite.texture_atlas.as_mut() {
+                atlas.index = index;
+            }
+        }
+    }
+}
+
+fn fog_alpha(state: RevealedState) -> f32 {
+    match state {
+        RevealedState::Visible => 1.0,
+        RevealedState::Explored => EXPLORED_ALPHA,
+        RevealedState::Hidden => 0.0,
+    }
+}
+
+/// What was last seen standing on an explored tile that is out of view now
+fn remembered_look(map: &Map, x: i32, y: i32) -> Option<(char, Color)> {
+    if x < 0 || x >= map.width || y < 0 || y >= map.height {
+        return None;
+    }
+    let idx = map.xy_idx(x, y);
+    if map.revealed_state(idx) != RevealedState::Explored {
+        return None;
+    }
+    let &(glyph, color) = map.remembered.get(&idx)?;
+    Some((glyph, color.with_alpha(REMEMBERED_ALPHA)))
+}
+
+/// Glyph and color of a map tile, or None if there is nothing to draw there
+fn cell_look(
+    map: &Map,
+    x: i32,
+    y: i32,
+    alpha: impl Fn(usize) -> f32,
+    light: impl Fn(Color) -> Color,
+) -> Option<(char, Color)> {
+    if x < 0 || x >= map.width || y < 0 || y >= map.height {
+        return None;
+    }
+    let alpha = alpha(map.xy_idx(x, y));
+    if alpha <= 0.0 {
+        return None;
+    }
+    let (glyph, base) = tile_glyph(map, x, y)?;
+    Some((glyph, light(base).with_alpha(alpha)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::CameraPlugin;
+    use crate::components::RenderOrder;
+    use crate::console::ConsolePlugin;
+    use crate::debug::DebugState;
+    use crate::lighting::{LightSource, LightingPlugin};
+    use crate::map::{MapPlugin, Position, TileType};
+    use crate::map_indexing::map_indexing_system;
+    use crate::player::Player;
+    use crate::viewshed::{Viewshed, ViewshedPlugin};
+    use crate::RunState;
+    use bevy::state::app::StatesPlugin;
+
+    #[test]
+    fn test_cells_only_draw_revealed_tiles_on_the_map() {
+        let mut map = Map::new(5, 5, 1);
+        let idx = map.xy_idx(2, 2);
+        map.tiles[idx] = TileType::Floor;
+        map.revealed_tiles[idx] = true;
+        let fog = |idx: usize| fog_alpha(map.revealed_state(idx));
+
+        let (_, color) = cell_look(&map, 2, 2, fog, |base| base).unwrap();
+        assert_eq!(color.alpha(), EXPLORED_ALPHA);
+        // The wall next to it hasn't been seen, and nothing is off the map
+        assert!(cell_look(&map, 1, 2, fog, |base| base).is_none());
+        assert!(cell_look(&map, -1, 2, |_| 1.0, |base| base).is_none());
+    }
+
+    #[test]
+    fn test_remembered_things_only_show_out_of_view() {
+        let mut map = Map::new(5, 5, 1);
+        let idx = map.xy_idx(2, 2);
+        map.tiles[idx] = TileType::Floor;
+        map.revealed_tiles[idx] = true;
+        map.remembered.insert(idx, ('o', Color::srgb(1.0, 0.0, 0.0)));
+
+        let (glyph, color) = remembered_look(&map, 2, 2).unwrap();
+        assert_eq!((glyph, color.alpha()), ('o', REMEMBERED_ALPHA));
+        // In view, the orc itself is drawn instead of the memory of it
+        map.visible_tiles[idx] = true;
+        assert!(remembered_look(&map, 2, 2).is_none());
+    }
+
+    #[test]
+    fn test_standing_still_leaves_the_grid_alone() {
+        let mut map = Map::default();
+        for y in 10..20 {
+            for x in 10..30 {
+                let idx = map.xy_idx(x, y);
+                map.tiles[idx] = TileType::Floor;
+            }
+        }
+
+        let mut app = App::new();
+        app.add_plugins((
+            StatesPlugin,
+            CameraPlugin,
+            ViewshedPlugin,
+            LightingPlugin,
+            MapPlugin,
+            ConsolePlugin,
+        ))
+        .insert_state(RunState::AwaitingInput)
+        .insert_resource(map)
+        .init_resource::<DebugState>()
+        .init_resource::<MapPreview>()
+        .init_resource::<GraphicsMode>()
+        .add_systems(Update, map_indexing_system)
+        .add_systems(PostUpdate, draw_map_layer.in_set(ConsoleSet::Draw))
+        .add_systems(PostUpdate, present_tile_cells.in_set(ConsoleSet::Present));
+        app.world_mut().spawn(Window::default());
+        app.world_mut().spawn((
+            Player,
+            Position { x: 15, y: 15 },
+            Viewshed::default(),
+            LightSource::lantern(),
+            Text2d::new("@"),
+            TextColor(Color::WHITE),
+            RenderOrder::PLAYER,
+            Visibility::Visible,
+        ));
+        let sprite = Sprite {
+            texture_atlas: Some(TextureAtlas::default()),
+            ..default()
+        };
+        let cell = app
+            .world_mut()
+            .spawn((TileCell { x: 0, y: 0 }, Text2d::default(), TextColor::default(), sprite))
+            .id();
+        let sprite_changed = |app: &App| app.world().entity(cell).get_ref::<Sprite>().unwrap().last_changed();
+
+        // Settle the first look around, then nothing moves
+        for _ in 0..3 {
+            app.update();
+        }
+        let drawn = app.world().resource_ref::<Console>().last_changed();
+        let presented = sprite_changed(&app);
+        app.update();
+        app.update();
+        assert_eq!(app.world().resource_ref::<Console>().last_changed(), drawn);
+        assert_eq!(sprite_changed(&app), presented);
+
+        // Taking a step redraws it
+        app.world_mut()
+            .query_filtered::<&mut Position, With<Player>>()
+            .single_mut(app.world_mut())
+            .x += 1;
+        app.update();
+        assert_ne!(app.world().resource_ref::<Console>().last_changed(), drawn);
+        // A cell that looks the same is left alone
+        assert_eq!(sprite_changed(&app), presented);
+    }
+}
@@ -0,0 +1,258 @@
+use bevy::prelude::*;
+use bevy::render::view::RenderLayers;
+
+use crate::components::Door;
+use crate::map::{Position, WallGlyph};
+use crate::tilemap::TileCell;
+
+// ============================================================================
+// Tileset - Sprites from a texture atlas in place of the text glyphs
+// ============================================================================
+
+/// Size of a sprite in the sheet, the same as a grid cell
+const SPRITE_PX: u32 = 16;
+/// The sheet is 16x16 sprites, one per CP437 code (tools/make_tileset.py draws it)
+const SHEET_COLUMNS: u32 = 16;
+
+/// An open door is drawn as '/' like a sword; the sheet has an open door at the quote
+const OPEN_DOOR: usize = '\'' as usize;
+
+/// A render layer no camera looks at, which hides an entity's own text behind its sprite
+pub const HIDDEN_LAYER: usize = 1;
+
+/// How the map and everything on it are drawn in the window
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GraphicsMode {
+    #[default]
+    Ascii,
+    Tiles,
+}
+
+impl GraphicsMode {
+    pub fn toggled(self) -> Self {
+        match self {
+            GraphicsMode::Ascii => GraphicsMode::Tiles,
+            GraphicsMode::Tiles => GraphicsMode::Ascii,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GraphicsMode::Ascii => "ASCII",
+            GraphicsMode::Tiles => "Tiles",
+        }
+    }
+}
+
+/// The sprite sheet and its atlas layout
+#[derive(Resource)]
+pub struct Tileset {
+    pub image: Handle<Image>,
+    pub layout: Handle<TextureAtlasLayout>,
+}
+
+impl FromWorld for Tileset {
+    fn from_world(world: &mut World) -> Self {
+        let image = world.resource::<AssetServer>().load("images/tileset.png");
+        let layout =
+            world
+                .resource_mut::<Assets<TextureAtlasLayout>>()
+                .add(TextureAtlasLayout::from_grid(
+                    UVec2::splat(SPRITE_PX),
+                    SHEET_COLUMNS,
+                    SHEET_COLUMNS,
+                    None,
+                    None,
+                ));
+        Tileset { image, layout }
+    }
+}
+
+impl Tileset {
+    pub fn sprite(&self, index: usize, color: Color) -> Sprite {
+        Sprite {
+            color,
+            ..Sprite::from_atlas_image(
+                self.image.clone(),
+                TextureAtlas {
+                    layout: self.layout.clone(),
+                    index,
+                },
+            )
+        }
+    }
+}
+
+/// Index of a glyph's sprite in the sheet, if it has one. Glyphs without one stay text.
+pub fn sprite_index(glyph: char) -> Option<usize> {
+    let code = match glyph {
+        '☺' => 1,
+        '♥' => 3,
+        '♣' => 5,
+        '○' => 9,
+        '▲' => 30,
+        '"' | '%' | '\'' | '(' | ')' | '+' | ',' | '.' | '/' | ':' | '<' | '=' | '>' | '^'
+        | 'g' | 'o' | '~' => glyph as usize,
+        '¡' => 173,
+        '│' => 179,
+        '┤' => 180,
+        '┐' => 191,
+        '└' => 192,
+        '┴' => 193,
+        '┬' => 194,
+        '├' => 195,
+        '─' => 196,
+        '┼' => 197,
+        '┘' => 217,
+        '┌' => 218,
+        '≡' => 240,
+        '≈' => 247,
+        '·' => 250,
+        _ => return None,
+    };
+    Some(code)
+}
+
+impl WallGlyph {
+    /// The autotile sprite joining this wall to its neighbors
+    pub fn sprite_index(&self) -> usize {
+        sprite_index(self.to_char()).expect("every wall glyph has a sprite")
+    }
+}
+
+/// Sprite of an entity: the one of its glyph, unless the glyph is shared with something else
+fn entity_sprite_index(glyph: &str, door: Option<&Door>) -> Option<usize> {
+    // Raised portcullises keep their own glyph
+    if door.is_some_and(|door| door.open && door.gate.is_none()) {
+        return Some(OPEN_DOOR);
+    }
+    let mut chars = glyph.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => sprite_index(c),
+        _ => None,
+    }
+}
+
+/// The sprite drawn over an entity in tiles mode, a child of the entity
+#[derive(Component)]
+pub struct GlyphSprite;
+
+pub struct TilesetPlugin;
+
+impl Plugin for TilesetPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GraphicsMode>()
+            .init_resource::<Tileset>()
+            .add_systems(
+                PostUpdate,
+                (despawn_orphaned_sprites, sync_entity_sprites).chain(),
+            );
+    }
+}
+
+/// Gives every entity with a known glyph a sprite in tiles mode, and takes it away again in
+/// ASCII mode
+fn sync_entity_sprites(
+    mut commands: Commands,
+    mode: Res<GraphicsMode>,
+    tileset: Res<Tileset>,
+    entities: Query<
+        (
+            Entity,
+            Ref<Text2d>,
+            Ref<TextColor>,
+            Option<Ref<Door>>,
+            Option<&Children>,
+        ),
+        (With<Position>, Without<TileCell>),
+    >,
+    mut sprites: Query<&mut Sprite, With<GlyphSprite>>,
+) {
+    for (entity, text, color, door, children) in &entities {
+        let changed = mode.is_changed()
+            || text.is_changed()
+            || color.is_changed()
+            || door.as_ref().is_some_and(|door| door.is_changed());
+        if !changed {
+            continue;
+        }
+
+        let index = match *mode {
+            GraphicsMode::Tiles => entity_sprite_index(&text.0, door.as_deref()),
+            GraphicsMode::Ascii => None,
+        };
+        let existing = children
+            .into_iter()
+            .flatten()
+            .copied()
+            .find(|child| sprites.contains(*child));
+
+        match (index, existing) {
+            (Some(index), Some(child)) => {
+                if let Ok(mut sprite) = sprites.get_mut(child) {
+                    sprite.color = color.0;
+                    if let Some(atlas) = sprite.texture_atlas.as_mut() {
+                        atlas.index = index;
+                    }
+                }
+            }
+            (Some(index), None) => {
+                commands
+                    .entity(entity)
+                    .insert(RenderLayers::layer(HIDDEN_LAYER))
+                    .with_child((
+                        GlyphSprite,
+                        tileset.sprite(index, color.0),
+                        // Just above the entity's own z
+                        Transform::from_xyz(0.0, 0.0, 0.01),
+                    ));
+            }
+            (None, Some(child)) => {
+                commands.entity(child).despawn_recursive();
+                commands.entity(entity).remove::<RenderLayers>();
+            }
+            (None, None) => {}
+        }
+    }
+}
+
+/// Entities are mostly despawned on their own, which leaves their sprite behind
+fn despawn_orphaned_sprites(
+    mut commands: Commands,
+    sprites: Query<(Entity, &Parent), With<GlyphSprite>>,
+    entities: Query<(), With<Position>>,
+) {
+    for (sprite, parent) in &sprites {
+        if !entities.contains(parent.get()) {
+            commands.entity(sprite).despawn();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wall_masks_map_to_autotiles() {
+        // A lone pillar, a straight run and a four-way junction
+        assert_eq!(WallGlyph(0).sprite_index(), 9);
+        assert_eq!(WallGlyph(12).sprite_index(), WallGlyph(4).sprite_index());
+        assert_eq!(WallGlyph(15).sprite_index(), 197);
+        for mask in 0..16 {
+            WallGlyph(mask).sprite_index();
+        }
+    }
+
+    #[test]
+    fn test_open_doors_do_not_look_like_swords() {
+        let open = Door {
+            open: true,
+            ..default()
+        };
+        assert_eq!(entity_sprite_index("/", None), Some('/' as usize));
+        assert_eq!(entity_sprite_index("/", Some(&open)), Some(OPEN_DOOR));
+        assert_eq!(entity_sprite_index("g", None), Some('g' as usize));
+        assert_eq!(entity_sprite_index("*", None), None);
+    }
+}
@@ -2,11 +2,12 @@ use bevy::prelude::*;
 use rand::Rng;
 
 use crate::{
-    combat::SufferDamage,
-    components::{EntryTrigger, Hidden, InflictsDamage, Name, SingleActivation},
-    gamelog::GameLog,
-    map::{Map, Position},
-    particle::ParticleBuilder,
+    components::{
+        EntryTrigger, Hidden, InflictsDamage, Name, SecretDoor, SingleActivation, WantsToSearch,
+    },
+    effects::{EffectEvent, Targets},
+    gamelog::{GameLog, LogCategory},
+    map::{Map, Position, TileType},
     player::Player,
     rng::GameRng,
     viewshed::Viewshed,
@@ -15,16 +16,23 @@ use crate::{
 pub fn trap_trigger_system(
     mut commands: Commands,
     mut gamelog: ResMut<GameLog>,
-    mut particle_builder: ResMut<ParticleBuilder>,
+    mut effects: EventWriter<EffectEvent>,
     map: Res<Map>,
-    moved_query: Query<(Entity, &Position, &Name), Changed<Position>>,
+    moved_query: Query<(Entity, &Position, &Name, &TextColor), Changed<Position>>,
     just_spawned: Query<Entity, Added<Position>>,
     trap_query: Query<
-        (Entity, &Position, &Name, &InflictsDamage, Option<&SingleActivation>),
+        (
+            Entity,
+            &Position,
+            &Name,
+            &TextColor,
+            &InflictsDamage,
+            Option<&SingleActivation>,
+        ),
         With<EntryTrigger>,
     >,
 ) {
-    for (victim_entity, victim_pos, victim_name) in &moved_query {
+    for (victim_entity, victim_pos, victim_name, victim_color) in &moved_query {
         // Skip entities that were just spawned (not actually moved)
         if just_spawned.contains(victim_entity) {
             continue;
@@ -33,24 +41,28 @@ pub fn trap_trigger_system(
         let idx = map.xy_idx(victim_pos.x, victim_pos.y);
 
         for trap_entity in map.tile_content[idx].iter() {
-            if let Ok((trap_ent, trap_pos, trap_name, damage, single)) =
+            if let Ok((trap_ent, trap_pos, trap_name, trap_color, damage, single)) =
                 trap_query.get(*trap_entity)
             {
-                // Apply damage
-                SufferDamage::new_damage(&mut commands, victim_entity, damage.damage);
-                gamelog.entries.push(format!(
-                    "{} triggers {}, taking {} damage!",
-                    victim_name.name, trap_name.name, damage.damage
-                ));
-
-                // Spawn particle
-                particle_builder.request(
-                    trap_pos.x,
-                    trap_pos.y,
+                gamelog
+                    .entry(LogCategory::Combat)
+                    .colored(&victim_name.name, victim_color.0)
+                    .text(" triggers ")
+                    .colored(&trap_name.name, trap_color.0)
+                    .text("!")
+                    .log();
+                effects.send(
+                    EffectEvent::damage(damage.damage, Targets::Single(victim_entity))
+                        .with_source(trap_ent, &trap_name.name, trap_color.0),
+                );
+                effects.send(EffectEvent::particle(
                     "‼",
                     Color::srgb(1.0, 0.5, 0.0),
-                    200.0,
-                );
+                    Targets::Tile {
+                        x: trap_pos.x,
+                        y: trap_pos.y,
+                    },
+                ));
 
                 // Remove Hidden component (now visible)
                 commands.entity(trap_ent).remove::<Hidden>();
@@ -69,6 +81,7 @@ pub fn reveal_hidden_system(
     mut gamelog: ResMut<GameLog>,
     player_query: Query<&Viewshed, With<Player>>,
     hidden_query: Query<(Entity, &Position, &Name), With<Hidden>>,
+    secret_doors: Query<(), With<SecretDoor>>,
     mut rng: ResMut<GameRng>,
 ) {
     let Ok(viewshed) = player_query.get_single() else {
@@ -76,14 +89,63 @@ pub fn reveal_hidden_system(
     };
 
     for (entity, pos, name) in &hidden_query {
-        if viewshed.visible_tiles.contains(&(pos.x, pos.y)) {
+        // Secret doors are looked for once a turn instead, in search_system
+        if secret_doors.contains(entity) {
+            continue;
+        }
+        if viewshed.visible_tiles.contains(pos.x, pos.y) {
             // 1 in 24 chance to spot hidden entity
             if rng.0.gen_range(1..=24) == 1 {
                 commands.entity(entity).remove::<Hidden>();
-                gamelog
-                    .entries
-                    .push(format!("You spotted a {}.", name.name));
+                gamelog.log(LogCategory::Status, format!("You spotted a {}.", name.name));
             }
         }
     }
 }
+
+/// Looks for hidden things once a player turn. Secret doors in view may be noticed in passing;
+/// a player who spends the turn searching goes over everything right next to them.
+pub fn search_system(
+    mut commands: Commands,
+    mut gamelog: ResMut<GameLog>,
+    mut map: ResMut<Map>,
+    mut rng: ResMut<GameRng>,
+    player_query: Query<(Entity, &Position, &Viewshed, Has<WantsToSearch>), With<Player>>,
+    hidden_query: Query<(Entity, &Position, &Name, Has<SecretDoor>), With<Hidden>>,
+) {
+    let Ok((player_entity, player_pos, viewshed, searching)) = player_query.get_single() else {
+        return;
+    };
+
+    let mut found = false;
+    for (entity, pos, name, secret) in &hidden_query {
+        let adjacent = (pos.x - player_pos.x).abs() <= 1 && (pos.y - player_pos.y).abs() <= 1;
+        let noticed = if searching && adjacent {
+            rng.0.gen_range(0..3) > 0
+        } else {
+            secret && viewshed.visible_tiles.contains(pos.x, pos.y) && rng.0.gen_range(1..=24) == 1
+        };
+        if !noticed {
+            continue;
+        }
+
+        found = true;
+        commands.entity(entity).remove::<Hidden>();
+        if secret {
+            // The wall it passed for becomes a doorway
+            commands.entity(entity).remove::<SecretDoor>();
+            let idx = map.xy_idx(pos.x, pos.y);
+            map.tiles[idx] = TileType::Floor;
+            gamelog.log(LogCategory::Status, "You found a secret door!");
+        } else {
+            gamelog.log(LogCategory::Status, format!("You found a {}.", name.name));
+        }
+    }
+
+    if searching {
+        commands.entity(player_entity).remove::<WantsToSearch>();
+        if !found {
+            gamelog.log(LogCategory::Status, "You search the area but find nothing.");
+        }
+    }
+}
@@ -0,0 +1,281 @@
+use std::collections::VecDeque;
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::Mutex;
+use std::thread;
+
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::input::mouse::MouseButtonInput;
+use bevy::input::ButtonState;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use crossterm::event::{
+    self, Event, KeyCode as TermKey, KeyEvent, KeyEventKind, KeyModifiers,
+    MouseButton as TermButton, MouseEventKind,
+};
+
+use crate::map::GRID_PX;
+
+// ============================================================================
+// Terminal Input - Keys and mouse from the terminal as Bevy input events
+// ============================================================================
+
+/// Events read from the terminal on a thread of their own, so a frame never waits on stdin
+#[derive(Resource)]
+pub struct TerminalEvents(Mutex<Receiver<Event>>);
+
+impl TerminalEvents {
+    pub fn spawn_reader() -> Self {
+        let (sender, receiver) = channel();
+        thread::spawn(move || {
+            while let Ok(event) = event::read() {
+                if sender.send(event).is_err() {
+                    break;
+                }
+            }
+        });
+        Self(Mutex::new(receiver))
+    }
+}
+
+/// A key press translated for Bevy. Terminals only report what was typed, so a shifted symbol
+/// becomes its key plus a held shift.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TerminalKey {
+    pub code: KeyCode,
+    pub logical: Key,
+    pub shift: bool,
+}
+
+const LETTERS: [KeyCode; 26] = [
+    KeyCode::KeyA,
+    KeyCode::KeyB,
+    KeyCode::KeyC,
+    KeyCode::KeyD,
+    KeyCode::KeyE,
+    KeyCode::KeyF,
+    KeyCode::KeyG,
+    KeyCode::KeyH,
+    KeyCode::KeyI,
+    KeyCode::KeyJ,
+    KeyCode::KeyK,
+    KeyCode::KeyL,
+    KeyCode::KeyM,
+    KeyCode::KeyN,
+    KeyCode::KeyO,
+    KeyCode::KeyP,
+    KeyCode::KeyQ,
+    KeyCode::KeyR,
+    KeyCode::KeyS,
+    KeyCode::KeyT,
+    KeyCode::KeyU,
+    KeyCode::KeyV,
+    KeyCode::KeyW,
+    KeyCode::KeyX,
+    KeyCode::KeyY,
+    KeyCode::KeyZ,
+];
+
+const DIGITS: [KeyCode; 10] = [
+    KeyCode::Digit0,
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+];
+
+const FUNCTION_KEYS: [(KeyCode, Key); 12] = [
+    (KeyCode::F1, Key::F1),
+    (KeyCode::F2, Key::F2),
+    (KeyCode::F3, Key::F3),
+    (KeyCode::F4, Key::F4),
+    (KeyCode::F5, Key::F5),
+    (KeyCode::F6, Key::F6),
+    (KeyCode::F7, Key::F7),
+    (KeyCode::F8, Key::F8),
+    (KeyCode::F9, Key::F9),
+    (KeyCode::F10, Key::F10),
+    (KeyCode::F11, Key::F11),
+    (KeyCode::F12, Key::F12),
+];
+
+/// The key a character is typed with on a US layout, and whether it needs shift
+fn char_key(c: char) -> Option<(KeyCode, bool)> {
+    if c.is_ascii_lowercase() {
+        return Some((LETTERS[(c as u8 - b'a') as usize], false));
+    }
+    if c.is_ascii_uppercase() {
+        return Some((LETTERS[(c as u8 - b'A') as usize], true));
+    }
+    if c.is_ascii_digit() {
+        return Some((DIGITS[(c as u8 - b'0') as usize], false));
+    }
+    if let Some(i) = ")!@#$%^&*(".find(c) {
+        return Some((DIGITS[i], true));
+    }
+
+    let key = match c {
+        ' ' => (KeyCode::Space, false),
+        '.' => (KeyCode::Period, false),
+        '>' => (KeyCode::Period, true),
+        ',' => (KeyCode::Comma, false),
+        '<' => (KeyCode::Comma, true),
+        '-' => (KeyCode::Minus, false),
+        '_' => (KeyCode::Minus, true),
+        '=' => (KeyCode::Equal, false),
+        '+' => (KeyCode::Equal, true),
+        '/' => (KeyCode::Slash, false),
+        '?' => (KeyCode::Slash, true),
+        ';' => (KeyCode::Semicolon, false),
+        ':' => (KeyCode::Semicolon, true),
+        '\'' => (KeyCode::Quote, false),
+        '"' => (KeyCode::Quote, true),
+        '`' => (KeyCode::Backquote, false),
+        '~' => (KeyCode::Backquote, true),
+        '[' => (KeyCode::BracketLeft, false),
+        '{' => (KeyCode::BracketLeft, true),
+        ']' => (KeyCode::BracketRight, false),
+        '}' => (KeyCode::BracketRight, true),
+        '\\' => (KeyCode::Backslash, false),
+        '|' => (KeyCode::Backslash, true),
+        _ => return None,
+    };
+    Some(key)
+}
+
+pub fn translate_key(event: &KeyEvent) -> Option<TerminalKey> {
+    let shift = event.modifiers.contains(KeyModifiers::SHIFT);
+    let (code, logical, shift) = match event.code {
+        TermKey::Char(c) => {
+            let (code, shifted) = char_key(c)?;
+            (code, Key::Character(c.to_string().into()), shift || shifted)
+        }
+        TermKey::Enter => (KeyCode::Enter, Key::Enter, shift),
+        TermKey::Esc => (KeyCode::Escape, Key::Escape, shift),
+        TermKey::Backspace => (KeyCode::Backspace, Key::Backspace, shift),
+        TermKey::Tab => (KeyCode::Tab, Key::Tab, shift),
+        TermKey::Left => (KeyCode::ArrowLeft, Key::ArrowLeft, shift),
+        TermKey::Right => (KeyCode::ArrowRight, Key::ArrowRight, shift),
+        TermKey::Up => (KeyCode::ArrowUp, Key::ArrowUp, shift),
+        TermKey::Down => (KeyCode::ArrowDown, Key::ArrowDown, shift),
+        TermKey::PageUp => (KeyCode::PageUp, Key::PageUp, shift),
+        TermKey::PageDown => (KeyCode::PageDown, Key::PageDown, shift),
+        TermKey::Home => (KeyCode::Home, Key::Home, shift),
+        TermKey::End => (KeyCode::End, Key::End, shift),
+        TermKey::F(n @ 1..=12) => {
+            let (code, logical) = FUNCTION_KEYS[n as usize - 1].clone();
+            (code, logical, shift)
+        }
+        _ => return None,
+    };
+    Some(TerminalKey {
+        code,
+        logical,
+        shift,
+    })
+}
+
+/// Turns terminal events into the keyboard and mouse events winit would send. Key presses are
+/// handed over one per frame and released on the next, the way a player types in the window;
+/// the mouse moves the cursor of the primary window.
+pub fn forward_terminal_input(
+    events: Res<TerminalEvents>,
+    mut queued: Local<VecDeque<TerminalKey>>,
+    mut held: Local<Vec<(KeyCode, Key)>>,
+    mut window: Single<(Entity, &mut Window), With<PrimaryWindow>>,
+    mut keys: EventWriter<KeyboardInput>,
+    mut buttons: EventWriter<MouseButtonInput>,
+    mut exit: EventWriter<AppExit>,
+) {
+    let (window_entity, ref mut window) = *window;
+    let key_event = |key_code: KeyCode, logical_key: Key, state: ButtonState| KeyboardInput {
+        key_code,
+        logical_key,
+        state,
+        repeat: false,
+        window: window_entity,
+    };
+
+    for (code, logical) in held.drain(..) {
+        keys.send(key_event(code, logical, ButtonState::Released));
+    }
+
+    let Ok(receiver) = events.0.lock() else {
+        return;
+    };
+    for event in receiver.try_iter() {
+        match event {
+            Event::Key(key) if key.kind != KeyEventKind::Release => {
+                // Raw mode swallows the interrupt, so Ctrl+C quits from here
+                if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == TermKey::Char('c') {
+                    exit.send(AppExit::Success);
+                } else if let Some(key) = translate_key(&key) {
+                    queued.push_back(key);
+                }
+            }
+            Event::Mouse(mouse) => {
+                window.set_cursor_position(Some(Vec2::new(
+                    (mouse.column as f32 + 0.5) * GRID_PX.x,
+                    (mouse.row as f32 + 0.5) * GRID_PX.y,
+                )));
+                let (button, state) = match mouse.kind {
+                    MouseEventKind::Down(button) => (button, ButtonState::Pressed),
+                    MouseEventKind::Up(button) => (button, ButtonState::Released),
+                    _ => continue,
+                };
+                let button = match button {
+                    TermButton::Left => MouseButton::Left,
+                    TermButton::Right => MouseButton::Right,
+                    TermButton::Middle => MouseButton::Middle,
+                };
+                buttons.send(MouseButtonInput {
+                    button,
+                    state,
+                    window: window_entity,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(key) = queued.pop_front() {
+        if key.shift {
+            keys.send(key_event(
+                KeyCode::ShiftLeft,
+                Key::Shift,
+                ButtonState::Pressed,
+            ));
+            held.push((KeyCode::ShiftLeft, Key::Shift));
+        }
+        keys.send(key_event(
+            key.code,
+            key.logical.clone(),
+            ButtonState::Pressed,
+        ));
+        held.push((key.code, key.logical));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_typed_keys_map_to_key_codes() {
+        let typed = |code| translate_key(&KeyEvent::new(code, KeyModifiers::NONE)).unwrap();
+
+        let h = typed(TermKey::Char('h'));
+        assert_eq!((h.code, h.shift), (KeyCode::KeyH, false));
+        // Stairs are typed as '>' but bound to the period key
+        let down = typed(TermKey::Char('>'));
+        assert_eq!((down.code, down.shift), (KeyCode::Period, true));
+        assert_eq!(typed(TermKey::Char('G')).code, KeyCode::KeyG);
+        assert_eq!(typed(TermKey::Up).code, KeyCode::ArrowUp);
+        assert_eq!(typed(TermKey::F(12)).code, KeyCode::F12);
+        assert!(translate_key(&KeyEvent::new(TermKey::Char('é'), KeyModifiers::NONE)).is_none());
+    }
+}
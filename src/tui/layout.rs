@@ -0,0 +1,490 @@
+use bevy::prelude::*;
+use bevy::render::texture::TRANSPARENT_IMAGE_HANDLE;
+
+use crate::console::Cell;
+use crate::map::GRID_PX;
+use crate::ui::{DepthText, GameLogText, HealthBar, HealthText, HungerText};
+
+// ============================================================================
+// UI Layout - Bevy UI nodes laid out on the terminal's cell grid
+// ============================================================================
+
+/// Terminal glyphs are much narrower than the window's UI text, so pixel layouts don't carry
+/// over. Nodes are laid out again in whole cells instead: text takes a cell per character, pixel
+/// sizes become cells of GRID_PX, and flex rows and columns stack their children.
+pub struct Block {
+    pub width: i32,
+    pub height: i32,
+    cells: Vec<Option<Cell>>,
+}
+
+impl Block {
+    fn new(width: i32, height: i32) -> Self {
+        let (width, height) = (width.max(0), height.max(0));
+        Self {
+            width,
+            height,
+            cells: vec![None; (width * height) as usize],
+        }
+    }
+
+    pub fn get(&self, x: i32, y: i32) -> Option<Cell> {
+        if x < 0 || x >= self.width || y < 0 || y >= self.height {
+            return None;
+        }
+        self.cells[(y * self.width + x) as usize]
+    }
+
+    fn set(&mut self, x: i32, y: i32, cell: Cell) {
+        if x >= 0 && x < self.width && y >= 0 && y < self.height {
+            self.cells[(y * self.width + x) as usize] = Some(cell);
+        }
+    }
+
+    /// Gives every cell a background, keeping whatever glyphs are already there
+    fn fill(&mut self, bg: Color) {
+        for cell in &mut self.cells {
+            let glyph = cell.map_or(Cell::new(' ', Color::NONE), |cell| cell);
+            *cell = Some(Cell { bg, ..glyph });
+        }
+    }
+
+    /// Copies another block's cells over this one, keeping this one's background under glyphs
+    /// that have none
+    fn blit(&mut self, other: &Block, x: i32, y: i32) {
+        for oy in 0..other.height {
+            for ox in 0..other.width {
+                let Some(mut cell) = other.get(ox, oy) else {
+                    continue;
+                };
+                if cell.bg.alpha() <= 0.0 {
+                    if let Some(under) = self.get(x + ox, y + oy) {
+                        cell.bg = under.bg;
+                    }
+                }
+                self.set(x + ox, y + oy, cell);
+            }
+        }
+    }
+
+    fn border(&mut self, color: Color) {
+        let (right, bottom) = (self.width - 1, self.height - 1);
+        let mut put = |x, y, glyph| {
+            let bg = self.get(x, y).map_or(Color::NONE, |cell| cell.bg);
+            self.set(
+                x,
+                y,
+                Cell {
+                    glyph,
+                    fg: color,
+                    bg,
+                },
+            );
+        };
+        for x in 1..right {
+            put(x, 0, '─');
+            put(x, bottom, '─');
+        }
+        for y in 1..bottom {
+            put(0, y, '│');
+            put(right, y, '│');
+        }
+        put(0, 0, '┌');
+        put(right, 0, '┐');
+        put(0, bottom, '└');
+        put(right, bottom, '┘');
+    }
+
+    fn print_line(&mut self, x: i32, y: i32, line: &[Cell]) {
+        for (i, cell) in line.iter().enumerate() {
+            self.set(x + i as i32, y, *cell);
+        }
+    }
+}
+
+fn cells(px: f32) -> i32 {
+    (px / GRID_PX.x).round() as i32
+}
+
+fn resolve(val: Val, available: i32) -> Option<i32> {
+    match val {
+        Val::Px(px) => Some(cells(px)),
+        Val::Percent(percent) => Some((available as f32 * percent / 100.0).round() as i32),
+        _ => None,
+    }
+}
+
+/// Any non-zero edge is at least one cell, so padding and borders never vanish
+fn edges(rect: UiRect, available: i32) -> (i32, i32, i32, i32) {
+    let edge = |val: Val| match val {
+        Val::Px(px) if px > 0.0 => cells(px).max(1),
+        Val::Percent(_) => resolve(val, available).unwrap_or(0),
+        _ => 0,
+    };
+    (
+        edge(rect.left),
+        edge(rect.right),
+        edge(rect.top),
+        edge(rect.bottom),
+    )
+}
+
+/// The text of a Text node and its spans, one colored cell per character, split into lines
+pub fn text_lines(world: &World, entity: Entity) -> Vec<Vec<Cell>> {
+    let mut lines = vec![Vec::new()];
+    let color = world
+        .get::<TextColor>(entity)
+        .map_or(Color::WHITE, |color| color.0);
+    if let Some(text) = world.get::<Text>(entity) {
+        push_text(&mut lines, &text.0, color);
+    }
+    push_spans(world, entity, color, &mut lines);
+    lines
+}
+
+fn push_spans(world: &World, entity: Entity, color: Color, lines: &mut Vec<Vec<Cell>>) {
+    let Some(children) = world.get::<Children>(entity) else {
+        return;
+    };
+    for &child in children {
+        let Some(span) = world.get::<TextSpan>(child) else {
+            continue;
+        };
+        let color = world.get::<TextColor>(child).map_or(color, |color| color.0);
+        push_text(lines, &span.0, color);
+        push_spans(world, child, color, lines);
+    }
+}
+
+fn push_text(lines: &mut Vec<Vec<Cell>>, text: &str, color: Color) {
+    for (i, part) in text.split('\n').enumerate() {
+        if i > 0 {
+            lines.push(Vec::new());
+        }
+        if let Some(line) = lines.last_mut() {
+            line.extend(part.chars().map(|glyph| Cell::new(glyph, color)));
+        }
+    }
+}
+
+/// Lays a node and its children out in at most `available` cells. Returns None for nodes that
+/// aren't shown.
+pub fn layout(world: &World, entity: Entity, available: IVec2) -> Option<Block> {
+    let node = world.get::<Node>(entity)?;
+    if node.display == Display::None || world.get::<Visibility>(entity) == Some(&Visibility::Hidden)
+    {
+        return None;
+    }
+
+    let width = resolve(node.width, available.x);
+    let height = resolve(node.height, available.y);
+    let (pad_l, pad_r, pad_t, pad_b) = edges(node.padding, available.x);
+    let (border_l, border_r, border_t, border_b) = edges(node.border, available.x);
+    let frame = IVec2::new(
+        pad_l + pad_r + border_l + border_r,
+        pad_t + pad_b + border_t + border_b,
+    );
+    let inner = IVec2::new(width.unwrap_or(available.x), height.unwrap_or(available.y)) - frame;
+
+    let row = matches!(
+        node.flex_direction,
+        FlexDirection::Row | FlexDirection::RowReverse
+    );
+    let content = if world.get::<Text>(entity).is_some() {
+        text_block(world, entity, inner.x)
+    } else {
+        children_block(world, entity, node, inner, row)
+    };
+
+    let size = IVec2::new(
+        width.unwrap_or(content.width + frame.x),
+        height.unwrap_or(content.height + frame.y),
+    );
+    let mut block = Block::new(size.x, size.y);
+    let opaque_image = world
+        .get::<ImageNode>(entity)
+        .is_some_and(|image| image.image != TRANSPARENT_IMAGE_HANDLE);
+    if opaque_image {
+        block.fill(Color::BLACK);
+    }
+    if let Some(bg) = world
+        .get::<BackgroundColor>(entity)
+        .filter(|bg| bg.0.alpha() > 0.0)
+    {
+        block.fill(bg.0);
+    }
+    if border_l > 0 {
+        let color = world
+            .get::<BorderColor>(entity)
+            .map_or(Color::WHITE, |border| border.0);
+        block.border(color);
+    }
+
+    // Centering applies to the content as a whole, along and across the flex direction
+    let inner = size - frame;
+    let (centered_x, centered_y) = if row {
+        (
+            node.justify_content == JustifyContent::Center,
+            node.align_items == AlignItems::Center,
+        )
+    } else {
+        (
+            node.align_items == AlignItems::Center,
+            node.justify_content == JustifyContent::Center,
+        )
+    };
+    let x = border_l
+        + pad_l
+        + if centered_x {
+            (inner.x - content.width) / 2
+        } else {
+            0
+        };
+    let y = border_t
+        + pad_t
+        + if centered_y {
+            (inner.y - content.height) / 2
+        } else {
+            0
+        };
+    block.blit(&content, x, y);
+    Some(block)
+}
+
+fn text_block(world: &World, entity: Entity, max_width: i32) -> Block {
+    // Lines too long for the space they have wrap onto the next row
+    let wrap = max_width.max(1) as usize;
+    let lines: Vec<Vec<Cell>> = text_lines(world, entity)
+        .into_iter()
+        .flat_map(|line| {
+            if line.is_empty() {
+                vec![line]
+            } else {
+                line.chunks(wrap).map(<[Cell]>::to_vec).collect()
+            }
+        })
+        .collect();
+
+    let width = lines.iter().map(Vec::len).max().unwrap_or(0) as i32;
+    let mut block = Block::new(width, lines.len() as i32);
+    for (y, line) in lines.iter().enumerate() {
+        block.print_line(0, y as i32, line);
+    }
+    block
+}
+
+fn children_block(world: &World, entity: Entity, node: &Node, inner: IVec2, row: bool) -> Block {
+    let children: Vec<Entity> = world
+        .get::<Children>(entity)
+        .map(|children| children.iter().copied().collect())
+        .unwrap_or_default();
+    let main_size = |size: IVec2| if row { size.x } else { size.y };
+    let gap = edges(
+        UiRect::horizontal(if row { node.column_gap } else { node.row_gap }),
+        main_size(inner),
+    )
+    .0;
+
+    // Children that grow share whatever their fixed siblings leave
+    let growing = |child: Entity| {
+        world
+            .get::<Node>(child)
+            .is_some_and(|node| node.flex_grow > 0.0)
+    };
+    let mut blocks: Vec<Option<Block>> = children
+        .iter()
+        .map(|&child| {
+            if growing(child) {
+                None
+            } else {
+                layout(world, child, inner)
+            }
+        })
+        .collect();
+    let shown = children
+        .iter()
+        .zip(&blocks)
+        .filter(|(&child, block)| growing(child) || block.is_some())
+        .count() as i32;
+    let fixed: i32 = blocks
+        .iter()
+        .flatten()
+        .map(|block| if row { block.width } else { block.height })
+        .sum();
+    let growers = children.iter().filter(|&&child| growing(child)).count() as i32;
+    let spare = (main_size(inner) - fixed - gap * (shown - 1).max(0)).max(0);
+    for (i, &child) in children.iter().enumerate() {
+        if growing(child) {
+            let share = spare / growers.max(1);
+            let available = if row {
+                IVec2::new(share, inner.y)
+            } else {
+                IVec2::new(inner.x, share)
+            };
+            blocks[i] = layout(world, child, available).map(|block| {
+                let mut sized = if row {
+                    Block::new(share, block.height)
+                } else {
+                    Block::new(block.width, share)
+                };
+                sized.blit(&block, 0, 0);
+                sized
+            });
+        }
+    }
+
+    let blocks: Vec<Block> = blocks.into_iter().flatten().collect();
+    let along: i32 = blocks
+        .iter()
+        .map(|b| if row { b.width } else { b.height })
+        .sum::<i32>()
+        + gap * (blocks.len() as i32 - 1).max(0);
+    let across = blocks
+        .iter()
+        .map(|b| if row { b.height } else { b.width })
+        .max()
+        .unwrap_or(0);
+    let mut content = if row {
+        Block::new(along, across)
+    } else {
+        Block::new(across, along)
+    };
+
+    let mut offset = 0;
+    for block in &blocks {
+        if row {
+            content.blit(block, offset, 0);
+            offset += block.width + gap;
+        } else {
+            content.blit(block, 0, offset);
+            offset += block.height + gap;
+        }
+    }
+    content
+}
+
+/// Where a root node sits on a screen of `screen` cells
+pub fn place(node: &Node, block: &Block, screen: IVec2) -> IVec2 {
+    let x = match (resolve(node.left, screen.x), resolve(node.right, screen.x)) {
+        (Some(left), _) => left,
+        (None, Some(right)) => screen.x - right - block.width,
+        (None, None) => 0,
+    };
+    let y = match (resolve(node.top, screen.y), resolve(node.bottom, screen.y)) {
+        (Some(top), _) => top,
+        (None, Some(bottom)) => screen.y - bottom - block.height,
+        (None, None) => 0,
+    };
+    IVec2::new(x, y)
+}
+
+/// The text of the node carrying marker T, on one line
+fn marked_text<T: Component>(world: &mut World) -> Option<Vec<Cell>> {
+    let entity = world
+        .query_filtered::<Entity, With<T>>()
+        .iter(world)
+        .next()?;
+    Some(text_lines(world, entity).concat())
+}
+
+/// The HUD panel rearranged for 80 columns: the status line on top, the log under it at full
+/// width
+pub fn hud_block(world: &mut World, panel: Entity, screen: IVec2) -> Option<Block> {
+    let node = world.get::<Node>(panel)?;
+    let height = resolve(node.height, screen.y)?;
+    let background = world
+        .get::<BackgroundColor>(panel)
+        .map_or(Color::BLACK, |bg| bg.0);
+    let space = Cell::new(' ', Color::NONE);
+
+    let mut status = marked_text::<HealthText>(world).unwrap_or_default();
+    let mut bar = world.query_filtered::<(&Node, &BackgroundColor, &Parent), With<HealthBar>>();
+    if let Ok((node, fill, parent)) = bar.get_single(world) {
+        // The bar keeps the width it has in the window, its fill the same share of it
+        let empty = world
+            .get::<BackgroundColor>(parent.get())
+            .map_or(Color::BLACK, |bg| bg.0);
+        let width = world
+            .get::<Node>(parent.get())
+            .and_then(|parent| resolve(parent.width, screen.x))
+            .unwrap_or(0);
+        let filled = resolve(node.width, width).unwrap_or(0);
+        status.push(space);
+        status.extend((0..width).map(|i| Cell {
+            bg: if i < filled { fill.0 } else { empty },
+            ..space
+        }));
+    }
+    for text in [
+        marked_text::<DepthText>(world),
+        marked_text::<HungerText>(world),
+    ]
+    .into_iter()
+    .flatten()
+    .filter(|text| !text.is_empty())
+    {
+        status.push(space);
+        status.extend(text);
+    }
+
+    let mut block = Block::new(screen.x, height);
+    block.fill(background);
+    block.print_line(1, 0, &status);
+
+    let mut log = world.query_filtered::<Entity, With<GameLogText>>();
+    if let Some(entity) = log.iter(world).next() {
+        let lines = text_lines(world, entity);
+        let shown = (height - 1).max(0) as usize;
+        for (y, line) in lines.iter().rev().take(shown).rev().enumerate() {
+            let width = line.len().min((screen.x - 2).max(0) as usize);
+            block.print_line(1, y as i32 + 1, &line[..width]);
+        }
+    }
+    Some(block)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_menu_box_is_centered_in_cells() {
+        let mut world = World::new();
+        let text = world
+            .spawn((Text::new("Hi\nthere"), TextColor(Color::WHITE)))
+            .id();
+        let panel = world
+            .spawn((
+                Node {
+                    padding: UiRect::all(Val::Px(16.0)),
+                    border: UiRect::all(Val::Px(2.0)),
+                    ..default()
+                },
+                BorderColor(Color::WHITE),
+                BackgroundColor(Color::BLACK),
+            ))
+            .add_child(text)
+            .id();
+        let root = world
+            .spawn(Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            })
+            .add_child(panel)
+            .id();
+
+        let block = layout(&world, root, IVec2::new(20, 10)).unwrap();
+        assert_eq!((block.width, block.height), (20, 10));
+        // "there" plus a cell of padding and border either side is 9 wide, the box 6 tall
+        let (left, top) = ((20 - 9) / 2, (10 - 6) / 2);
+        assert_eq!(block.get(left, top).unwrap().glyph, '┌');
+        assert_eq!(block.get(left + 2, top + 2).unwrap().glyph, 'H');
+        assert_eq!(block.get(left + 2, top + 3).unwrap().glyph, 't');
+        assert_eq!(block.get(left + 8, top + 5).unwrap().glyph, '┘');
+        // Outside the box is left see-through
+        assert!(block.get(0, 0).is_none());
+    }
+}
@@ -0,0 +1,184 @@
+mod input;
+mod layout;
+
+use std::io::{self, IsTerminal, Stdout, Write};
+use std::time::Duration;
+
+use bevy::app::ScheduleRunnerPlugin;
+use bevy::input::InputSystem;
+use bevy::log::LogPlugin;
+use bevy::prelude::*;
+use bevy::render::settings::{RenderCreation, WgpuSettings};
+use bevy::render::RenderPlugin;
+use bevy::window::ExitCondition;
+#[cfg(feature = "window")]
+use bevy::winit::WinitPlugin;
+use crossterm::event::{DisableMouseCapture, EnableMouseCapture};
+use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{cursor, execute};
+
+use crate::console::{AnsiBackend, Console, ConsoleBackend, ConsoleSet, Layer};
+use crate::ui::HudPanel;
+use crate::RESOLUTION;
+
+use input::TerminalEvents;
+
+// ============================================================================
+// Terminal Frontend - The game in a terminal, for playing over SSH
+// ============================================================================
+
+/// How long a frame lasts; the terminal only receives the cells that changed
+const FRAME_TIME: Duration = Duration::from_millis(33);
+
+/// Runs the game without a window or GPU. The ECS, the game rules and the Bevy UI all run as
+/// they do in the window; the console is presented on the terminal with ANSI escapes, and
+/// terminal keys and mouse clicks come in as Bevy input events.
+pub struct TuiPlugin;
+
+impl Plugin for TuiPlugin {
+    fn build(&self, app: &mut App) {
+        let terminal = match Terminal::open() {
+            Ok(terminal) => terminal,
+            Err(e) => {
+                eprintln!("The terminal frontend needs an interactive terminal: {}", e);
+                std::process::exit(1);
+            }
+        };
+
+        let plugins = DefaultPlugins
+            .set(WindowPlugin {
+                // Never opened: it gives the UI a size and holds the mouse cursor
+                primary_window: Some(Window {
+                    resolution: RESOLUTION.into(),
+                    ..default()
+                }),
+                exit_condition: ExitCondition::DontExit,
+                close_when_requested: false,
+            })
+            .set(RenderPlugin {
+                render_creation: RenderCreation::Automatic(WgpuSettings {
+                    backends: None,
+                    ..default()
+                }),
+                ..default()
+            })
+            // Log lines would scribble over the screen
+            .disable::<LogPlugin>();
+        // Built alongside the window frontend, Bevy would otherwise open a window
+        #[cfg(feature = "window")]
+        let plugins = plugins.disable::<WinitPlugin>();
+
+        app.add_plugins(plugins)
+            .add_plugins(ScheduleRunnerPlugin::run_loop(FRAME_TIME))
+            .insert_resource(terminal)
+            .insert_resource(TerminalEvents::spawn_reader())
+            .add_systems(PreUpdate, input::forward_terminal_input.before(InputSystem))
+            .add_systems(PostUpdate, draw_ui_layer.in_set(ConsoleSet::Draw))
+            .add_systems(PostUpdate, present_terminal.in_set(ConsoleSet::Present));
+    }
+}
+
+/// The terminal in raw mode on the alternate screen, put back the way it was on drop
+#[derive(Resource)]
+struct Terminal {
+    backend: AnsiBackend<Stdout>,
+}
+
+impl Terminal {
+    fn open() -> io::Result<Self> {
+        if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+            return Err(io::Error::other("stdin and stdout have to be a terminal"));
+        }
+        terminal::enable_raw_mode()?;
+        let screen = execute!(
+            io::stdout(),
+            EnterAlternateScreen,
+            cursor::Hide,
+            EnableMouseCapture
+        );
+        if let Err(e) = screen {
+            restore();
+            return Err(e);
+        }
+
+        // A panic mid-game shouldn't leave the shell in raw mode
+        let hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            restore();
+            hook(info);
+        }));
+
+        Ok(Self {
+            backend: AnsiBackend::new(io::stdout()),
+        })
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        restore();
+    }
+}
+
+fn restore() {
+    let mut out = io::stdout();
+    let _ = execute!(out, DisableMouseCapture, cursor::Show, LeaveAlternateScreen);
+    let _ = out.flush();
+    let _ = terminal::disable_raw_mode();
+}
+
+/// Lays the Bevy UI out into the console's UI layer, the HUD first and every other panel over it
+fn draw_ui_layer(world: &mut World) {
+    let screen = {
+        let console = world.resource::<Console>();
+        IVec2::new(console.width, console.height)
+    };
+
+    let mut roots =
+        world.query_filtered::<(Entity, Option<&GlobalZIndex>), (With<Node>, Without<Parent>)>();
+    let mut roots: Vec<(i32, Entity)> = roots
+        .iter(world)
+        .map(|(entity, z)| (z.map_or(0, |z| z.0), entity))
+        .collect();
+    roots.sort();
+
+    let mut placed = Vec::new();
+    let mut hud = world.query_filtered::<Entity, With<HudPanel>>();
+    if let Some(panel) = hud.iter(world).next() {
+        if let Some(block) = layout::hud_block(world, panel, screen) {
+            placed.push((IVec2::new(0, screen.y - block.height), block));
+        }
+    }
+    for (_, root) in roots {
+        if world.get::<HudPanel>(root).is_some() {
+            continue;
+        }
+        let Some(node) = world.get::<Node>(root) else {
+            continue;
+        };
+        if let Some(block) = layout::layout(world, root, screen) {
+            placed.push((layout::place(node, &block, screen), block));
+        }
+    }
+
+    let mut console = world.resource_mut::<Console>();
+    console.clear_layer(Layer::Ui);
+    for (at, block) in placed {
+        for y in 0..block.height {
+            for x in 0..block.width {
+                if let Some(cell) = block.get(x, y) {
+                    console.set(Layer::Ui, at.x + x, at.y + y, cell);
+                }
+            }
+        }
+    }
+}
+
+fn present_terminal(console: Res<Console>, mut terminal: ResMut<Terminal>) {
+    if !console.is_changed() {
+        return;
+    }
+    if let Err(e) = terminal.backend.present(&console) {
+        error!("Failed to draw to the terminal: {}", e);
+    }
+}
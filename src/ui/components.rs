@@ -4,6 +4,10 @@ use bevy::prelude::*;
 // HUD Components
 // ============================================================================
 
+/// The bottom panel holding the rest of the HUD
+#[derive(Component)]
+pub struct HudPanel;
+
 #[derive(Component)]
 pub struct HealthText;
 
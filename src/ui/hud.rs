@@ -2,13 +2,14 @@ use bevy::prelude::*;
 
 use crate::combat::CombatStats;
 use crate::components::{HungerClock, HungerState};
-use crate::gamelog::GameLog;
+use crate::gamelog::{GameLog, LogCategory, LogEntry};
 use crate::map::Map;
 use crate::player::Player;
+use crate::theme::Theme;
 use crate::resources::UiFont;
 use crate::RunState;
 
-use super::components::{DepthText, GameLogText, HealthBar, HealthText, HungerText};
+use super::components::{DepthText, GameLogText, HealthBar, HealthText, HudPanel, HungerText};
 
 pub struct HudPlugin;
 
@@ -17,6 +18,9 @@ impl Plugin for HudPlugin {
         // HUD updates only during actual gameplay
         let in_gameplay = not(in_state(RunState::MainMenu))
             .and(not(in_state(RunState::GameOver)))
+            .and(not(in_state(RunState::Victory)))
+            .and(not(in_state(RunState::HighScores)))
+            .and(not(in_state(RunState::Options)))
             .and(not(in_state(RunState::MapGeneration)))
             .and(not(in_state(RunState::MapBuilderSelect)));
 
@@ -48,6 +52,7 @@ fn setup_hud(mut commands: Commands, font: Res<UiFont>) {
                 ..default()
             },
             BackgroundColor(Color::srgb(0.15, 0.15, 0.15)),
+            HudPanel,
         ))
         .with_children(|parent| {
             // HP label and value
@@ -155,7 +160,7 @@ fn update_health_bar(
 
 fn update_depth(map: Res<Map>, mut depth_text_query: Query<&mut Text, With<DepthText>>) {
     if let Ok(mut text) = depth_text_query.get_single_mut() {
-        **text = format!("Depth: {}", map.depth);
+        **text = format!("Depth: {} - {}", map.depth, Theme::for_depth(map.depth).name());
     }
 }
 
@@ -186,20 +191,54 @@ fn update_hunger_display(
     }
 }
 
-fn update_game_log(game_log: Res<GameLog>, mut log_text_query: Query<&mut Text, With<GameLogText>>) {
-    if let Ok(mut text) = log_text_query.get_single_mut() {
-        // Show last 5 messages, newest at bottom
-        let messages: Vec<&str> = game_log
-            .entries
-            .iter()
-            .rev()
-            .take(5)
-            .map(|s| s.as_str())
-            .collect::<Vec<_>>()
-            .into_iter()
-            .rev()
-            .collect();
-
-        **text = messages.join("\n");
+fn update_game_log(
+    mut commands: Commands,
+    game_log: Res<GameLog>,
+    font: Res<UiFont>,
+    log_text_query: Query<Entity, With<GameLogText>>,
+) {
+    if !game_log.is_changed() {
+        return;
+    }
+    let Ok(log_text) = log_text_query.get_single() else {
+        return;
+    };
+
+    let text_font = TextFont {
+        font: font.0.clone(),
+        font_size: 14.0,
+        ..default()
+    };
+
+    // Show last 5 messages, newest at bottom; debug messages only appear in the full log
+    let messages: Vec<&LogEntry> = game_log
+        .entries
+        .iter()
+        .rev()
+        .filter(|entry| entry.category != LogCategory::Debug)
+        .take(5)
+        .collect();
+
+    commands
+        .entity(log_text)
+        .despawn_descendants()
+        .with_children(|parent| {
+            for (i, entry) in messages.into_iter().rev().enumerate() {
+                if i > 0 {
+                    parent.spawn((TextSpan::new("\n"), text_font.clone()));
+                }
+                spawn_log_entry_spans(parent, entry, &text_font);
+            }
+        });
+}
+
+/// Spawn one colored TextSpan per segment of a log entry
+pub fn spawn_log_entry_spans(parent: &mut ChildBuilder, entry: &LogEntry, font: &TextFont) {
+    for segment in &entry.segments {
+        parent.spawn((
+            TextSpan::new(segment.text.clone()),
+            font.clone(),
+            TextColor(segment.color()),
+        ));
     }
 }
@@ -2,7 +2,9 @@ use bevy::input::keyboard::KeyboardInput;
 use bevy::input::ButtonState;
 use bevy::prelude::*;
 
+use crate::morgue;
 use crate::resources::UiFont;
+use crate::run_stats::{self, RunStats};
 use crate::RunState;
 
 use crate::ui::menu::{ModalMenuBuilder, ModalMenuContainer, MenuStyle};
@@ -12,7 +14,15 @@ pub struct GameOverPlugin;
 
 impl Plugin for GameOverPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(RunState::GameOver), spawn_game_over_menu)
+        app.add_systems(
+            OnEnter(RunState::GameOver),
+            (
+                run_stats::finalize_run,
+                morgue::write_morgue_file,
+                spawn_game_over_menu,
+            )
+                .chain(),
+        )
             .add_systems(OnExit(RunState::GameOver), despawn_game_over_menu)
             .add_systems(
                 Update,
@@ -21,10 +31,19 @@ impl Plugin for GameOverPlugin {
     }
 }
 
-fn spawn_game_over_menu(mut commands: Commands, font: Res<UiFont>, menu_page: Res<MenuPage>) {
+fn spawn_game_over_menu(
+    mut commands: Commands,
+    font: Res<UiFont>,
+    menu_page: Res<MenuPage>,
+    run_stats: Res<RunStats>,
+) {
     ModalMenuBuilder::new("GAME OVER")
-        .empty_message("You have died.")
-        .footer("(Press any key to return to menu)")
+        .empty_message(format!(
+            "You have died.\n{}\n\n{}",
+            run_stats.cause_of_death.as_deref().unwrap_or_default(),
+            run_stats::summary(&run_stats)
+        ))
+        .footer("(H) High scores\n(Press any other key to return to menu)")
         .on_any_key(RunState::MainMenu)
         .style(MenuStyle {
             background_color: Color::srgba(0.1, 0.0, 0.0, 0.9),
@@ -53,7 +72,11 @@ fn handle_game_over_input(
 ) {
     for ev in evr_kbd.read() {
         if ev.state == ButtonState::Pressed {
-            next_state.set(RunState::MainMenu);
+            if ev.key_code == KeyCode::KeyH {
+                next_state.set(RunState::HighScores);
+            } else {
+                next_state.set(RunState::MainMenu);
+            }
             return;
         }
     }
@@ -0,0 +1,60 @@
+use bevy::prelude::*;
+
+use crate::highscores;
+use crate::resources::UiFont;
+use crate::RunState;
+
+use crate::ui::menu::{ModalMenuBuilder, ModalMenuContainer, MenuStyle};
+use crate::ui::resources::MenuPage;
+
+pub struct HighScoresPlugin;
+
+impl Plugin for HighScoresPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(RunState::HighScores), spawn_high_scores_menu)
+            .add_systems(OnExit(RunState::HighScores), despawn_high_scores_menu)
+            .add_systems(
+                Update,
+                handle_high_scores_input.run_if(in_state(RunState::HighScores)),
+            );
+    }
+}
+
+fn spawn_high_scores_menu(mut commands: Commands, font: Res<UiFont>, menu_page: Res<MenuPage>) {
+    let scores = highscores::load();
+
+    ModalMenuBuilder::new("HIGH SCORES")
+        .empty_message(highscores::table_text(&scores))
+        .footer("(Press any key to return to menu)")
+        .on_any_key(RunState::MainMenu)
+        .style(MenuStyle {
+            background_color: Color::srgba(0.0, 0.05, 0.1, 0.9),
+            border_color: Color::srgb(0.4, 0.7, 1.0),
+            text_color: Color::srgb(0.8, 0.9, 1.0),
+            title_color: None,
+            padding: 30.0,
+            font_size: 18.0,
+            background_image: None,
+        })
+        .spawn(&mut commands, &font, &menu_page);
+}
+
+fn despawn_high_scores_menu(
+    mut commands: Commands,
+    menu_query: Query<Entity, With<ModalMenuContainer>>,
+) {
+    for entity in &menu_query {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+// Uses ButtonInput rather than an EventReader so the key that opened this
+// screen from the game-over menu doesn't immediately close it again
+fn handle_high_scores_input(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut next_state: ResMut<NextState<RunState>>,
+) {
+    if keyboard.get_just_pressed().next().is_some() {
+        next_state.set(RunState::MainMenu);
+    }
+}
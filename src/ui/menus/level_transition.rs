@@ -0,0 +1,90 @@
+use bevy::prelude::*;
+
+use crate::map::Map;
+use crate::map_builders::LevelInfo;
+use crate::resources::UiFont;
+use crate::RunState;
+
+use crate::ui::menu::{MenuStyle, ModalMenuBuilder, ModalMenuContainer};
+use crate::ui::resources::{LevelTransitionTimer, MenuPage};
+
+pub struct LevelTransitionPlugin;
+
+impl Plugin for LevelTransitionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LevelInfo>()
+            .init_resource::<LevelTransitionTimer>()
+            .add_systems(OnEnter(RunState::LevelTransition), spawn_level_transition)
+            .add_systems(OnExit(RunState::LevelTransition), despawn_level_transition)
+            .add_systems(
+                Update,
+                handle_level_transition.run_if(in_state(RunState::LevelTransition)),
+            );
+    }
+}
+
+/// The interstitial shown on arriving at a level: its depth and name, the feeling the level
+/// gives off and any arrival scene
+fn spawn_level_transition(
+    mut commands: Commands,
+    font: Res<UiFont>,
+    menu_page: Res<MenuPage>,
+    map: Res<Map>,
+    info: Res<LevelInfo>,
+    mut timer: ResMut<LevelTransitionTimer>,
+) {
+    timer.0.reset();
+
+    let mut text = info.name.clone();
+    if let Some(feeling) = &info.feeling {
+        text.push_str(&format!("\n\n{}", feeling));
+    }
+    if let Some(arrival) = &info.arrival {
+        text.push_str(&format!("\n\n{}", arrival));
+    }
+    let title = if map.depth > 0 {
+        format!("DEPTH {}", map.depth)
+    } else {
+        "THE SURFACE".to_string()
+    };
+
+    ModalMenuBuilder::new(title)
+        .empty_message(text)
+        .footer("(Press any key)")
+        .style(MenuStyle {
+            background_color: Color::srgba(0.0, 0.0, 0.0, 0.95),
+            border_color: Color::srgb(0.5, 0.5, 0.6),
+            text_color: Color::srgb(0.85, 0.85, 0.9),
+            title_color: None,
+            padding: 30.0,
+            font_size: 20.0,
+            background_image: None,
+        })
+        .spawn(&mut commands, &font, &menu_page);
+}
+
+fn despawn_level_transition(
+    mut commands: Commands,
+    menu_query: Query<Entity, With<ModalMenuContainer>>,
+) {
+    for entity in &menu_query {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Any key moves on; without an arrival scene to read, so does waiting a moment. Keys are read
+/// as pressed this frame, so the one that led here (taking the stairs, starting a new game)
+/// doesn't skip the screen straight away.
+fn handle_level_transition(
+    time: Res<Time>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    info: Res<LevelInfo>,
+    mut timer: ResMut<LevelTransitionTimer>,
+    mut next_state: ResMut<NextState<RunState>>,
+) {
+    let key_pressed = keyboard.get_just_pressed().next().is_some();
+    timer.0.tick(time.delta());
+    if key_pressed || (info.arrival.is_none() && timer.0.finished()) {
+        next_state.set(RunState::PreRun);
+    }
+}
@@ -0,0 +1,186 @@
+use bevy::input::keyboard::KeyboardInput;
+use bevy::input::ButtonState;
+use bevy::prelude::*;
+
+use crate::gamelog::GameLog;
+use crate::resources::UiFont;
+use crate::RunState;
+
+use crate::ui::hud::spawn_log_entry_spans;
+use crate::ui::resources::LogScroll;
+
+/// Number of log lines shown at once
+const VISIBLE_LINES: usize = 30;
+
+/// Marker for the log viewer container
+#[derive(Component)]
+pub struct LogViewerContainer;
+
+/// Marker for the text element holding the visible log lines
+#[derive(Component)]
+pub struct LogViewerText;
+
+pub struct LogViewerPlugin;
+
+impl Plugin for LogViewerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LogScroll>()
+            .add_systems(OnEnter(RunState::ShowLog), spawn_log_viewer)
+            .add_systems(OnExit(RunState::ShowLog), despawn_log_viewer)
+            .add_systems(
+                Update,
+                (handle_log_viewer_input, update_log_viewer)
+                    .chain()
+                    .run_if(in_state(RunState::ShowLog)),
+            );
+    }
+}
+
+fn spawn_log_viewer(mut commands: Commands, font: Res<UiFont>, mut scroll: ResMut<LogScroll>) {
+    // Always open on the newest messages
+    scroll.0 = 0;
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            LogViewerContainer,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    Node {
+                        width: Val::Percent(90.0),
+                        padding: UiRect::all(Val::Px(20.0)),
+                        border: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    BorderColor(Color::WHITE),
+                    BackgroundColor(Color::srgba(0.1, 0.1, 0.1, 0.95)),
+                ))
+                .with_children(|inner| {
+                    inner.spawn((
+                        Text::new(""),
+                        TextFont {
+                            font: font.0.clone(),
+                            font_size: 14.0,
+                            ..default()
+                        },
+                        TextColor(Color::WHITE),
+                        LogViewerText,
+                    ));
+                });
+        });
+}
+
+fn despawn_log_viewer(
+    mut commands: Commands,
+    viewer_query: Query<Entity, With<LogViewerContainer>>,
+) {
+    for entity in &viewer_query {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn handle_log_viewer_input(
+    mut evr_kbd: EventReader<KeyboardInput>,
+    mut next_state: ResMut<NextState<RunState>>,
+    mut scroll: ResMut<LogScroll>,
+    game_log: Res<GameLog>,
+) {
+    let max_scroll = game_log.entries.len().saturating_sub(VISIBLE_LINES);
+
+    for ev in evr_kbd.read() {
+        if ev.state != ButtonState::Pressed {
+            continue;
+        }
+
+        // Scroll offset counts lines back from the newest message
+        let offset = match ev.key_code {
+            KeyCode::Escape => {
+                next_state.set(RunState::AwaitingInput);
+                return;
+            }
+            KeyCode::ArrowUp | KeyCode::KeyK => scroll.0 + 1,
+            KeyCode::ArrowDown | KeyCode::KeyJ => scroll.0.saturating_sub(1),
+            KeyCode::PageUp => scroll.0 + VISIBLE_LINES,
+            KeyCode::PageDown => scroll.0.saturating_sub(VISIBLE_LINES),
+            KeyCode::Home => max_scroll,
+            KeyCode::End => 0,
+            _ => continue,
+        };
+        let offset = offset.min(max_scroll);
+        if offset != scroll.0 {
+            scroll.0 = offset;
+        }
+    }
+}
+
+fn update_log_viewer(
+    mut commands: Commands,
+    game_log: Res<GameLog>,
+    scroll: Res<LogScroll>,
+    font: Res<UiFont>,
+    text_query: Query<Entity, With<LogViewerText>>,
+    added_query: Query<Entity, Added<LogViewerText>>,
+) {
+    let Ok(text_entity) = text_query.get_single() else {
+        return;
+    };
+    if !scroll.is_changed() && !game_log.is_changed() && added_query.is_empty() {
+        return;
+    }
+
+    let text_font = TextFont {
+        font: font.0.clone(),
+        font_size: 14.0,
+        ..default()
+    };
+    let dim = TextColor(Color::srgb(0.5, 0.5, 0.5));
+
+    let total = game_log.entries.len();
+    let end = total - scroll.0.min(total);
+    let start = end.saturating_sub(VISIBLE_LINES);
+
+    commands
+        .entity(text_entity)
+        .despawn_descendants()
+        .with_children(|parent| {
+            parent.spawn((
+                TextSpan::new(format!(
+                    "Message Log ({}-{} of {})\n\n",
+                    start + 1,
+                    end,
+                    total
+                )),
+                text_font.clone(),
+            ));
+
+            for entry in game_log.entries.range(start..end) {
+                parent.spawn((
+                    TextSpan::new(format!("{:>5} ", entry.turn)),
+                    text_font.clone(),
+                    dim,
+                ));
+                parent.spawn((
+                    TextSpan::new(format!("{:<7} ", entry.category.label())),
+                    text_font.clone(),
+                    TextColor(entry.category.color()),
+                ));
+                spawn_log_entry_spans(parent, entry, &text_font);
+                parent.spawn((TextSpan::new("\n"), text_font.clone()));
+            }
+
+            parent.spawn((
+                TextSpan::new("\n(Up/Down/PgUp/PgDn to scroll, Escape to close)"),
+                text_font.clone(),
+                dim,
+            ));
+        });
+}
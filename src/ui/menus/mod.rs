@@ -1,5 +1,15 @@
 pub mod game_over;
+pub mod high_scores;
 pub mod inventory;
+pub mod level_transition;
+pub mod log_viewer;
+pub mod options;
+pub mod victory;
 
 pub use game_over::GameOverPlugin;
+pub use high_scores::HighScoresPlugin;
 pub use inventory::InventoryPlugin;
+pub use level_transition::LevelTransitionPlugin;
+pub use log_viewer::LogViewerPlugin;
+pub use options::OptionsPlugin;
+pub use victory::VictoryPlugin;
@@ -0,0 +1,70 @@
+use bevy::prelude::*;
+
+use crate::resources::{MenuBackground, UiFont};
+use crate::tileset::GraphicsMode;
+use crate::RunState;
+
+use crate::ui::menu::{
+    build_menu_text, MenuAction, ModalMenuBuilder, ModalMenuContainer, ModalMenuText,
+};
+use crate::ui::resources::MenuPage;
+
+pub struct OptionsPlugin;
+
+impl Plugin for OptionsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(RunState::Options), spawn_options_menu)
+            .add_systems(OnExit(RunState::Options), despawn_options_menu)
+            .add_systems(
+                Update,
+                handle_options_input.run_if(in_state(RunState::Options)),
+            );
+    }
+}
+
+fn options_menu(mode: GraphicsMode, background: &MenuBackground) -> ModalMenuBuilder {
+    ModalMenuBuilder::new("OPTIONS")
+        .item(format!("Graphics: {}", mode.name()), 'a', MenuAction::None)
+        .footer("(Esc) Back")
+        .background_image(background.0.clone())
+        .background_color(Color::srgba(0.0, 0.0, 0.0, 0.7))
+        .padding(30.0)
+        .font_size(20.0)
+}
+
+fn spawn_options_menu(
+    mut commands: Commands,
+    font: Res<UiFont>,
+    menu_page: Res<MenuPage>,
+    background: Res<MenuBackground>,
+    mode: Res<GraphicsMode>,
+) {
+    options_menu(*mode, &background).spawn(&mut commands, &font, &menu_page);
+}
+
+fn despawn_options_menu(
+    mut commands: Commands,
+    menu_query: Query<Entity, With<ModalMenuContainer>>,
+) {
+    for entity in &menu_query {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+// Uses ButtonInput rather than an EventReader so the key that opened this
+// screen from the main menu isn't read again here
+fn handle_options_input(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut next_state: ResMut<NextState<RunState>>,
+    mut mode: ResMut<GraphicsMode>,
+    menu_page: Res<MenuPage>,
+    background: Res<MenuBackground>,
+    mut text: Single<&mut Text, With<ModalMenuText>>,
+) {
+    if keyboard.just_pressed(KeyCode::Escape) {
+        next_state.set(RunState::MainMenu);
+    } else if keyboard.just_pressed(KeyCode::KeyA) {
+        *mode = mode.toggled();
+        text.0 = build_menu_text(&options_menu(*mode, &background).build(), &menu_page);
+    }
+}
@@ -0,0 +1,82 @@
+use bevy::input::keyboard::KeyboardInput;
+use bevy::input::ButtonState;
+use bevy::prelude::*;
+
+use crate::morgue;
+use crate::resources::UiFont;
+use crate::run_stats::{self, RunStats};
+use crate::RunState;
+
+use crate::ui::menu::{ModalMenuBuilder, ModalMenuContainer, MenuStyle};
+use crate::ui::resources::MenuPage;
+
+pub struct VictoryPlugin;
+
+impl Plugin for VictoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(RunState::Victory),
+            (
+                run_stats::finalize_run,
+                morgue::write_morgue_file,
+                spawn_victory_menu,
+            )
+                .chain(),
+        )
+            .add_systems(OnExit(RunState::Victory), despawn_victory_menu)
+            .add_systems(
+                Update,
+                handle_victory_input.run_if(in_state(RunState::Victory)),
+            );
+    }
+}
+
+fn spawn_victory_menu(
+    mut commands: Commands,
+    font: Res<UiFont>,
+    menu_page: Res<MenuPage>,
+    run_stats: Res<RunStats>,
+) {
+    ModalMenuBuilder::new("VICTORY")
+        .empty_message(format!(
+            "You escaped the Abyss with its Heart!\n\n{}",
+            run_stats::summary(&run_stats)
+        ))
+        .footer("(H) High scores\n(Press any other key to return to menu)")
+        .on_any_key(RunState::MainMenu)
+        .style(MenuStyle {
+            background_color: Color::srgba(0.1, 0.08, 0.0, 0.9),
+            border_color: Color::srgb(1.0, 0.84, 0.0),
+            text_color: Color::srgb(1.0, 0.9, 0.4),
+            title_color: None,
+            padding: 30.0,
+            font_size: 20.0,
+            background_image: None,
+        })
+        .spawn(&mut commands, &font, &menu_page);
+}
+
+fn despawn_victory_menu(
+    mut commands: Commands,
+    menu_query: Query<Entity, With<ModalMenuContainer>>,
+) {
+    for entity in &menu_query {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn handle_victory_input(
+    mut evr_kbd: EventReader<KeyboardInput>,
+    mut next_state: ResMut<NextState<RunState>>,
+) {
+    for ev in evr_kbd.read() {
+        if ev.state == ButtonState::Pressed {
+            if ev.key_code == KeyCode::KeyH {
+                next_state.set(RunState::HighScores);
+            } else {
+                next_state.set(RunState::MainMenu);
+            }
+            return;
+        }
+    }
+}
@@ -4,5 +4,19 @@ use bevy::prelude::*;
 #[derive(Resource, Default)]
 pub struct MenuPage(pub usize);
 
+/// How many lines the message log viewer is scrolled back from the newest entry
+#[derive(Resource, Default)]
+pub struct LogScroll(pub usize);
+
 /// Number of items shown per page in menus
 pub const ITEMS_PER_PAGE: usize = 10;
+
+/// How long the interstitial between levels stays up when there is nothing to read on it
+#[derive(Resource)]
+pub struct LevelTransitionTimer(pub Timer);
+
+impl Default for LevelTransitionTimer {
+    fn default() -> Self {
+        Self(Timer::from_seconds(2.5, TimerMode::Once))
+    }
+}
@@ -5,7 +5,7 @@ use bevy::prelude::*;
 use crate::camera::Camera as GameCamera;
 use crate::components::{AreaOfEffect, Targeting, WantsToUseItem};
 use crate::distance::DistanceAlg;
-use crate::map::{Map, Position, TileType, GRID_PX, MAP_HEIGHT, MAP_WIDTH};
+use crate::map::{Map, Position, GRID_PX, MAP_HEIGHT, MAP_WIDTH};
 use crate::monsters::Monster;
 use crate::player::Player;
 use crate::resources::UiFont;
@@ -203,13 +203,13 @@ fn spawn_range_indicator(
     // Muted gray color for range border
     let border_color = Color::srgba(0.5, 0.5, 0.5, 0.5);
 
-    // Helper to check if a tile is a valid floor tile in range
+    // Helper to check if a tile is a valid target tile in range
     let is_valid_floor = |x: i32, y: i32| -> bool {
         if x < 0 || x >= MAP_WIDTH as i32 || y < 0 || y >= MAP_HEIGHT as i32 {
             return false;
         }
         let idx = map.xy_idx(x, y);
-        map.tiles[idx] == TileType::Floor
+        !map.tiles[idx].is_opaque()
     };
 
     // Check each tile and draw borders on the edge of range
@@ -225,8 +225,8 @@ fn spawn_range_indicator(
 
             let idx = map.xy_idx(tile_x, tile_y);
 
-            // Only draw on floor tiles that are visible
-            if map.tiles[idx] != TileType::Floor || !map.visible_tiles[idx] {
+            // Only draw on open tiles that are visible
+            if map.tiles[idx].is_opaque() || !map.visible_tiles[idx] {
                 continue;
             }
 
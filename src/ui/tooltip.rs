@@ -2,7 +2,7 @@ use bevy::prelude::*;
 
 use crate::camera::Camera as GameCamera;
 use crate::components::Name;
-use crate::map::{Map, Position, GRID_PX, MAP_HEIGHT, MAP_WIDTH};
+use crate::map::{Footprint, Map, Position, GRID_PX, MAP_HEIGHT, MAP_WIDTH};
 use crate::resources::UiFont;
 use crate::RunState;
 
@@ -14,6 +14,9 @@ impl Plugin for TooltipPlugin {
     fn build(&self, app: &mut App) {
         let in_gameplay = not(in_state(RunState::MainMenu))
             .and(not(in_state(RunState::GameOver)))
+            .and(not(in_state(RunState::Victory)))
+            .and(not(in_state(RunState::HighScores)))
+            .and(not(in_state(RunState::Options)))
             .and(not(in_state(RunState::MapGeneration)))
             .and(not(in_state(RunState::MapBuilderSelect)));
 
@@ -28,7 +31,7 @@ fn update_tooltip(
     game_camera: Res<GameCamera>,
     map: Res<Map>,
     font: Res<UiFont>,
-    entities_query: Query<(&Position, &Name)>,
+    entities_query: Query<(&Position, Option<&Footprint>, &Name)>,
     tooltip_query: Query<Entity, With<Tooltip>>,
     highlight_query: Query<Entity, With<CursorHighlight>>,
 ) {
@@ -96,8 +99,8 @@ fn update_tooltip(
 
     // Find entities at this position (using world coordinates)
     let mut tooltip_names: Vec<String> = Vec::new();
-    for (pos, name) in &entities_query {
-        if pos.x == map_x && pos.y == map_y {
+    for (pos, footprint, name) in &entities_query {
+        if footprint.copied().unwrap_or_default().distance_to(*pos, map_x, map_y) == 0 {
             tooltip_names.push(name.name.clone());
         }
     }
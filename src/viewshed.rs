@@ -1,35 +1,128 @@
+use std::collections::HashSet;
+
 use bevy::prelude::*;
 
-use crate::map::{Map, Position, TileType};
+use crate::map::{Footprint, Map, Position, TileType};
 
-#[derive(Component, Default, Debug)]
+#[derive(Component, Debug)]
 pub struct Viewshed {
     pub range: i32,
-    pub visible_tiles: Vec<(i32, i32)>,
+    pub visible_tiles: TileSet,
+    /// Set when the field of view needs recomputing; cleared by `update_viewshed`
     pub dirty: bool,
 }
 
+impl Default for Viewshed {
+    fn default() -> Self {
+        Self {
+            range: 8,
+            visible_tiles: TileSet::default(),
+            dirty: true,
+        }
+    }
+}
+
+/// A set of map tiles, one bit per tile. Points outside the map are never members.
+#[derive(Clone, Default, Debug)]
+pub struct TileSet {
+    width: i32,
+    height: i32,
+    bits: Vec<u64>,
+}
+
+impl TileSet {
+    pub fn new(width: i32, height: i32) -> Self {
+        let len = (width.max(0) * height.max(0)) as usize;
+        Self {
+            width,
+            height,
+            bits: vec![0; len.div_ceil(64)],
+        }
+    }
+
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        if x < 0 || x >= self.width || y < 0 || y >= self.height {
+            return None;
+        }
+        Some((y * self.width + x) as usize)
+    }
+
+    pub fn insert(&mut self, x: i32, y: i32) {
+        if let Some(idx) = self.index(x, y) {
+            self.bits[idx / 64] |= 1 << (idx % 64);
+        }
+    }
+
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        self.index(x, y)
+            .is_some_and(|idx| self.bits[idx / 64] & (1 << (idx % 64)) != 0)
+    }
+
+    /// Adds every member of another set of the same size
+    pub fn union_with(&mut self, other: &TileSet) {
+        for (word, other) in self.bits.iter_mut().zip(&other.bits) {
+            *word |= other;
+        }
+    }
+
+    /// Members in row-major order
+    pub fn iter(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        let width = self.width;
+        self.bits.iter().enumerate().flat_map(move |(word_idx, &word)| {
+            (0..64)
+                .filter(move |bit| word & (1 << bit) != 0)
+                .map(move |bit| {
+                    let idx = word_idx as i32 * 64 + bit;
+                    (idx % width, idx / width)
+                })
+        })
+    }
+}
+
 pub struct ViewshedPlugin;
 
 impl Plugin for ViewshedPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, update_viewshed);
+        // Door positions come from the map index, so it has to be current before we look
+        app.add_systems(
+            Update,
+            update_viewshed.after(crate::map_indexing::map_indexing_system),
+        );
     }
 }
 
-fn update_viewshed(map: Res<Map>, mut query: Query<(&Position, &mut Viewshed)>) {
-    let is_opaque = |x: i32, y: i32| {
-        let idx = map.xy_idx(x, y);
-        // Block visibility for walls and entities with BlocksVisibility
-        matches!(map.tiles[idx], TileType::Wall) || map.view_blocked.contains(&idx)
-    };
+/// The parts of the map that affect sight, as of the last viewshed update
+#[derive(Default)]
+struct SightSnapshot {
+    tiles: Vec<TileType>,
+    view_blocked: HashSet<usize>,
+}
+
+fn update_viewshed(
+    map: Res<Map>,
+    mut snapshot: Local<SightSnapshot>,
+    mut query: Query<(Ref<Position>, Option<&Footprint>, &mut Viewshed)>,
+) {
+    // A door opening or a new level changes what everyone can see
+    let map_changed = snapshot.tiles != map.tiles || snapshot.view_blocked != map.view_blocked;
+    if map_changed {
+        snapshot.tiles = map.tiles.clone();
+        snapshot.view_blocked = map.view_blocked.clone();
+    }
 
-    for (pos, mut viewshed) in &mut query {
-        viewshed.visible_tiles.clear();
-        viewshed.visible_tiles = calculate_fov(pos.x, pos.y, viewshed.range, is_opaque);
-        viewshed
-            .visible_tiles
-            .retain(|p| p.0 >= 0 && p.0 < map.width && p.1 >= 0 && p.1 < map.height);
+    let is_opaque = |x: i32, y: i32| map.is_opaque(x, y);
+
+    for (pos, footprint, mut viewshed) in &mut query {
+        if !(viewshed.dirty || pos.is_changed() || map_changed) {
+            continue;
+        }
+        // A large monster sees what any of the tiles it covers can see
+        let mut visible = TileSet::new(map.width, map.height);
+        for (x, y) in footprint.copied().unwrap_or_default().cells(*pos) {
+            visible.union_with(&calculate_fov(x, y, viewshed.range, map.width, map.height, is_opaque));
+        }
+        viewshed.visible_tiles = visible;
+        viewshed.dirty = false;
     }
 }
 
@@ -63,44 +156,197 @@ pub fn bresenham_line(x0: i32, y0: i32, x1: i32, y1: i32) -> Vec<(i32, i32)> {
     points
 }
 
-/// Calculates FOV around (cx, cy) within the given radius.
-/// `is_opaque` is a closure that tells us if a cell is blocking.
-fn calculate_fov(
+/// A slope as an exact fraction, so that symmetry doesn't depend on float rounding
+#[derive(Clone, Copy)]
+struct Slope {
+    num: i32,
+    den: i32,
+}
+
+impl Slope {
+    /// `depth * self`, rounded to the nearest column with ties going up
+    fn round_ties_up(self, depth: i32) -> i32 {
+        (2 * depth * self.num + self.den).div_euclid(2 * self.den)
+    }
+
+    /// `depth * self`, rounded to the nearest column with ties going down
+    fn round_ties_down(self, depth: i32) -> i32 {
+        -(self.den - 2 * depth * self.num).div_euclid(2 * self.den)
+    }
+}
+
+/// Calculates FOV around (cx, cy) within the given radius using symmetric shadowcasting:
+/// if A can see B then B can see A. Opaque cells bordering the visible area are included.
+/// `is_opaque` is only called for points inside the `width` x `height` map.
+pub fn calculate_fov(
     cx: i32,
     cy: i32,
     radius: i32,
+    width: i32,
+    height: i32,
     is_opaque: impl Fn(i32, i32) -> bool,
-) -> Vec<(i32, i32)> {
-    let mut visible_cells = Vec::new();
-
-    for x in (cx - radius)..=(cx + radius) {
-        for y in (cy - radius)..=(cy + radius) {
-            // Check circular boundary.
-            let dx = x - cx;
-            let dy = y - cy;
-            if dx * dx + dy * dy <= radius * radius {
-                // Get points on the line from center to this cell.
-                let line = bresenham_line(cx, cy, x, y);
-                let mut blocked = false;
-                for &(lx, ly) in &line {
-                    if is_opaque(lx, ly) {
-                        // Mark the opaque cell (lx, ly) itself as visible (omit it if we don't
-                        // want the opaque cell to be considered visible).
-                        visible_cells.push((lx, ly));
-                        blocked = true;
-                        break;
-                    } else {
-                        // If not blocked, keep it in FOV.
-                        visible_cells.push((lx, ly));
-                    }
-                }
-                // Once blocked, no further cells on that line are added.
-                if blocked {
-                    continue;
-                }
+) -> TileSet {
+    let mut fov = Fov {
+        cx,
+        cy,
+        radius,
+        width,
+        height,
+        is_opaque,
+        visible: TileSet::new(width, height),
+    };
+    fov.visible.insert(cx, cy);
+
+    // North, east, south and west quadrants, as (column, depth) -> (dx, dy) transforms
+    for transform in [[1, 0, 0, -1], [0, 1, 1, 0], [1, 0, 0, 1], [0, -1, 1, 0]] {
+        fov.scan(transform, 1, Slope { num: -1, den: 1 }, Slope { num: 1, den: 1 });
+    }
+
+    fov.visible
+}
+
+struct Fov<F> {
+    cx: i32,
+    cy: i32,
+    radius: i32,
+    width: i32,
+    height: i32,
+    is_opaque: F,
+    visible: TileSet,
+}
+
+impl<F: Fn(i32, i32) -> bool> Fov<F> {
+    fn point(&self, t: [i32; 4], col: i32, depth: i32) -> (i32, i32) {
+        (
+            self.cx + t[0] * col + t[1] * depth,
+            self.cy + t[2] * col + t[3] * depth,
+        )
+    }
+
+    /// Everything off the map blocks sight
+    fn blocks(&self, (x, y): (i32, i32)) -> bool {
+        x < 0 || x >= self.width || y < 0 || y >= self.height || (self.is_opaque)(x, y)
+    }
+
+    /// Scans one row of a quadrant between two slopes, recursing into the rows behind it
+    fn scan(&mut self, t: [i32; 4], depth: i32, mut start: Slope, end: Slope) {
+        if depth > self.radius {
+            return;
+        }
+
+        let mut prev_blocked = None;
+        for col in start.round_ties_up(depth)..=end.round_ties_down(depth) {
+            let point = self.point(t, col, depth);
+            let blocked = self.blocks(point);
+            let in_range = col * col + depth * depth <= self.radius * self.radius;
+
+            // Floors are only visible when their center is inside the unobstructed cone,
+            // which is what makes the result symmetric
+            let symmetric =
+                col * start.den >= depth * start.num && col * end.den <= depth * end.num;
+            if in_range && (blocked || symmetric) {
+                self.visible.insert(point.0, point.1);
+            }
+
+            let tile_slope = Slope {
+                num: 2 * col - 1,
+                den: 2 * depth,
+            };
+            match prev_blocked {
+                Some(true) if !blocked => start = tile_slope,
+                Some(false) if blocked => self.scan(t, depth + 1, start, tile_slope),
+                _ => {}
+            }
+            prev_blocked = Some(blocked);
+        }
+
+        if prev_blocked == Some(false) {
+            self.scan(t, depth + 1, start, end);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: i32 = 21;
+
+    /// A map with a wall border and walls wherever `wall` says so
+    fn grid(mut wall: impl FnMut(i32, i32) -> bool) -> Vec<bool> {
+        let mut walls = Vec::new();
+        for y in 0..SIZE {
+            for x in 0..SIZE {
+                walls.push(x == 0 || y == 0 || x == SIZE - 1 || y == SIZE - 1 || wall(x, y));
+            }
+        }
+        walls
+    }
+
+    fn fov(walls: &[bool], x: i32, y: i32, radius: i32) -> TileSet {
+        calculate_fov(x, y, radius, SIZE, SIZE, |x, y| walls[(y * SIZE + x) as usize])
+    }
+
+    #[test]
+    fn test_fov_is_symmetric() {
+        // Scattered pillars from a small LCG so the test is deterministic
+        let mut state = 12345u32;
+        let walls = grid(|_, _| {
+            state = state.wrapping_mul(1103515245).wrapping_add(12345);
+            (state >> 16).is_multiple_of(5)
+        });
+        let floors: Vec<(i32, i32)> = (0..SIZE * SIZE)
+            .filter(|&i| !walls[i as usize])
+            .map(|i| (i % SIZE, i / SIZE))
+            .collect();
+        let views: Vec<TileSet> = floors.iter().map(|&(x, y)| fov(&walls, x, y, 8)).collect();
+
+        for (a, view_a) in floors.iter().zip(&views) {
+            for (b, view_b) in floors.iter().zip(&views) {
+                assert_eq!(
+                    view_a.contains(b.0, b.1),
+                    view_b.contains(a.0, a.1),
+                    "{:?} and {:?} disagree",
+                    a,
+                    b
+                );
             }
         }
     }
 
-    visible_cells
+    #[test]
+    fn test_walls_are_visible_but_block_sight() {
+        // A room from (5, 5) to (15, 15) with a pillar at (10, 8)
+        let walls = grid(|x, y| {
+            let on_edge = (x == 4 || x == 16) && (4..=16).contains(&y)
+                || (y == 4 || y == 16) && (4..=16).contains(&x);
+            on_edge || (x, y) == (10, 8)
+        });
+        let view = fov(&walls, 10, 10, 10);
+
+        // Every wall of the room can be seen from inside, apart from the one behind the pillar
+        assert!(view.contains(4, 4));
+        assert!(view.contains(16, 16));
+        assert!(view.contains(4, 10));
+        assert!(view.contains(10, 8));
+        assert!(!view.contains(10, 7));
+        assert!(!view.contains(10, 4));
+
+        // Nothing outside the room is visible
+        assert!(!view.contains(2, 10));
+        assert!(view.iter().all(|(x, y)| (4..=16).contains(&x) && (4..=16).contains(&y)));
+    }
+
+    #[test]
+    fn test_fov_has_no_duplicates_and_respects_radius() {
+        let walls = grid(|_, _| false);
+        let view = fov(&walls, 10, 10, 3);
+
+        let points: Vec<(i32, i32)> = view.iter().collect();
+        let unique: HashSet<(i32, i32)> = points.iter().copied().collect();
+        assert_eq!(points.len(), unique.len());
+        assert!(points.iter().all(|(x, y)| (x - 10).pow(2) + (y - 10).pow(2) <= 9));
+        // A radius 3 disc on open ground has 29 tiles
+        assert_eq!(points.len(), 29);
+    }
 }
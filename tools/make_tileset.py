@@ -0,0 +1,378 @@
+#!/usr/bin/env python3
+"""Draws assets/images/tileset.png, the sprite sheet of the tiles graphics mode.
+
+The sheet is 16x16 sprites of 16x16 pixels laid out like code page 437, so src/tileset.rs can
+find a glyph's sprite by its CP437 code. Sprites are white on transparent and get tinted with
+the glyph's color in game. Only the glyphs the game draws have art; the rest stay empty and
+fall back to text. Run it from the repository root after changing a sprite.
+"""
+
+import struct
+import zlib
+
+TILE = 16
+COLUMNS = 16
+
+# 8x8 art, doubled to 16x16. Keys are CP437 codes.
+ART = {
+    # ☺ the player
+    1: """
+...##...
+...##...
+.######.
+#.####.#
+..####..
+..#..#..
+..#..#..
+.##..##.
+""",
+    # ♥ the Heart of the Abyss
+    3: """
+.##..##.
+########
+########
+########
+.######.
+..####..
+...##...
+........
+""",
+    # ♣ fungus
+    5: """
+..####..
+.######.
+########
+#.#..#.#
+...##...
+...##...
+..####..
+........
+""",
+    # ○ a lone pillar
+    9: """
+..####..
+.######.
+..####..
+..####..
+..####..
+..####..
+.######.
+########
+""",
+    # ▲ stalagmite
+    30: """
+...#....
+...##...
+..###...
+..####..
+.#####..
+.######.
+########
+........
+""",
+    # " grass
+    34: """
+........
+.#...#..
+.#.#.#..
+..#.#..#
+......#.
+..#..#..
+.#.#.#..
+........
+""",
+    # % rations
+    37: """
+........
+..###...
+.#####..
+########
+########
+.######.
+..####..
+........
+""",
+    # ' an open door
+    39: """
+#.......
+##......
+#.#.....
+#..#....
+#..#....
+#..#....
+#.##....
+##......
+""",
+    # ( shield
+    40: """
+########
+#.####.#
+#.####.#
+#.####.#
+.#.##.#.
+.#.##.#.
+..#..#..
+...##...
+""",
+    # ) scroll
+    41: """
+.######.
+#......#
+.#.###.#
+.#.....#
+.#.###.#
+.#.....#
+##.....#
+.######.
+""",
+    # + a closed door
+    43: """
+.######.
+#.#..#.#
+#.#..#.#
+#.#..###
+#.#..###
+#.#..#.#
+#.#..#.#
+########
+""",
+    # , gravel
+    44: """
+........
+.#...#..
+........
+...#...#
+#.......
+....#...
+.#....#.
+........
+""",
+    # . floor
+    46: """
+........
+........
+........
+...##...
+...##...
+........
+........
+........
+""",
+    # / sword
+    47: """
+.......#
+......##
+.....##.
+....##..
+#..##...
+.###....
+..#.....
+.#.#....
+""",
+    # : chasm
+    58: """
+#.#.#.#.
+.#.#.#.#
+#.#.#.#.
+.#.#.#.#
+#.#.#.#.
+.#.#.#.#
+#.#.#.#.
+.#.#.#.#
+""",
+    # < stairs up
+    60: """
+......##
+......##
+....####
+....##..
+..####..
+..##....
+####....
+##......
+""",
+    # = bridge
+    61: """
+########
+#..#..#.
+########
+........
+########
+#..#..#.
+########
+........
+""",
+    # > stairs down
+    62: """
+##......
+##......
+####....
+..##....
+..####..
+....##..
+....####
+......##
+""",
+    # ^ trap
+    94: """
+........
+...##...
+..#..#..
+.#.##.#.
+#.#..#.#
+.#....#.
+#.#..#.#
+........
+""",
+    # g goblin
+    103: """
+#......#
+.######.
+.#.##.#.
+.######.
+..#..#..
+.######.
+#..##..#
+..#..#..
+""",
+    # o orc
+    111: """
+..####..
+.#.##.#.
+.######.
+.#....#.
+########
+#.####.#
+..#..#..
+.##..##.
+""",
+    # ~ shallow water
+    126: """
+........
+.##.....
+#..#..#.
+....##..
+........
+.##.....
+#..#..#.
+....##..
+""",
+    # ¡ potion
+    173: """
+...##...
+...##...
+..####..
+.#....#.
+#.####.#
+#.####.#
+#.####.#
+.######.
+""",
+    # ≡ road
+    240: """
+########
+........
+########
+........
+########
+........
+########
+........
+""",
+    # ≈ deep water and lava
+    247: """
+.##...##
+#..#.#..
+....#...
+........
+.##...##
+#..#.#..
+....#...
+........
+""",
+    # · temple floor
+    250: """
+........
+........
+........
+........
+...#....
+........
+........
+........
+""",
+}
+
+# Box-drawing pieces, used as wall autotiles: the neighbors each one joins (N, S, W, E)
+WALLS = {
+    179: "NS",
+    180: "NSW",
+    191: "SW",
+    192: "NE",
+    193: "NWE",
+    194: "SWE",
+    195: "NSE",
+    196: "WE",
+    197: "NSWE",
+    217: "NW",
+    218: "SE",
+}
+
+
+def art_pixels(art):
+    rows = art.strip().splitlines()
+    return {(x * 2 + dx, y * 2 + dy)
+            for y, row in enumerate(rows)
+            for x, c in enumerate(row) if c == "#"
+            for dx in (0, 1) for dy in (0, 1)}
+
+
+def wall_pixels(joins):
+    """A block of masonry in the middle, reaching out to the walls it joins"""
+    lo, hi = 3, 13
+    xs = range(0 if "W" in joins else lo, TILE if "E" in joins else hi)
+    ys = range(0 if "N" in joins else lo, TILE if "S" in joins else hi)
+    pixels = set()
+    for y in range(TILE):
+        for x in range(TILE):
+            inside = (lo <= x < hi and y in ys) or (lo <= y < hi and x in xs)
+            # Mortar lines every four rows, staggered like brickwork
+            mortar = y % 4 == 3 or (x + (4 if y % 8 < 4 else 0)) % 8 == 7
+            if inside and not mortar:
+                pixels.add((x, y))
+    return pixels
+
+
+def sprites():
+    for code, art in ART.items():
+        yield code, art_pixels(art)
+    for code, joins in WALLS.items():
+        yield code, wall_pixels(joins)
+
+
+def png(width, height, rgba):
+    def chunk(kind, data):
+        body = kind + data
+        return struct.pack(">I", len(data)) + body + struct.pack(">I", zlib.crc32(body))
+
+    raw = b"".join(b"\x00" + bytes(rgba[y * width * 4:(y + 1) * width * 4]) for y in range(height))
+    return (b"\x89PNG\r\n\x1a\n"
+            + chunk(b"IHDR", struct.pack(">IIBBBBB", width, height, 8, 6, 0, 0, 0))
+            + chunk(b"IDAT", zlib.compress(raw, 9))
+            + chunk(b"IEND", b""))
+
+
+def main():
+    size = TILE * COLUMNS
+    rgba = bytearray(size * size * 4)
+    for code, pixels in sprites():
+        left, top = (code % COLUMNS) * TILE, (code // COLUMNS) * TILE
+        for x, y in pixels:
+            i = ((top + y) * size + left + x) * 4
+            rgba[i:i + 4] = b"\xff\xff\xff\xff"
+    with open("assets/images/tileset.png", "wb") as f:
+        f.write(png(size, size, rgba))
+
+
+if __name__ == "__main__":
+    main()
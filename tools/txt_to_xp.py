@@ -0,0 +1,41 @@
+#!/usr/bin/env python3
+"""Converts the art of a prefab to a REXPaint .xp image, to carry on drawing it in REXPaint.
+
+    tools/txt_to_xp.py art.txt assets/prefabs/art.xp
+
+The input is the plain art, one row per line. Walls become solid blocks; every other character
+is kept as its CP437 code. src/map_builders/prefab_file.rs reads the result back.
+"""
+
+import gzip
+import struct
+import sys
+
+WALL = 219
+WHITE = (255, 255, 255)
+BLACK = (0, 0, 0)
+
+
+def xp(rows):
+    width = max(len(row) for row in rows)
+    height = len(rows)
+    data = struct.pack("<iiii", -1, 1, width, height)
+    # Cells are stored column by column
+    for x in range(width):
+        for y in range(height):
+            ch = rows[y][x] if x < len(rows[y]) else " "
+            code = WALL if ch == "#" else ord(ch)
+            data += struct.pack("<i", code) + bytes(WHITE) + bytes(BLACK)
+    return gzip.compress(data)
+
+
+def main():
+    source, target = sys.argv[1:3]
+    with open(source) as f:
+        rows = f.read().rstrip("\n").split("\n")
+    with open(target, "wb") as f:
+        f.write(xp(rows))
+
+
+if __name__ == "__main__":
+    main()